@@ -0,0 +1,11 @@
+use cosmwasm_schema::write_api;
+use counting_factory::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+
+// generating the factory schema the same way as for the counting contract
+fn main() {
+  write_api! {
+    instantiate: InstantiateMsg,
+    execute: ExecMsg,
+    query: QueryMsg,
+  }
+}
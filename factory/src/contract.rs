@@ -0,0 +1,233 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult};
+use cw2::set_contract_version;
+
+use crate::msg::TreasuryConfig;
+use crate::state::{Config, Treasury, CONFIG, TREASURY};
+
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn instantiate(deps: DepsMut, info: MessageInfo, code_id: u64, treasury: Option<TreasuryConfig>) -> StdResult<Response> {
+  set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+  CONFIG.save(deps.storage, &Config {
+    owner: info.sender,
+    code_id,
+  })?;
+
+  if let Some(treasury) = treasury {
+    let treasury = treasury_from(deps.as_ref(), treasury)?;
+    TREASURY.save(deps.storage, &treasury)?;
+  }
+
+  Ok(Response::new())
+}
+
+fn treasury_from(deps: Deps, treasury: TreasuryConfig) -> StdResult<Treasury> {
+  Ok(Treasury {
+    address: deps.api.addr_validate(&treasury.addr)?,
+    donating_period: treasury.donating_period,
+    part: treasury.part,
+  })
+}
+
+pub mod query {
+  use cosmwasm_std::{Addr, Deps, Order, StdResult};
+  use cw_storage_plus::Bound;
+
+  use crate::msg::{ConfigResp, ContractResp, ContractsResp, TreasuryConfig};
+  use crate::state::{CONFIG, CONTRACTS, CONTRACTS_BY_CREATOR, TREASURY};
+
+  const DEFAULT_LIMIT: u32 = 10;
+  const MAX_LIMIT: u32 = 30;
+
+  pub fn config(deps: Deps) -> StdResult<ConfigResp> {
+    let config = CONFIG.load(deps.storage)?;
+    let treasury = TREASURY.may_load(deps.storage)?.map(|treasury| TreasuryConfig {
+      addr: treasury.address.into_string(),
+      donating_period: treasury.donating_period,
+      part: treasury.part,
+    });
+
+    Ok(ConfigResp {
+      owner: config.owner,
+      code_id: config.code_id,
+      treasury,
+    })
+  }
+
+  pub fn contracts(
+    deps: Deps,
+    creator: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+  ) -> StdResult<ContractsResp> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let addresses = match creator {
+      Some(creator) => {
+        let creator = deps.api.addr_validate(&creator)?;
+        CONTRACTS_BY_CREATOR
+          .prefix(&creator)
+          .keys(deps.storage, start, None, Order::Ascending)
+          .take(limit)
+          .collect::<StdResult<Vec<Addr>>>()?
+      } // the index is only read for its keys, the data is in CONTRACTS
+      None => CONTRACTS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?,
+    };
+
+    let contracts = addresses
+      .into_iter()
+      .map(|address| {
+        let data = CONTRACTS.load(deps.storage, &address)?;
+        Ok(ContractResp {
+          address,
+          creator: data.creator,
+          label: data.label,
+        })
+      })
+      .collect::<StdResult<_>>()?;
+
+    Ok(ContractsResp { contracts })
+  }
+}
+
+pub mod exec {
+  use cosmwasm_std::{to_binary, Coin, DepsMut, MessageInfo, Response, SubMsg, WasmMsg};
+  use counting_contract::msg::{InstantiateMsg as CountingInstantiateMsg, Parent};
+
+  use crate::error::ContractError;
+  use crate::msg::TreasuryConfig;
+  use crate::state::{ContractData, CONFIG, PENDING, TREASURY};
+
+  use super::reply::INSTANTIATE_REPLY_ID;
+
+  pub fn create_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    label: String,
+    counter: u64,
+    minimal_donation: Coin,
+    with_treasury: bool,
+  ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let parent = if with_treasury {
+      let treasury = TREASURY.may_load(deps.storage)?.ok_or(ContractError::NoTreasury)?;
+      Some(Parent {
+        addr: treasury.address.into_string(),
+        donating_period: treasury.donating_period,
+        part: treasury.part,
+        ibc: None,
+      })
+    } else {
+      None
+    };
+
+    let msg = WasmMsg::Instantiate {
+      admin: Some(info.sender.to_string()),
+      code_id: config.code_id,
+      msg: to_binary(&CountingInstantiateMsg::New {
+        counter,
+        minimal_donation,
+        parent,
+        rewards: None,
+        owner: Some(info.sender.to_string()),
+        limits: None,
+      })?,
+      funds: vec![],
+      label: label.clone(),
+    };
+    // the creator is both the wasm admin and the owner, the factory keeps no control over the contract
+
+    PENDING.save(deps.storage, &ContractData {
+      creator: info.sender.clone(),
+      label: label.clone(),
+    })?;
+    // the reply comes within the same transaction, so only one contract is pending at a time
+
+    let resp = Response::new()
+      .add_submessage(SubMsg::reply_on_success(msg, INSTANTIATE_REPLY_ID))
+      .add_attribute("action", "create_contract")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("label", label);
+
+    Ok(resp)
+  }
+
+  pub fn update_code_id(deps: DepsMut, info: MessageInfo, code_id: u64) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+      return Err(ContractError::Unauthorized {
+        owner: config.owner.to_string(),
+      });
+    }
+
+    config.code_id = code_id;
+    CONFIG.save(deps.storage, &config)?;
+
+    let resp = Response::new()
+      .add_attribute("action", "update_code_id")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("code_id", code_id.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn set_treasury(deps: DepsMut, info: MessageInfo, treasury: Option<TreasuryConfig>) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+      return Err(ContractError::Unauthorized {
+        owner: config.owner.to_string(),
+      });
+    }
+
+    match treasury {
+      Some(treasury) => {
+        let treasury = super::treasury_from(deps.as_ref(), treasury)?;
+        TREASURY.save(deps.storage, &treasury)?;
+      }
+      None => TREASURY.remove(deps.storage),
+    }
+    // contracts created before keep the parent they were instantiated with
+
+    let resp = Response::new()
+      .add_attribute("action", "set_treasury")
+      .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+}
+
+pub mod reply {
+  use cosmwasm_std::{DepsMut, Empty, Reply, Response, StdError};
+  use cw_utils::parse_reply_instantiate_data;
+
+  use crate::error::ContractError;
+  use crate::state::{CONTRACTS, CONTRACTS_BY_CREATOR, PENDING};
+
+  pub const INSTANTIATE_REPLY_ID: u64 = 1;
+
+  pub fn contract_instantiated(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    let data = PENDING.load(deps.storage)?;
+    PENDING.remove(deps.storage);
+
+    let resp = parse_reply_instantiate_data(reply).map_err(|err| StdError::generic_err(err.to_string()))?;
+    let address = deps.api.addr_validate(&resp.contract_address)?;
+
+    CONTRACTS_BY_CREATOR.save(deps.storage, (&data.creator, &address), &Empty {})?;
+    CONTRACTS.save(deps.storage, &address, &data)?;
+
+    let resp = Response::new()
+      .add_attribute("action", "contract_instantiated")
+      .add_attribute("contract", address.as_str())
+      .add_attribute("creator", data.creator.as_str());
+
+    Ok(resp)
+  }
+}
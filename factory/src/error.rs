@@ -0,0 +1,18 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+
+  #[error("{0}")]
+  Std(#[from] StdError),
+  // StdError reps the error type from the cosmwasm_std library
+
+  #[error("Unauthorized - only {owner} can call it")]
+  Unauthorized { owner: String },
+  // Unauthorized varient, returned when someone else than the owner changes the factory config
+
+  #[error("No treasury is configured for the new contracts")]
+  NoTreasury,
+  // NoTreasury varient, returned when a contract is created with the treasury as its parent, but the factory has none
+}
@@ -0,0 +1,63 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult};
+
+use error::ContractError;
+use msg::{ExecMsg, InstantiateMsg, QueryMsg};
+
+mod contract; // msg handlers, same layout as in the counting contract
+pub mod error;
+pub mod msg;
+#[cfg(any(test, feature = "tests"))]
+pub mod multitest;
+mod state;
+
+// the factory instantiates counting contracts from the stored code id and keeps track of who created which one
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+    contract::instantiate(deps, info, msg.code_id, msg.treasury)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecMsg) -> Result<Response, ContractError> {
+    use contract::exec;
+    use ExecMsg::*;
+
+    match msg {
+        CreateContract {
+            label,
+            counter,
+            minimal_donation,
+            with_treasury,
+        } => exec::create_contract(deps, info, label, counter, minimal_donation, with_treasury),
+        UpdateCodeId { code_id } => exec::update_code_id(deps, info, code_id),
+        SetTreasury { treasury } => exec::set_treasury(deps, info, treasury),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    use contract::query;
+    use QueryMsg::*;
+
+    match msg {
+        Config {} => to_binary(&query::config(deps)?),
+        Contracts {
+            creator,
+            start_after,
+            limit,
+        } => to_binary(&query::contracts(deps, creator, start_after, limit)?),
+    }
+}
+
+// the address of a new contract is only known once it is instantiated, so it is recorded in the reply
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    use contract::reply;
+
+    match msg.id {
+        reply::INSTANTIATE_REPLY_ID => reply::contract_instantiated(deps, msg),
+        id => Err(StdError::generic_err(format!("Unknown reply id: {}", id)).into()),
+    }
+}
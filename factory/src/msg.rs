@@ -0,0 +1,68 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Coin, Decimal};
+
+#[cw_serde]
+pub struct TreasuryConfig {
+    pub addr: String,
+    pub donating_period: u64,
+    pub part: Decimal,
+}
+// the Parent the contracts created with_treasury are wired to
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub code_id: u64,
+    #[serde(default)]
+    pub treasury: Option<TreasuryConfig>,
+}
+// the sender becomes the owner of the factory
+
+#[cw_serde]
+pub enum ExecMsg {
+    CreateContract {
+        label: String,
+        #[serde(default)]
+        counter: u64,
+        minimal_donation: Coin,
+        #[serde(default)]
+        with_treasury: bool,
+    }, // instantiates a counting contract owned by the sender, who is also its wasm admin
+    UpdateCodeId {
+        code_id: u64,
+    }, // owner only, contracts created before keep their code
+    SetTreasury {
+        treasury: Option<TreasuryConfig>,
+    }, // owner only, None stops wiring the new contracts to a treasury
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResp)]
+    Config {},
+    #[returns(ContractsResp)]
+    Contracts {
+        creator: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    }, // contracts created by the factory ordered by address, only the ones of creator if it is given
+}
+
+#[cw_serde]
+pub struct ConfigResp {
+    pub owner: Addr,
+    pub code_id: u64,
+    pub treasury: Option<TreasuryConfig>,
+}
+
+#[cw_serde]
+pub struct ContractResp {
+    pub address: Addr,
+    pub creator: Addr,
+    pub label: String,
+}
+
+#[cw_serde]
+pub struct ContractsResp {
+    pub contracts: Vec<ContractResp>,
+}
@@ -0,0 +1,5 @@
+// proxy and tests of the factory, the same way as the counting contract ones
+
+pub mod contract;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,137 @@
+// proxy of the factory, used by its multitests and by other contracts tests
+
+use cosmwasm_std::{Addr, Coin, StdResult};
+use counting_contract::multitest::contract::CountingContract;
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+use crate::error::ContractError;
+use crate::msg::{ConfigResp, ContractsResp, ExecMsg, InstantiateMsg, QueryMsg, TreasuryConfig};
+use crate::{execute, instantiate, query, reply};
+
+pub struct FactoryContract(Addr);
+
+impl FactoryContract {
+  pub fn addr(&self) -> &Addr {
+    &self.0
+  }
+
+  pub fn store_code(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+    app.store_code(Box::new(contract))
+  }
+  // the reply records the contracts the factory instantiates
+
+  #[track_caller]
+  pub fn instantiate(
+    app: &mut App,
+    code_id: u64,
+    sender: &Addr,
+    label: &str,
+    counting_code_id: u64,
+    treasury: impl Into<Option<TreasuryConfig>>,
+  ) -> StdResult<Self> {
+    app.instantiate_contract(
+      code_id,
+      sender.clone(),
+      &InstantiateMsg {
+        code_id: counting_code_id,
+        treasury: treasury.into(),
+      },
+      &[],
+      label,
+      None,
+    )
+    .map(FactoryContract)
+    .map_err(|err| err.downcast().unwrap())
+  }
+
+  #[track_caller]
+  pub fn create_contract(
+    &self,
+    app: &mut App,
+    sender: &Addr,
+    label: &str,
+    minimal_donation: Coin,
+    with_treasury: bool,
+  ) -> Result<CountingContract, ContractError> {
+    let resp = app
+      .execute_contract(
+        sender.clone(),
+        self.0.clone(),
+        &ExecMsg::CreateContract {
+          label: label.to_owned(),
+          counter: 0,
+          minimal_donation,
+          with_treasury,
+        },
+        &[],
+      )
+      .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+    let addr = resp
+      .events
+      .iter()
+      .find(|ev| ev.ty == "instantiate")
+      .and_then(|ev| ev.attributes.iter().find(|attr| attr.key == "_contract_addr"))
+      .map(|attr| Addr::unchecked(&attr.value))
+      .unwrap();
+    // multitest reports the address of every instantiated contract in an instantiate event
+
+    Ok(addr.into())
+  }
+
+  #[track_caller]
+  pub fn update_code_id(&self, app: &mut App, sender: &Addr, code_id: u64) -> Result<(), ContractError> {
+    app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::UpdateCodeId { code_id }, &[])
+      .map_err(|err| err.downcast().unwrap())
+      .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn set_treasury(
+    &self,
+    app: &mut App,
+    sender: &Addr,
+    treasury: impl Into<Option<TreasuryConfig>>,
+  ) -> Result<(), ContractError> {
+    app.execute_contract(
+      sender.clone(),
+      self.0.clone(),
+      &ExecMsg::SetTreasury {
+        treasury: treasury.into(),
+      },
+      &[],
+    )
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn query_config(&self, app: &App) -> StdResult<ConfigResp> {
+    app.wrap().query_wasm_smart(self.0.clone(), &QueryMsg::Config {})
+  }
+
+  #[track_caller]
+  pub fn query_contracts<'a>(
+    &self,
+    app: &App,
+    creator: impl Into<Option<&'a Addr>>,
+    start_after: impl Into<Option<String>>,
+    limit: impl Into<Option<u32>>,
+  ) -> StdResult<ContractsResp> {
+    app.wrap().query_wasm_smart(
+      self.0.clone(),
+      &QueryMsg::Contracts {
+        creator: creator.into().map(Addr::to_string),
+        start_after: start_after.into(),
+        limit: limit.into(),
+      },
+    )
+  }
+}
+
+impl From<FactoryContract> for Addr {
+  fn from(contract: FactoryContract) -> Self {
+    contract.0
+  }
+}
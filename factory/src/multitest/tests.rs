@@ -0,0 +1,216 @@
+use cosmwasm_std::{coin, Addr, Decimal};
+use counting_contract::msg::Parent;
+use counting_contract::multitest::contract::CountingContract;
+use cw_multi_test::App;
+
+use crate::error::ContractError;
+use crate::msg::{ConfigResp, ContractResp, TreasuryConfig};
+
+use super::contract::FactoryContract;
+
+const ATOM: &str = "atom";
+
+fn treasury(addr: &Addr) -> TreasuryConfig {
+    TreasuryConfig {
+        addr: addr.to_string(),
+        donating_period: 2,
+        part: Decimal::percent(10),
+    }
+}
+
+#[test]
+fn create_contracts() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let treasury_addr = Addr::unchecked("treasury");
+
+    let mut app = App::default();
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let code_id = FactoryContract::store_code(&mut app);
+    let factory = FactoryContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Factory",
+        counting_code_id,
+        treasury(&treasury_addr),
+    )
+    .unwrap();
+
+    let alice_contract = factory
+        .create_contract(&mut app, &alice, "Alice counter", coin(10, ATOM), false)
+        .unwrap();
+    let bob_contract = factory
+        .create_contract(&mut app, &bob, "Bob counter", coin(5, ATOM), true)
+        .unwrap();
+
+    let info = alice_contract.query_campaign(&app, "default").unwrap();
+    assert_eq!(info.owner, alice);
+    assert_eq!(info.minimal_donation, coin(10, ATOM));
+    assert_eq!(info.parent, None);
+    // the creator owns the contract, not the factory
+
+    let info = bob_contract.query_campaign(&app, "default").unwrap();
+    assert_eq!(info.owner, bob);
+    assert_eq!(
+        info.parent,
+        Some(Parent {
+            addr: treasury_addr.to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            ibc: None,
+        })
+    );
+    // created with_treasury, so the treasury is its parent
+
+    let admin = app
+        .wrap()
+        .query_wasm_contract_info(bob_contract.addr().clone())
+        .unwrap()
+        .admin;
+    assert_eq!(admin, Some(bob.to_string()));
+    // and it can be migrated by its creator
+
+    let resp = factory.query_contracts(&app, None, None, None).unwrap();
+    let mut expected = vec![
+        ContractResp {
+            address: alice_contract.addr().clone(),
+            creator: alice.clone(),
+            label: "Alice counter".to_owned(),
+        },
+        ContractResp {
+            address: bob_contract.addr().clone(),
+            creator: bob.clone(),
+            label: "Bob counter".to_owned(),
+        },
+    ];
+    expected.sort_by(|a, b| a.address.cmp(&b.address));
+    assert_eq!(resp.contracts, expected);
+
+    let resp = factory.query_contracts(&app, &bob, None, None).unwrap();
+    assert_eq!(
+        resp.contracts,
+        vec![ContractResp {
+            address: bob_contract.addr().clone(),
+            creator: bob,
+            label: "Bob counter".to_owned(),
+        }]
+    );
+}
+
+#[test]
+fn contracts_paginated() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::default();
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let code_id = FactoryContract::store_code(&mut app);
+    let factory = FactoryContract::instantiate(&mut app, code_id, &owner, "Factory", counting_code_id, None).unwrap();
+
+    for idx in 0..3 {
+        factory
+            .create_contract(&mut app, &alice, &format!("Alice counter {}", idx), coin(10, ATOM), false)
+            .unwrap();
+    }
+    factory
+        .create_contract(&mut app, &bob, "Bob counter", coin(10, ATOM), false)
+        .unwrap();
+
+    let first = factory.query_contracts(&app, &alice, None, 2).unwrap().contracts;
+    assert_eq!(first.len(), 2);
+
+    let last = first.last().unwrap().address.to_string();
+    let rest = factory.query_contracts(&app, &alice, last, 2).unwrap().contracts;
+    assert_eq!(rest.len(), 1);
+    assert!(rest.iter().chain(&first).all(|contract| contract.creator == alice));
+    // the bob contract is skipped by the creator filter
+
+    let all = factory.query_contracts(&app, None, None, None).unwrap().contracts;
+    assert_eq!(all.len(), 4);
+}
+
+#[test]
+fn treasury_required() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let treasury_addr = Addr::unchecked("treasury");
+
+    let mut app = App::default();
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let code_id = FactoryContract::store_code(&mut app);
+    let factory = FactoryContract::instantiate(&mut app, code_id, &owner, "Factory", counting_code_id, None).unwrap();
+
+    let err = factory
+        .create_contract(&mut app, &alice, "Alice counter", coin(10, ATOM), true)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoTreasury);
+
+    factory.set_treasury(&mut app, &owner, treasury(&treasury_addr)).unwrap();
+    let contract = factory
+        .create_contract(&mut app, &alice, "Alice counter", coin(10, ATOM), true)
+        .unwrap();
+    assert!(contract.query_campaign(&app, "default").unwrap().parent.is_some());
+
+    factory.set_treasury(&mut app, &owner, None).unwrap();
+    assert!(contract.query_campaign(&app, "default").unwrap().parent.is_some());
+    // removing the treasury doesn't change the contracts created before
+}
+
+#[test]
+fn config_owner_only() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let treasury_addr = Addr::unchecked("treasury");
+
+    let mut app = App::default();
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let code_id = FactoryContract::store_code(&mut app);
+    let factory = FactoryContract::instantiate(&mut app, code_id, &owner, "Factory", counting_code_id, None).unwrap();
+
+    let err = factory.update_code_id(&mut app, &alice, 42).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    let err = factory.set_treasury(&mut app, &alice, treasury(&treasury_addr)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    let new_code_id = CountingContract::store_code(&mut app);
+    factory.update_code_id(&mut app, &owner, new_code_id).unwrap();
+    factory.set_treasury(&mut app, &owner, treasury(&treasury_addr)).unwrap();
+
+    assert_eq!(
+        factory.query_config(&app).unwrap(),
+        ConfigResp {
+            owner: owner.clone(),
+            code_id: new_code_id,
+            treasury: Some(treasury(&treasury_addr)),
+        }
+    );
+
+    let contract = factory
+        .create_contract(&mut app, &alice, "Alice counter", coin(10, ATOM), false)
+        .unwrap();
+    let code_id = app
+        .wrap()
+        .query_wasm_contract_info(contract.addr().clone())
+        .unwrap()
+        .code_id;
+    assert_eq!(code_id, new_code_id);
+}
@@ -0,0 +1,31 @@
+use cosmwasm_std::{Addr, Decimal, Empty};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+  pub owner: Addr,
+  pub code_id: u64,
+} // code_id is the counting contract code every new contract is instantiated from
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Treasury {
+  pub address: Addr,
+  pub donating_period: u64,
+  pub part: Decimal,
+} // set as the parent of the new contracts created with_treasury
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContractData {
+  pub creator: Addr,
+  pub label: String,
+} // what the factory knows about a contract it created
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const TREASURY: Item<Treasury> = Item::new("treasury");
+pub const PENDING: Item<ContractData> = Item::new("pending");
+// the contract being instantiated, its address is only known in the reply
+
+pub const CONTRACTS: Map<&Addr, ContractData> = Map::new("contracts");
+pub const CONTRACTS_BY_CREATOR: Map<(&Addr, &Addr), Empty> = Map::new("contracts_by_creator");
+// index of CONTRACTS by (creator, contract), so the contracts of one creator are listed without going through all of them
@@ -0,0 +1,359 @@
+use cosmwasm_std::{Addr, Decimal256, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Uint128, Uint256};
+use counting_contract::msg::{ContributionsResp, QueryMsg as CountingQueryMsg};
+use cw2::set_contract_version;
+
+use crate::state::{Config, Match, Round, Tally, CONFIG, ROUND_COUNT};
+
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const CONTRIBUTIONS_PAGE: u32 = 30; // the largest page the counting contract returns
+const DEFAULT_FINALIZE_LIMIT: u32 = 300; // donors counted by a single Finalize, ten pages of contributions
+
+pub fn instantiate(deps: DepsMut, info: MessageInfo, denom: String) -> StdResult<Response> {
+  set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+  CONFIG.save(deps.storage, &Config {
+    owner: info.sender,
+    denom,
+  })?;
+  ROUND_COUNT.save(deps.storage, &0)?;
+
+  Ok(Response::new())
+}
+
+fn contributions(deps: Deps, project: &Addr, round: &Round, start_after: Option<String>, limit: u32) -> StdResult<ContributionsResp> {
+  deps.querier.query_wasm_smart(
+    project,
+    &CountingQueryMsg::Contributions {
+      from_height: Some(round.start_height),
+      to_height: Some(round.end_height),
+      start_after,
+      limit: Some(limit),
+    },
+  )
+}
+
+// adds the square roots of up to limit contributions to the tally, true once all the projects are counted
+fn count(deps: Deps, round: &Round, tally: &mut Tally, mut limit: u32) -> StdResult<bool> {
+  while let Some(project) = round.projects.get(tally.project) {
+    if limit == 0 {
+      return Ok(false);
+    }
+
+    let page_size = limit.min(CONTRIBUTIONS_PAGE);
+    let page = contributions(deps, project, round, tally.start_after.take(), page_size)?.contributions;
+
+    for contribution in &page {
+      let amount = Decimal256::from_atomics(contribution.amount, 0).map_err(|err| StdError::generic_err(err.to_string()))?;
+      tally.sums[tally.project] = tally.sums[tally.project].checked_add(amount.sqrt())?;
+    }
+    limit -= page.len() as u32;
+
+    if page.len() < page_size as usize {
+      tally.project += 1;
+    } else {
+      tally.start_after = page.last().map(|contribution| contribution.donor.clone());
+    }
+  } // going through all the donors of every project page by page, donors who gave nothing in the round included
+
+  Ok(true)
+}
+
+// shares of the pool proportional to the weights of the projects, the squares of the sums of square roots, rounded down
+fn matches(round: &Round, sums: &[Decimal256]) -> StdResult<Vec<Match>> {
+  let weights = sums
+    .iter()
+    .map(|sum| sum.checked_mul(*sum))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let total = weights
+    .iter()
+    .try_fold(Decimal256::zero(), |total, weight| total.checked_add(*weight))?;
+
+  round
+    .projects
+    .iter()
+    .zip(weights)
+    .map(|(project, weight)| {
+      let amount = if total.is_zero() {
+        Uint128::zero()
+      } else {
+        Uint256::from(round.pool)
+          .multiply_ratio(weight.atomics(), total.atomics())
+          .try_into()?
+      }; // a share is never more than the pool, so it fits back into Uint128
+
+      Ok(Match {
+        project: project.clone(),
+        amount,
+      })
+    })
+    .collect()
+}
+
+pub mod query {
+  use cosmwasm_std::{Deps, StdResult};
+
+  use crate::msg::{ConfigResp, MatchResp, MatchesResp, RoundResp};
+  use crate::state::{Tally, CONFIG, ROUNDS};
+
+  pub fn config(deps: Deps) -> StdResult<ConfigResp> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResp {
+      owner: config.owner,
+      denom: config.denom,
+    })
+  }
+
+  pub fn round(deps: Deps, id: u64) -> StdResult<RoundResp> {
+    let round = ROUNDS.load(deps.storage, id)?;
+
+    Ok(RoundResp {
+      id,
+      projects: round.projects,
+      start_height: round.start_height,
+      end_height: round.end_height,
+      pool: round.pool,
+      finalized: round.matches.is_some(),
+      cancelled: round.cancelled,
+    })
+  }
+
+  pub fn matching(deps: Deps, id: u64) -> StdResult<MatchesResp> {
+    let round = ROUNDS.load(deps.storage, id)?;
+
+    let matches = match round.matches.clone() {
+      Some(matches) => matches,
+      None => {
+        let mut tally = Tally::new(&round);
+        super::count(deps, &round, &mut tally, u32::MAX)?;
+        super::matches(&round, &tally.sums)?
+      }
+    };
+    // the paid out shares don't change anymore, even if the projects are migrated
+    // counting everything at once can run out of gas for big rounds, finalizing them goes in batches instead
+
+    let matches = matches
+      .into_iter()
+      .map(|m| MatchResp {
+        project: m.project,
+        amount: m.amount,
+      })
+      .collect();
+
+    Ok(MatchesResp { matches })
+  }
+}
+
+pub mod exec {
+  use cosmwasm_std::{
+    coins, Addr, BankMsg, DepsMut, Env, MessageInfo, OverflowError, OverflowOperation, Response, StdError, StdResult, Uint128,
+  };
+  use cw_utils::{may_pay, must_pay};
+
+  use super::DEFAULT_FINALIZE_LIMIT;
+  use crate::error::ContractError;
+  use crate::state::{Round, Tally, CONFIG, FUNDS, ROUNDS, ROUND_COUNT, TALLIES};
+
+  pub fn create_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    projects: Vec<String>,
+    duration: u64,
+  ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+      return Err(ContractError::Unauthorized {
+        owner: config.owner.to_string(),
+      });
+    }
+
+    if projects.is_empty() {
+      return Err(ContractError::NoProjects);
+    }
+
+    let pool = may_pay(&info, &config.denom)?;
+    // the pool can be empty at first and funded later
+
+    let end_height = env
+      .block
+      .height
+      .checked_add(duration)
+      .ok_or_else(|| StdError::overflow(OverflowError::new(OverflowOperation::Add, env.block.height, duration)))?;
+
+    let mut round = Round {
+      projects: vec![],
+      start_height: env.block.height,
+      end_height,
+      pool,
+      matches: None,
+      cancelled: false,
+    };
+
+    for project in projects {
+      let project = deps.api.addr_validate(&project)?;
+      if round.projects.contains(&project) {
+        return Err(ContractError::DuplicateProject {
+          project: project.into_string(),
+        });
+      } // a project listed twice would be matched twice
+
+      let denom = super::contributions(deps.as_ref(), &project, &round, None, 0)?.denom;
+      if denom != config.denom {
+        return Err(ContractError::InvalidProjectDenom {
+          project: project.into_string(),
+          denom,
+        });
+      } // only the minimal donation denom is recorded by the projects
+
+      round.projects.push(project);
+    }
+
+    let id = ROUND_COUNT.update(deps.storage, |count| -> Result<_, ContractError> { Ok(count + 1) })?;
+    ROUNDS.save(deps.storage, id, &round)?;
+    if !pool.is_zero() {
+      FUNDS.save(deps.storage, (id, &info.sender), &pool)?;
+    }
+
+    let resp = Response::new()
+      .add_attribute("action", "create_round")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("round", id.to_string())
+      .add_attribute("pool", pool.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn fund_round(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut round = ROUNDS.load(deps.storage, id)?;
+    round.ensure_open(id)?;
+
+    let amount = must_pay(&info, &config.denom)?;
+    round.pool = round.pool.checked_add(amount).map_err(StdError::from)?;
+    ROUNDS.save(deps.storage, id, &round)?;
+    FUNDS.update(deps.storage, (id, &info.sender), |funded| -> StdResult<_> {
+      Ok(funded.unwrap_or_default().checked_add(amount)?)
+    })?;
+    // kept for the refunds, in case the round is cancelled
+
+    let resp = Response::new()
+      .add_attribute("action", "fund_round")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("round", id.to_string())
+      .add_attribute("amount", amount.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn finalize(deps: DepsMut, env: Env, info: MessageInfo, id: u64, limit: Option<u32>) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut round = ROUNDS.load(deps.storage, id)?;
+    round.ensure_open(id)?;
+    if env.block.height < round.end_height {
+      return Err(ContractError::RoundNotEnded {
+        id,
+        end_height: round.end_height,
+      });
+    }
+
+    let mut tally = TALLIES.may_load(deps.storage, id)?.unwrap_or_else(|| Tally::new(&round));
+    let done = super::count(deps.as_ref(), &round, &mut tally, limit.unwrap_or(DEFAULT_FINALIZE_LIMIT))?;
+
+    let mut resp = Response::new()
+      .add_attribute("action", "finalize")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("round", id.to_string())
+      .add_attribute("done", done.to_string());
+
+    if !done {
+      TALLIES.save(deps.storage, id, &tally)?;
+      return Ok(resp);
+    } // the next Finalize carries on where this one stopped, the contributions are read at the fixed heights of the round
+    TALLIES.remove(deps.storage, id);
+
+    let matches = super::matches(&round, &tally.sums)?;
+
+    let mut paid = Uint128::zero();
+    for m in matches.iter().filter(|m| !m.amount.is_zero()) {
+      paid += m.amount;
+      resp = resp.add_message(BankMsg::Send {
+        to_address: m.project.to_string(),
+        amount: coins(m.amount.u128(), &config.denom),
+      });
+    } // the match goes to the project balance, its owner withdraws it with the donations
+
+    let rest = round.pool - paid;
+    if !rest.is_zero() {
+      resp = resp.add_message(BankMsg::Send {
+        to_address: config.owner.to_string(),
+        amount: coins(rest.u128(), &config.denom),
+      });
+    } // the rounding dust, or the whole pool if nobody donated, goes back to the owner
+
+    round.matches = Some(matches);
+    ROUNDS.save(deps.storage, id, &round)?;
+
+    Ok(resp)
+  }
+
+  pub fn cancel_round(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+      return Err(ContractError::Unauthorized {
+        owner: config.owner.to_string(),
+      });
+    }
+
+    let mut round = ROUNDS.load(deps.storage, id)?;
+    round.ensure_open(id)?;
+
+    round.cancelled = true;
+    ROUNDS.save(deps.storage, id, &round)?;
+    TALLIES.remove(deps.storage, id);
+
+    let resp = Response::new()
+      .add_attribute("action", "cancel_round")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("round", id.to_string());
+
+    Ok(resp.add_messages(refund_msg(deps, &config.denom, id, &info.sender)?))
+  }
+  // the owner gets its part of the pool back right away, the other funders take theirs with Refund
+
+  pub fn refund(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let round = ROUNDS.load(deps.storage, id)?;
+    if !round.cancelled {
+      return Err(ContractError::RoundNotCancelled { id });
+    }
+
+    let msg = refund_msg(deps, &config.denom, id, &info.sender)?.ok_or(ContractError::NothingToRefund { id })?;
+
+    let resp = Response::new()
+      .add_message(msg)
+      .add_attribute("action", "refund")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("round", id.to_string());
+
+    Ok(resp)
+  }
+
+  fn refund_msg(deps: DepsMut, denom: &str, id: u64, funder: &Addr) -> StdResult<Option<BankMsg>> {
+    let funded = FUNDS.may_load(deps.storage, (id, funder))?.unwrap_or_default();
+    FUNDS.remove(deps.storage, (id, funder));
+
+    if funded.is_zero() {
+      return Ok(None);
+    }
+
+    Ok(Some(BankMsg::Send {
+      to_address: funder.to_string(),
+      amount: coins(funded.u128(), denom),
+    }))
+  }
+  // sends back what the funder put in the pool of the round, once
+}
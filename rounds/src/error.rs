@@ -0,0 +1,51 @@
+use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+
+  #[error("{0}")]
+  Std(#[from] StdError),
+  // StdError reps the error type from the cosmwasm_std library
+
+  #[error("{0}")]
+  Payment(#[from] PaymentError),
+  // Payment varient, returned when the matching pool is not sent in the round denom only
+
+  #[error("Unauthorized - only {owner} can call it")]
+  Unauthorized { owner: String },
+  // Unauthorized varient, returned when someone else than the owner creates a round
+
+  #[error("A round needs at least one project")]
+  NoProjects,
+  // NoProjects varient, returned when a round is created without projects
+
+  #[error("Project {project} takes donations in {denom}, not in the round denom")]
+  InvalidProjectDenom { project: String, denom: String },
+  // InvalidProjectDenom varient, returned when the contributions of a project can't be matched with the pool
+
+  #[error("Project {project} is listed more than once")]
+  DuplicateProject { project: String },
+  // DuplicateProject varient, returned when a round is created with the same project twice
+
+  #[error("Round {id} ends at height {end_height}")]
+  RoundNotEnded { id: u64, end_height: u64 },
+  // RoundNotEnded varient, returned when a round is finalized before its end height
+
+  #[error("Round {id} is already finalized")]
+  RoundFinalized { id: u64 },
+  // RoundFinalized varient, returned when a round is funded or finalized after its matching pool was paid out
+
+  #[error("Round {id} is cancelled")]
+  RoundCancelled { id: u64 },
+  // RoundCancelled varient, returned when a cancelled round is funded, finalized or cancelled again
+
+  #[error("Round {id} is not cancelled")]
+  RoundNotCancelled { id: u64 },
+  // RoundNotCancelled varient, returned when asking for a refund from a round which can still pay out
+
+  #[error("Nothing to refund from round {id}")]
+  NothingToRefund { id: u64 },
+  // NothingToRefund varient, returned when the sender didn't fund the round or took the refund already
+}
@@ -0,0 +1,46 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+
+use error::ContractError;
+use msg::{ExecMsg, InstantiateMsg, QueryMsg};
+
+mod contract; // msg handlers, same layout as in the counting contract
+pub mod error;
+pub mod msg;
+#[cfg(any(test, feature = "tests"))]
+pub mod multitest;
+mod state;
+
+// the round manager matches the donations to counting contracts with a pool shared by quadratic funding
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+    contract::instantiate(deps, info, msg.denom)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecMsg) -> Result<Response, ContractError> {
+    use contract::exec;
+    use ExecMsg::*;
+
+    match msg {
+        CreateRound { projects, duration } => exec::create_round(deps, env, info, projects, duration),
+        FundRound { id } => exec::fund_round(deps, info, id),
+        Finalize { id, limit } => exec::finalize(deps, env, info, id, limit),
+        CancelRound { id } => exec::cancel_round(deps, info, id),
+        Refund { id } => exec::refund(deps, info, id),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    use contract::query;
+    use QueryMsg::*;
+
+    match msg {
+        Config {} => to_binary(&query::config(deps)?),
+        Round { id } => to_binary(&query::round(deps, id)?),
+        Matching { id } => to_binary(&query::matching(deps, id)?),
+    }
+}
@@ -0,0 +1,71 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint128};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub denom: String,
+}
+// the sender becomes the owner, the only one creating rounds
+
+#[cw_serde]
+pub enum ExecMsg {
+    CreateRound {
+        projects: Vec<String>,
+        duration: u64,
+    }, // projects are counting contracts, the round starts now and lasts duration blocks, funds sent are the matching pool
+    FundRound {
+        id: u64,
+    }, // anyone can add to the matching pool until the round is finalized
+    Finalize {
+        id: u64,
+        limit: Option<u32>,
+    }, // anyone can pay out the matching pool once the round ended, limit is how many donors are counted by this call
+    // a round with more donors is finalized by calling it again until the pool is paid out
+    CancelRound {
+        id: u64,
+    }, // owner only, until the round is finalized, the owner gets its part of the pool back
+    Refund {
+        id: u64,
+    }, // anyone who funded a cancelled round, sends back what they put in the pool
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResp)]
+    Config {},
+    #[returns(RoundResp)]
+    Round { id: u64 },
+    #[returns(MatchesResp)]
+    Matching { id: u64 },
+    // how the pool would be shared if the round was finalized now, the paid out shares once it is
+}
+
+#[cw_serde]
+pub struct ConfigResp {
+    pub owner: Addr,
+    pub denom: String,
+}
+
+#[cw_serde]
+pub struct MatchResp {
+    pub project: Addr,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct MatchesResp {
+    pub matches: Vec<MatchResp>,
+}
+// matches are in the order of the projects of the round
+
+#[cw_serde]
+pub struct RoundResp {
+    pub id: u64,
+    pub projects: Vec<Addr>,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub pool: Uint128,
+    pub finalized: bool,
+    pub cancelled: bool,
+}
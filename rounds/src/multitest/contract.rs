@@ -0,0 +1,141 @@
+// proxy of the round manager, used by its multitests and by other contracts tests
+
+use cosmwasm_std::{Addr, Coin, StdResult};
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+use crate::error::ContractError;
+use crate::msg::{ConfigResp, ExecMsg, InstantiateMsg, MatchesResp, QueryMsg, RoundResp};
+use crate::{execute, instantiate, query};
+
+pub struct RoundsContract(Addr);
+
+impl RoundsContract {
+  pub fn addr(&self) -> &Addr {
+    &self.0
+  }
+
+  pub fn store_code(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate, query);
+    app.store_code(Box::new(contract))
+  }
+
+  #[track_caller]
+  pub fn instantiate(app: &mut App, code_id: u64, sender: &Addr, label: &str, denom: &str) -> StdResult<Self> {
+    app.instantiate_contract(
+      code_id,
+      sender.clone(),
+      &InstantiateMsg {
+        denom: denom.to_owned(),
+      },
+      &[],
+      label,
+      None,
+    )
+    .map(RoundsContract)
+    .map_err(|err| err.downcast().unwrap())
+  }
+
+  #[track_caller]
+  pub fn create_round(
+    &self,
+    app: &mut App,
+    sender: &Addr,
+    projects: &[&Addr],
+    duration: u64,
+    funds: &[Coin],
+  ) -> Result<u64, ContractError> {
+    app.execute_contract(
+      sender.clone(),
+      self.0.clone(),
+      &ExecMsg::CreateRound {
+        projects: projects.iter().map(|project| project.to_string()).collect(),
+        duration,
+      },
+      funds,
+    )
+    .map_err(|err| err.downcast().unwrap())
+    .map(|resp| {
+      resp
+        .events
+        .iter()
+        .flat_map(|ev| &ev.attributes)
+        .find(|attr| attr.key == "round")
+        .map(|attr| attr.value.parse().unwrap())
+        .unwrap()
+    })
+  }
+  // returns the id of the new round
+
+  #[track_caller]
+  pub fn fund_round(&self, app: &mut App, sender: &Addr, id: u64, funds: &[Coin]) -> Result<(), ContractError> {
+    app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::FundRound { id }, funds)
+      .map_err(|err| err.downcast().unwrap())
+      .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn finalize(&self, app: &mut App, sender: &Addr, id: u64) -> Result<(), ContractError> {
+    app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Finalize { id, limit: None }, &[])
+      .map_err(|err| err.downcast().unwrap())
+      .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn finalize_batch(&self, app: &mut App, sender: &Addr, id: u64, limit: u32) -> Result<bool, ContractError> {
+    app.execute_contract(
+      sender.clone(),
+      self.0.clone(),
+      &ExecMsg::Finalize {
+        id,
+        limit: Some(limit),
+      },
+      &[],
+    )
+    .map_err(|err| err.downcast().unwrap())
+    .map(|resp| {
+      resp
+        .events
+        .iter()
+        .flat_map(|ev| &ev.attributes)
+        .find(|attr| attr.key == "done")
+        .map(|attr| attr.value.parse().unwrap())
+        .unwrap()
+    })
+  }
+  // returns whether the round got paid out, or more batches are needed
+
+  #[track_caller]
+  pub fn cancel_round(&self, app: &mut App, sender: &Addr, id: u64) -> Result<(), ContractError> {
+    app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::CancelRound { id }, &[])
+      .map_err(|err| err.downcast().unwrap())
+      .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn refund(&self, app: &mut App, sender: &Addr, id: u64) -> Result<(), ContractError> {
+    app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Refund { id }, &[])
+      .map_err(|err| err.downcast().unwrap())
+      .map(|_| ())
+  }
+
+  #[track_caller]
+  pub fn query_config(&self, app: &App) -> StdResult<ConfigResp> {
+    app.wrap().query_wasm_smart(self.0.clone(), &QueryMsg::Config {})
+  }
+
+  #[track_caller]
+  pub fn query_round(&self, app: &App, id: u64) -> StdResult<RoundResp> {
+    app.wrap().query_wasm_smart(self.0.clone(), &QueryMsg::Round { id })
+  }
+
+  #[track_caller]
+  pub fn query_matching(&self, app: &App, id: u64) -> StdResult<MatchesResp> {
+    app.wrap().query_wasm_smart(self.0.clone(), &QueryMsg::Matching { id })
+  }
+}
+
+impl From<RoundsContract> for Addr {
+  fn from(contract: RoundsContract) -> Self {
+    contract.0
+  }
+}
@@ -0,0 +1,354 @@
+use cosmwasm_std::{coin, coins, Addr, StdError, Uint128};
+use counting_contract::multitest::contract::CountingContract;
+use cw_multi_test::{next_block, App};
+
+use crate::error::ContractError;
+use crate::msg::MatchResp;
+
+use super::contract::RoundsContract;
+
+const ATOM: &str = "atom";
+const OSMO: &str = "osmo";
+
+fn project(app: &mut App, code_id: u64, owner: &Addr, label: &str, denom: &str) -> CountingContract {
+    CountingContract::instantiate(app, code_id, owner, label, None, None, coin(10, denom), None).unwrap()
+}
+
+#[test]
+fn matching_shares() {
+    let owner = Addr::unchecked("owner");
+    let donors: Vec<_> = (0..6).map(|idx| Addr::unchecked(format!("donor{}", idx))).collect();
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(1000, ATOM))
+            .unwrap();
+        for donor in &donors {
+            router
+                .bank
+                .init_balance(storage, donor, coins(1000, ATOM))
+                .unwrap();
+        }
+    });
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let first = project(&mut app, counting_code_id, &owner, "First project", ATOM);
+    let second = project(&mut app, counting_code_id, &owner, "Second project", ATOM);
+
+    let code_id = RoundsContract::store_code(&mut app);
+    let rounds = RoundsContract::instantiate(&mut app, code_id, &owner, "Rounds", ATOM).unwrap();
+
+    second
+        .donate(&mut app, &donors[5], &coins(900, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+    // given before the round, so not matched
+
+    let start = app.block_info().height;
+    let id = rounds
+        .create_round(&mut app, &owner, &[first.addr(), second.addr()], 10, &coins(1000, ATOM))
+        .unwrap();
+
+    for donor in &donors[..4] {
+        first
+            .donate(&mut app, donor, &coins(100, ATOM))
+            .unwrap();
+    }
+    second
+        .donate(&mut app, &donors[4], &coins(400, ATOM))
+        .unwrap();
+    // the first project is weighted (4 * sqrt(100))^2 = 1600, the second sqrt(400)^2 = 400
+
+    let expected = vec![
+        MatchResp {
+            project: first.addr().clone(),
+            amount: Uint128::new(800),
+        },
+        MatchResp {
+            project: second.addr().clone(),
+            amount: Uint128::new(200),
+        },
+    ];
+    assert_eq!(rounds.query_matching(&app, id).unwrap().matches, expected);
+
+    let err = rounds.finalize(&mut app, &owner, id).unwrap_err();
+    assert_eq!(err, ContractError::RoundNotEnded { id, end_height: start + 10 });
+
+    app.update_block(|block| block.height += 10);
+
+    second
+        .donate(&mut app, &donors[4], &coins(400, ATOM))
+        .unwrap();
+    // given after the round, so not matched either
+
+    rounds.finalize(&mut app, &donors[0], id).unwrap();
+
+    assert_eq!(rounds.query_matching(&app, id).unwrap().matches, expected);
+    assert!(rounds.query_round(&app, id).unwrap().finalized);
+    assert_eq!(
+        app.wrap().query_balance(first.addr(), ATOM).unwrap(),
+        coin(1200, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_balance(second.addr(), ATOM).unwrap(),
+        coin(1900, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_balance(rounds.addr(), ATOM).unwrap(),
+        coin(0, ATOM)
+    );
+
+    let err = rounds.finalize(&mut app, &owner, id).unwrap_err();
+    assert_eq!(err, ContractError::RoundFinalized { id });
+}
+
+#[test]
+fn rounding_dust_returned() {
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+    let sponsor = Addr::unchecked("sponsor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(600, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &sponsor, coins(400, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &donor, coins(300, ATOM))
+            .unwrap();
+    });
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let projects: Vec<_> = (0..3)
+        .map(|idx| project(&mut app, counting_code_id, &owner, &format!("Project {}", idx), ATOM))
+        .collect();
+    let addrs: Vec<_> = projects.iter().map(CountingContract::addr).collect();
+
+    let code_id = RoundsContract::store_code(&mut app);
+    let rounds = RoundsContract::instantiate(&mut app, code_id, &owner, "Rounds", ATOM).unwrap();
+
+    let id = rounds
+        .create_round(&mut app, &owner, &addrs, 5, &coins(600, ATOM))
+        .unwrap();
+    rounds
+        .fund_round(&mut app, &sponsor, id, &coins(400, ATOM))
+        .unwrap();
+    assert_eq!(rounds.query_round(&app, id).unwrap().pool, Uint128::new(1000));
+
+    for project in &projects {
+        project
+            .donate(&mut app, &donor, &coins(100, ATOM))
+            .unwrap();
+    }
+    app.update_block(|block| block.height += 5);
+
+    rounds.finalize(&mut app, &owner, id).unwrap();
+
+    for project in &projects {
+        assert_eq!(
+            app.wrap().query_balance(project.addr(), ATOM).unwrap(),
+            coin(433, ATOM)
+        );
+    } // equal weights, 1000 / 3 rounded down on top of the donation
+    assert_eq!(
+        app.wrap().query_balance(&owner, ATOM).unwrap(),
+        coin(1, ATOM)
+    );
+    // the dust goes back to the owner
+}
+
+#[test]
+fn round_validation() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+
+    let mut app = App::default();
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let atom_project = project(&mut app, counting_code_id, &owner, "Atom project", ATOM);
+    let osmo_project = project(&mut app, counting_code_id, &owner, "Osmo project", OSMO);
+
+    let code_id = RoundsContract::store_code(&mut app);
+    let rounds = RoundsContract::instantiate(&mut app, code_id, &owner, "Rounds", ATOM).unwrap();
+
+    let err = rounds
+        .create_round(&mut app, &alice, &[atom_project.addr()], 5, &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    let err = rounds.create_round(&mut app, &owner, &[], 5, &[]).unwrap_err();
+    assert_eq!(err, ContractError::NoProjects);
+
+    let err = rounds
+        .create_round(&mut app, &owner, &[atom_project.addr(), osmo_project.addr()], 5, &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidProjectDenom {
+            project: osmo_project.addr().to_string(),
+            denom: OSMO.to_owned(),
+        }
+    );
+
+    let err = rounds
+        .create_round(&mut app, &owner, &[atom_project.addr(), atom_project.addr()], 5, &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DuplicateProject {
+            project: atom_project.addr().to_string(),
+        }
+    );
+
+    let err = rounds
+        .create_round(&mut app, &owner, &[atom_project.addr()], u64::MAX, &[])
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    // the end height can't wrap around
+
+    let id = rounds
+        .create_round(&mut app, &owner, &[atom_project.addr()], 5, &[])
+        .unwrap();
+    app.update_block(|block| block.height += 5);
+
+    rounds.finalize(&mut app, &alice, id).unwrap();
+    assert_eq!(
+        rounds.query_matching(&app, id).unwrap().matches,
+        vec![MatchResp {
+            project: atom_project.addr().clone(),
+            amount: Uint128::zero(),
+        }]
+    );
+    // nothing to share without a pool
+
+    let err = rounds.fund_round(&mut app, &alice, id, &[]).unwrap_err();
+    assert_eq!(err, ContractError::RoundFinalized { id });
+}
+
+#[test]
+fn finalize_in_batches() {
+    let owner = Addr::unchecked("owner");
+    let donors: Vec<_> = (0..5).map(|idx| Addr::unchecked(format!("donor{}", idx))).collect();
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(1000, ATOM))
+            .unwrap();
+        for donor in &donors {
+            router
+                .bank
+                .init_balance(storage, donor, coins(1000, ATOM))
+                .unwrap();
+        }
+    });
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let first = project(&mut app, counting_code_id, &owner, "First project", ATOM);
+    let second = project(&mut app, counting_code_id, &owner, "Second project", ATOM);
+
+    let code_id = RoundsContract::store_code(&mut app);
+    let rounds = RoundsContract::instantiate(&mut app, code_id, &owner, "Rounds", ATOM).unwrap();
+
+    let id = rounds
+        .create_round(&mut app, &owner, &[first.addr(), second.addr()], 10, &coins(1000, ATOM))
+        .unwrap();
+
+    for donor in &donors[..4] {
+        first
+            .donate(&mut app, donor, &coins(100, ATOM))
+            .unwrap();
+    }
+    second
+        .donate(&mut app, &donors[4], &coins(400, ATOM))
+        .unwrap();
+    app.update_block(|block| block.height += 10);
+
+    let expected = rounds.query_matching(&app, id).unwrap().matches;
+
+    assert!(!rounds.finalize_batch(&mut app, &owner, id, 2).unwrap());
+    assert!(!rounds.finalize_batch(&mut app, &owner, id, 2).unwrap());
+    assert!(!rounds.query_round(&app, id).unwrap().finalized);
+    // the first project has more donors than the first batch counts
+
+    let err = rounds.cancel_round(&mut app, &donors[0], id).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    assert!(rounds.finalize_batch(&mut app, &owner, id, 2).unwrap());
+    assert_eq!(rounds.query_matching(&app, id).unwrap().matches, expected);
+    assert_eq!(
+        app.wrap().query_balance(first.addr(), ATOM).unwrap(),
+        coin(1200, ATOM)
+    );
+    // the same shares as finalizing at once
+}
+
+#[test]
+fn cancelled_round_refunded() {
+    let owner = Addr::unchecked("owner");
+    let sponsor = Addr::unchecked("sponsor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(600, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &sponsor, coins(400, ATOM))
+            .unwrap();
+    });
+
+    let counting_code_id = CountingContract::store_code(&mut app);
+    let atom_project = project(&mut app, counting_code_id, &owner, "Atom project", ATOM);
+
+    let code_id = RoundsContract::store_code(&mut app);
+    let rounds = RoundsContract::instantiate(&mut app, code_id, &owner, "Rounds", ATOM).unwrap();
+
+    let id = rounds
+        .create_round(&mut app, &owner, &[atom_project.addr()], 5, &coins(600, ATOM))
+        .unwrap();
+    rounds
+        .fund_round(&mut app, &sponsor, id, &coins(400, ATOM))
+        .unwrap();
+
+    let err = rounds.refund(&mut app, &sponsor, id).unwrap_err();
+    assert_eq!(err, ContractError::RoundNotCancelled { id });
+
+    rounds.cancel_round(&mut app, &owner, id).unwrap();
+    assert!(rounds.query_round(&app, id).unwrap().cancelled);
+    assert_eq!(
+        app.wrap().query_balance(&owner, ATOM).unwrap(),
+        coin(600, ATOM)
+    );
+
+    rounds.refund(&mut app, &sponsor, id).unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&sponsor, ATOM).unwrap(),
+        coin(400, ATOM)
+    );
+    // every funder gets back what they put in the pool
+
+    let err = rounds.refund(&mut app, &sponsor, id).unwrap_err();
+    assert_eq!(err, ContractError::NothingToRefund { id });
+
+    app.update_block(|block| block.height += 5);
+    let err = rounds.finalize(&mut app, &owner, id).unwrap_err();
+    assert_eq!(err, ContractError::RoundCancelled { id });
+}
@@ -0,0 +1,64 @@
+use cosmwasm_std::{Addr, Decimal256, Uint128};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+  pub owner: Addr,
+  pub denom: String,
+} // the matching pools and the matched contributions are all in denom
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+  pub project: Addr,
+  pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+  pub projects: Vec<Addr>,
+  pub start_height: u64,
+  pub end_height: u64,
+  pub pool: Uint128,
+  pub matches: Option<Vec<Match>>,
+  #[serde(default)]
+  pub cancelled: bool,
+} // contributions to the projects from the beginning of start_height to the beginning of end_height are matched
+// matches is set once the round is finalized and the pool is paid out, a cancelled round refunds the pool instead
+
+impl Round {
+  pub fn ensure_open(&self, id: u64) -> Result<(), ContractError> {
+    if self.matches.is_some() {
+      return Err(ContractError::RoundFinalized { id });
+    }
+    if self.cancelled {
+      return Err(ContractError::RoundCancelled { id });
+    }
+    Ok(())
+  }
+} // a round can be funded, finalized or cancelled until it is either finalized or cancelled
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Tally {
+  pub project: usize,
+  pub start_after: Option<String>,
+  pub sums: Vec<Decimal256>,
+} // progress of a round finalized in batches, project is the index of the one being counted and sums the square roots counted so far
+
+impl Tally {
+  pub fn new(round: &Round) -> Self {
+    Tally {
+      project: 0,
+      start_after: None,
+      sums: vec![Decimal256::zero(); round.projects.len()],
+    }
+  }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const ROUND_COUNT: Item<u64> = Item::new("round_count"); // id of the last round
+pub const ROUNDS: Map<u64, Round> = Map::new("rounds");
+pub const TALLIES: Map<u64, Tally> = Map::new("tallies"); // only there while a round is being finalized
+pub const FUNDS: Map<(u64, &Addr), Uint128> = Map::new("funds"); // round id and funder are the key, what the funder put in the pool
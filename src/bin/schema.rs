@@ -1,5 +1,5 @@
 use cosmwasm_schema::write_api;
-use counting_contract::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+use counting_contract::msg::{ExecMsg, InstantiateMsg, QueryMsg, SudoMsg};
 
 // generating schema using the write_api! macro and the types we defined in msg.rs
 fn main() {
@@ -8,6 +8,7 @@ fn main() {
     instantiate: InstantiateMsg, 
     execute: ExecMsg,
     query: QueryMsg,
+    sudo: SudoMsg,
   }
   // takes InstantiateMsg, ExecMsg, and QueryMsg as an argument for message type 
   // generates a schema file for it
@@ -3,26 +3,47 @@
 
 // Modules (mod) also allow us to declare items that are only available within a given scope, rather than making them available to the entire crate.
 
-use cosmwasm_std::{Addr, Coin, DepsMut, MessageInfo, Response, StdResult};
+use cosmwasm_std::{to_vec, Addr, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128};
 use cw2::{get_contract_version, set_contract_version};
-use cw_storage_plus::Item;
-use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::Parent;
-use crate::state::{State, STATE, PARENT_DONATION, ParentDonation};
+use crate::msg::{CampaignInfo, DonationLimits, LedgerPage, MigrateMsg, Parent, RewardConfig, Snapshot, SnapshotData, Update};
+use crate::state::{
+    Allowance, Campaign, Governance, Hook, ReceiverPolicy, Receipts, Referrer, Restoring, State, ACCESS_MODE, ALLOWANCES, ALLOWLIST, BLOCKLIST, CAMPAIGNS, COUNTER,
+    DONATIONS, GOVERNANCE, HOOKS, LIMITS, MERKLE_ROOT, PARENT_DONATION, PAUSED, ParentDonation, RECEIPTS, RECEIPTS_MINTED, RECEIVERS, RECEIVER_POLICY, REFERRAL_ACCRUED,
+    REFERRALS, REFERRED_BY, REFERRERS, RESTORING, REWARD_RATES, REWARD_TOKEN, STAKING, STATE, TOTAL_DONATED, VOUCHER_SIGNER,
+};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION"); 
 // notice the use of env! macro, which allows us to access environment variables at compile time, the use of const is important here to prevent mutable access (changes)
 
-pub fn instantiate(deps: DepsMut, info: MessageInfo, counter: u64, minimal_donation: Coin, parent: Option<Parent>) -> StdResult<Response> {
+#[allow(clippy::too_many_arguments)] // every field of InstantiateMsg::New is passed on its own
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    counter: u64,
+    minimal_donation: Coin,
+    parent: Option<Parent>,
+    rewards: Option<RewardConfig>,
+    owner: Option<String>,
+    limits: Option<DonationLimits>,
+) -> Result<Response, ContractError> {
   set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+  COUNTER.save(deps.storage, &counter, env.block.height)?;
+  // the counter is kept with its history, so it is saved with the height of the block changing it
+
+  let owner = match owner {
+    Some(owner) => deps.api.addr_validate(&owner)?,
+    None => info.sender,
+  }; // a factory spawning the contract passes the creator, so it is not the factory owning it
+
   STATE.save(deps.storage, &State {
-    counter,
     minimal_donation,
-    owner: info.sender,
+    owner,
     donating_parent: parent.as_ref().map(|p| p.donating_period), 
         }, // added donating_parent field which is a countdown till the donation period ends
         // if parent is Some, we map it to the donating_period field, if not, we map it to None (coming from Option<Parent>)
@@ -30,342 +51,3758 @@ pub fn instantiate(deps: DepsMut, info: MessageInfo, counter: u64, minimal_donat
 
     // if Some
     if let Some(parent) = parent {
-        PARENT_DONATION.save(deps.storage, 
-          &ParentDonation {
-            address: deps.api.addr_validate(&parent.addr)?,
-            donating_parent_period: parent.donating_period,
-            part: parent.part,
-        })?;
+        let parent = parent_donation(deps.as_ref(), parent)?;
+        PARENT_DONATION.save(deps.storage, &parent)?;
     } // if parent is Some, we save it to the storage using the PARENT_DONATION key and the referred ParentDonation struct
-    // we validate the address using the addr_validate function from the api module, which returns a StdResult<Addr> type, then we also save the donating_parent_ period and part fields from the Parent struct
+    // parent_donation validates the address using the addr_validate function from the api module, unless the parent is on another chain
+
+    if let Some(rewards) = rewards {
+        REWARD_TOKEN.save(deps.storage, &deps.api.addr_validate(&rewards.token)?)?;
+        for rate in rewards.rates {
+            REWARD_RATES.save(deps.storage, &rate.denom, &rate.rate)?;
+        }
+    } // donors are rewarded only when the contract is instantiated with a token minting the rewards
+
+    if let Some(limits) = limits {
+        exec::validate_limits(&limits)?;
+        LIMITS.save(deps.storage, &limits)?;
+    }
 
     Ok(Response::new())
 }
 // instantiate contract, set contract version, save state
 
+fn parent_donation(deps: Deps, parent: Parent) -> Result<ParentDonation, ContractError> {
+    if parent.donating_period == 0 {
+        return Err(ContractError::InvalidParent {
+            reason: "donating period has to be at least one donation".to_owned(),
+        });
+    } // the countdown would never reach zero again
 
+    if parent.part > Decimal::one() {
+        return Err(ContractError::InvalidParent {
+            reason: "part is greater than the whole donation".to_owned(),
+        });
+    }
 
-pub fn migrate(mut deps: DepsMut, parent: Option<Parent>) -> Result<Response, ContractError> {
-    let contract_version = get_contract_version(deps.storage)?;
+    let address = match parent.ibc {
+        Some(_) => Addr::unchecked(parent.addr),
+        None => deps.api.addr_validate(&parent.addr)?,
+    }; // an address on the other chain can't be validated here, a wrong one makes the transfers fail and get recorded for a retry
+
+    Ok(ParentDonation {
+        address,
+        donating_parent_period: parent.donating_period,
+        part: parent.part,
+        ibc: parent.ibc,
+    })
+}
+
+fn campaign(deps: Deps, info: CampaignInfo) -> Result<Campaign, ContractError> {
+    Ok(Campaign {
+        owner: deps.api.addr_validate(&info.owner)?,
+        minimal_donation: info.minimal_donation,
+        counter: info.counter,
+        donating_parent: info.donating_parent,
+        parent: info.parent.map(|parent| parent_donation(deps, parent)).transpose()?,
+        balance: info.balance,
+    })
+}
+
+const SNAPSHOT_FORMAT: u32 = 15;
+// bumped whenever the layout of the Snapshot message changes
+
+fn snapshot_checksum(format: u32, contract: &str, version: &str, data: &SnapshotData) -> StdResult<Binary> {
+    let bytes = to_vec(&(format, contract, version, data))?;
+    Ok(Binary::from(Sha256::digest(&bytes).as_slice()))
+}
+// sha256 over everything in the snapshot except the checksum itself
+
+pub fn restore(deps: DepsMut, env: Env, snapshot: Snapshot) -> Result<Response, ContractError> {
+    if snapshot.format != SNAPSHOT_FORMAT {
+        return Err(ContractError::InvalidSnapshot {
+            reason: format!("unsupported snapshot format {}", snapshot.format),
+        });
+    }
 
-    if contract_version.contract != CONTRACT_NAME {
+    if snapshot.contract != CONTRACT_NAME {
         return Err(ContractError::InvalidContract {
-            contract: contract_version.contract,
+            contract: snapshot.contract,
         });
     }
 
- let resp = match contract_version.version.as_str() {
-        "0.1.0" => migrate_0_1_0(deps.branch(), parent).map_err(ContractError::from)?,
-        // branch function we call on deps, utility that allows having another copy of a mutable state in a single contract, like a clone() function
-        "0.2.0" => migrate_0_2_0(deps.branch(), parent).map_err(ContractError::from)?,
-        CONTRACT_VERSION => return Ok(Response::default()),
-        version => {
-            return Err(ContractError::InvalidContractVersion {
-                version: version.into(),
-            })
-        } // finally, we update the contract version to the new value so the contract version would be valid on future migrations
-    };
-    
-    // loaded version of the contract, then we validate if the contract name didn't change
-    // if it did, we return an error, if not we match the version with the migrate functions
-    // works if CONTRACT_VERSION is a constant, if its a variable, it would be treated as a generic match, and the last branch would be unreachable
+    if migration::parse_version(&snapshot.version)? > migration::parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::DowngradeNotAllowed {
+            from: snapshot.version,
+            to: CONTRACT_VERSION.into(),
+        });
+    } // same rule as for migrations, older code can't restore what newer code exported
+
+    let checksum = snapshot_checksum(snapshot.format, &snapshot.contract, &snapshot.version, &snapshot.data)?;
+    if checksum != snapshot.checksum {
+        return Err(ContractError::InvalidSnapshot {
+            reason: "checksum mismatch".to_owned(),
+        });
+    } // the snapshot was changed or damaged on its way
+
+    let data = snapshot.data;
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    Ok(resp)
-  } // migrate contract state to a different version (0.1.0 or 0.2.0)
-  // It would also be a great idea to keep all of this in its own migration module. Then create another migrate function, performing the version dispatch:
+    COUNTER.save(deps.storage, &data.counter, env.block.height)?;
+    // only the current value is exported, the history of the restored contract starts here
 
-  pub fn migrate_0_1_0(deps: DepsMut, parent: Option<Parent>) -> StdResult<Response> {
-    const COUNTER: Item<u64> = Item::new("counter");
-    const MINIMAL_DONATION: Item<Coin> = Item::new("minimal_donation");
-    const OWNER: Item<Addr> = Item::new("owner");
-    let counter = COUNTER.load(deps.storage)?;
-    let minimal_donation = MINIMAL_DONATION.load(deps.storage)?;
-    let owner = OWNER.load(deps.storage)?;
     STATE.save(
         deps.storage,
         &State {
-            counter,
-            minimal_donation,
-            owner,
-            donating_parent: parent.as_ref().map(|p| p.donating_period),
-            // parent has a function as ref, which maps the parent to the donating_period field, if parent is None, it maps it to None
-            // as_ref() is a function that returns an Option<&T> type, which is a reference to the value inside the Option, this comes from the std::option::Option module
+            minimal_donation: data.minimal_donation,
+            owner: deps.api.addr_validate(&data.owner)?,
+            donating_parent: data.donating_parent,
         },
     )?;
 
-    if let Some(parent) = parent {
-        PARENT_DONATION.save(
+    if let Some(parent) = data.parent {
+        let parent = parent_donation(deps.as_ref(), parent)?;
+        PARENT_DONATION.save(deps.storage, &parent)?;
+    }
+
+    for allowance in data.allowances {
+        exec::validate_allowance(&allowance.limit, allowance.period)?;
+        ALLOWANCES.save(
             deps.storage,
-            &ParentDonation {
-                address: deps.api.addr_validate(&parent.addr)?,
-                donating_parent_period: parent.donating_period,
-                part: parent.part,
+            &deps.api.addr_validate(&allowance.spender)?,
+            &Allowance {
+                limit: allowance.limit,
+                period: allowance.period,
+                spent: allowance.spent,
+                period_start: allowance.period_start,
             },
         )?;
     }
-    // if parent is Some, we save it to the storage using the PARENT_DONATION key and the referred ParentDonation struct
-    // we save the donating_parent_period and part fields from the Parent struct
-    Ok(Response::new())
-} // migrate from 0.1.0 to 0.2.0
-      
-// similar to instantiation, but we are loading the data from the old state and saving it to the new state
-
-pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> StdResult<Response> {
-    #[derive(Serialize, Deserialize)]
-    struct OldState {
-        counter: u64,
-        minimal_donation: Coin,
-        owner: Addr,
-    }
-
-const OLD_STATE: Item<OldState> = Item::new("state");
-// assigns the Item struct to the constant OLD_STATE, which is a state accessor from src/state.rs
 
- let OldState {
-        counter,
-        minimal_donation,
-        owner,
-    } = OLD_STATE.load(deps.storage)?;
-    // let is used instead of const because it is used to bind a value to a variable, and we are binding the value of the load function to the variables
-    // whereas const is used to bind a value to a constant, and we are binding the value of the Item struct to the constant
-    // assigns OldState to the variables and sets them to the values of the load function, which uses deps.storage as an argument
-    // we use OldState to load the old state from the storage, then we save it to the new state
-    // we have to use the same names as the fields in the State struct, so we can use the shorthand syntax, which is the same as writing: 
-    // counter: counter, minimal_donation: minimal_donation, owner: owner
-STATE.save(
+    RECEIVER_POLICY.save(
         deps.storage,
-        &State {
-            counter,
-            minimal_donation,
-            owner,
-            donating_parent: parent.as_ref().map(|p| p.donating_period),
+        &ReceiverPolicy {
+            enforce: data.receiver_policy.enforce,
+            delay: data.receiver_policy.delay,
+            pending: data.receiver_policy.pending,
         },
     )?;
-    // saving the old state to the new state by using the save function from STATE, which is a state accessor from src/state.rs 
-    // takes deps.storage as an argument from the migrate function (DepsMut, and the new State struct, which is a struct from src/state.rs
 
-      if let Some(parent) = parent {
-        PARENT_DONATION.save(
-          deps.storage,
-          &ParentDonation {
-            address: deps.api.addr_validate(&parent.addr)?,
-            donating_parent_period: parent.donating_period,
-            part: parent.part,
-          },
+    for receiver in data.receivers {
+        RECEIVERS.save(
+            deps.storage,
+            &deps.api.addr_validate(&receiver.receiver)?,
+            &receiver.allowed_from,
         )?;
-      } // if parent is Some, we save it to the storage using the PARENT_DONATION key and the referred ParentDonation struct
-
-    Ok(Response::new())
-    // returns a new Response struct, which is a struct from cosmwasm_std
-} // migrate from 0.2.0 to 0.3.0
+    }
 
-// query is a read operation
-pub mod query {
-  use cosmwasm_std::{Deps, StdResult};
-  
-  use crate::msg::ValueResp;
-  use crate::state::STATE;
+    for hook in data.hooks {
+        HOOKS.save(
+            deps.storage,
+            &deps.api.addr_validate(&hook.hook)?,
+            &Hook {
+                on_reset: hook.on_reset,
+                on_withdraw: hook.on_withdraw,
+            },
+        )?;
+    }
 
-    pub fn value(deps: Deps) -> StdResult<ValueResp> { // Deps to access contract/bc storage
-      let value = STATE.load(deps.storage)?.counter; 
-      // error handling, so we use ?
-      // load function, loading from the state, taking state accessor as an arguement
-      Ok(ValueResp { value })
+    if let Some(receipts) = data.receipts {
+        RECEIPTS.save(
+            deps.storage,
+            &Receipts {
+                cw721: deps.api.addr_validate(&receipts.cw721)?,
+                token_uri: receipts.token_uri,
+                threshold: receipts.threshold,
+            },
+        )?;
     }
-  } 
-  // load vs save: load is a read-only operation, save is a write operation.
-  // we have a function called value, which takes a Deps argument and returns a result of type StdResult<ValueResp>
-  // it is a read-only operation (pub mod query), so we use the load function on the STATE constant to load the value from the contract's storage.
+    RECEIPTS_MINTED.save(deps.storage, &data.receipts_minted)?;
+    // kept, so the restored contract doesn't mint token ids which already exist on the nft contract
 
-  // execute is a write operation
-  pub mod exec {
-    use cosmwasm_std::{BankMsg, Coin, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, WasmMsg, to_binary};
+    if let Some(token) = data.reward_token {
+        REWARD_TOKEN.save(deps.storage, &deps.api.addr_validate(&token)?)?;
+    }
 
-    use crate::error::ContractError;
-    use crate::msg::ExecMsg;
-    use crate::state::{STATE, PARENT_DONATION};
+    for rate in data.reward_rates {
+        REWARD_RATES.save(deps.storage, &rate.denom, &rate.rate)?;
+    }
 
-     pub fn donate(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
-      // we use DepsMut to access contract/bc storage, and we use it to write to the storage
-      // we use Env to access the blockchain context, and we use it to get the current block height
-      // we use MessageInfo to access the message sender, and we use it to get the sender's address
+    TOTAL_DONATED.save(deps.storage, &Uint128::zero(), env.block.height)?;
+    // goes up as the donations are imported, like the counter the ledger history starts with the restore
 
-      let mut state = STATE.load(deps.storage)?;
-      let mut resp = Response::new();
-      // setting the state to the value of the load function, which takes deps.storage as an argument
-      // setting the response to a new Response struct, which is a struct from cosmwasm_std that is used to build a response
+    if let Some(governance) = data.governance {
+        GOVERNANCE.save(
+            deps.storage,
+            &Governance {
+                quorum: governance.quorum,
+                threshold: governance.threshold,
+                voting_period: governance.voting_period,
+                restrict_withdrawals: governance.restrict_withdrawals,
+            },
+        )?;
+    }
 
-      // |coin| is a closure, a function that can be passed as an argument to another function
-      if state.minimal_donation.amount.is_zero() || info.funds.iter().any(|coin| {
-        coin.denom == state.minimal_donation.denom && coin.amount >= state.minimal_donation.amount
-      }) {
-        state.counter += 1;
-      // if the minimal donation amount is zero, or if the funds in the message info are greater than or equal to the minimal donation amount, then we increment the counter by 1
+    if let Some(staking) = data.staking {
+        let staking = exec::validate_staking(deps.as_ref(), staking)?;
+        STAKING.save(deps.storage, &staking)?;
+    } // the validator has to exist on the chain the contract is restored on
 
-        if let Some(parent) = &mut state.donating_parent {
-          *parent -= 1;
-      // if the donating parent is not empty, then we decrement the parent by 1
+    if let Some(limits) = data.limits {
+        exec::validate_limits(&limits)?;
+        LIMITS.save(deps.storage, &limits)?;
+    } // donors start with a clean activity on the new deployment
 
-       if *parent == 0 {
-      let parent_donation = PARENT_DONATION.load(deps.storage)?;
-      *parent = parent_donation.donating_parent_period;
-      // if the parent is equal to 0, then we set the parent to the donating parent period, which is a field in the parent donation struct
+    if let Some(mode) = data.access_mode {
+        ACCESS_MODE.save(deps.storage, &mode)?;
+    }
+    if let Some(root) = data.merkle_root {
+        MERKLE_ROOT.save(deps.storage, &exec::validate_merkle_root(&root)?)?;
+    }
+    if let Some(pubkey) = data.voucher_signer {
+        VOUCHER_SIGNER.save(deps.storage, &pubkey)?;
+    }
+    if let Some(referrals) = data.referrals {
+        REFERRALS.save(deps.storage, &referrals)?;
+    }
+    exec::check_split(deps.as_ref())?;
+    // the snapshot could come from a contract which was never checked for it
 
-      // *parent is a dereference operator that allows you to access the value stored in the memory location pointed to by a reference.
-      // this is different from &parent, which is a reference operator that allows you to access the memory location of a value. 
-      // * is pointed to by a reference, & is a reference to a value.
-        // in this example, we are pointing to parent, which is a reference to a value which is a u64, and we are setting it to the donating parent period, which is a field in the parent donation struct
+    RESTORING.save(
+        deps.storage,
+        &Restoring {
+            total_donated: data.total_donated,
+            referral_accrued: data.referral_accrued,
+            paused: data.paused,
+        },
+    )?;
+    // the pause only applies once the ledgers are in, or they couldn't be imported
 
-  let funds: Vec<_> = deps
-    .querier
-    .query_all_balances(env.contract.address)?
-    .into_iter()
-    .map(|mut coin| {
-      coin.amount = coin.amount * parent_donation.part;
-      coin
-    })
-    .collect();
-  // we are setting the funds to a vector of coins, which is a struct from cosmwasm_std that is used to represent a coin
-  // we then use the query_all_balances function from the querier, which is a struct from cosmwasm_std that is used to query the blockchain
-  // we use the contract address from the env struct, which is a struct from cosmwasm_std that is used to access the blockchain context
-  // we then use the into_iter function to iterate over the balances, which is a vector of coins
-  // we then use the map function to map over the balances, which is a vector of coins, we map using a closure, which is a function that can be passed as an argument to another function
-  // we then use the amount field from the coin struct, which is a struct from cosmwasm_std that is used to represent a coin
-  // we then use the part field from the parent donation struct, which is a field in the parent donation struct
-  // we multiply the amount by the part, which is a field in the parent donation struct and we set it to the amount field of the coin struct
-  // we then use the collect function to collect the results of the map function, which is a vector of coins
+    migration::check_invariants(deps.as_ref())?;
+    // a snapshot with a matching checksum can still be inconsistent if it was exported by a buggy version
 
-    let msg = WasmMsg::Execute {
-      contract_addr: parent_donation.address.to_string(),
-      msg: to_binary(&ExecMsg::Donate {})?,
-      funds,
-    }; 
-    // here we just set the message to a WasmMsg::Execute struct, which is a struct from cosmwasm_std that is used to build a wasm message
-    // we set the contract address to the address field from the parent donation struct, which is a field in the parent donation struct
-    // we set the message to the donate message, which is a field in the exec message struct, which is a struct from the crate::msg module, this would be turned into a binary using the to_binary function from cosmwasm_std
-    // we set the funds to the funds vector, which is a vector of coins
-    // the purpose of msg is to send a message to another contract, which is the parent donation address, which is a field in the parent donation struct
+    let resp = Response::new()
+        .add_attribute("action", "restore")
+        .add_attribute("from_version", snapshot.version);
 
-    resp = resp
-        .add_message(msg)
-        .add_attribute("donated_to_parent", parent_donation.address.to_string());
-    // we add the message to the response, which is a struct from cosmwasm_std that is used to build a response
-    // we also add an attribute to the response, which is a struct from cosmwasm_std that is used to build a response
-      // the purpose of the attribute is to add a key-value pair to the response, which is a struct from cosmwasm_std that is used to build a response
-      // the key is donated_to_parent, and the value is the address field from the parent donation struct, which is a field in the parent donation struct
-    // finally, we convert the response to string and return it, assigning it to the response variable
-  }      
+    Ok(resp)
 }
+// instantiate contract from a snapshot, after checking its integrity
 
-      STATE.save(deps.storage, &state)?;
-      // we save the state to the storage, which is a field in the deps struct, which is a struct from cosmwasm_std that is used to access the blockchain context
-      }
+pub fn import_ledger(deps: DepsMut, env: Env, info: MessageInfo, page: LedgerPage) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {
+            owner: owner.to_string(),
+        });
+    }
 
-  resp = resp 
-    .add_attribute("action", "donate")
-    .add_attribute("sender", info.sender.to_string())
-    .add_attribute("counter", state.counter.to_string());
-    // adding attributes to the wasm event (only default event type that is emitted from every execution)
-      Ok(resp)
-      } 
+    if RESTORING.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NotRestoring);
+    } // a running contract has its own ledgers already
 
-        pub fn reset(deps: DepsMut, info: MessageInfo, counter: u64) -> Result<Response, ContractError> {
-         let mut state = STATE.load(deps.storage)?;
-         if info.sender != state.owner {
-           return Err(ContractError::Unauthorized {
-            owner: state.owner.to_string(),
-           });
-         } 
+    let imported = match page {
+        LedgerPage::Donations(donations) => {
+            let mut total_donated = TOTAL_DONATED.load(deps.storage)?;
+            for donation in &donations {
+                let donor = deps.api.addr_validate(&donation.donor)?;
+                if DONATIONS.may_load(deps.storage, &donor)?.is_some() {
+                    return Err(imported_twice(&donation.donor));
+                }
+                DONATIONS.save(deps.storage, &donor, &donation.amount, env.block.height)?;
+                total_donated += donation.amount;
+            }
+            TOTAL_DONATED.save(deps.storage, &total_donated, env.block.height)?;
+            donations.len()
+        }
+        LedgerPage::Campaigns(campaigns) => {
+            let imported = campaigns.len();
+            for info in campaigns {
+                if CAMPAIGNS.has(deps.storage, &info.id) {
+                    return Err(imported_twice(&info.id));
+                }
+                let id = info.id.clone();
+                let campaign = campaign(deps.as_ref(), info)?;
+                exec::update_campaign_balances(deps.storage, &[], &campaign.balance)?;
+                CAMPAIGNS.save(deps.storage, &id, &campaign)?;
+            } // campaign balances are restored as they were, the funds have to be sent to the new deployment with the rest
+            imported
+        }
+        LedgerPage::Allowlist(donors) => {
+            for donor in &donors {
+                ALLOWLIST.save(deps.storage, &deps.api.addr_validate(donor)?, &Empty {})?;
+            }
+            donors.len()
+        }
+        LedgerPage::Blocklist(donors) => {
+            for donor in &donors {
+                BLOCKLIST.save(deps.storage, &deps.api.addr_validate(donor)?, &Empty {})?;
+            }
+            donors.len()
+        }
+        LedgerPage::Referrers(referrers) => {
+            let imported = referrers.len();
+            let mut accrued = REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default();
+            for referrer in referrers {
+                let address = deps.api.addr_validate(&referrer.address)?;
+                if REFERRERS.has(deps.storage, &address) {
+                    return Err(imported_twice(&referrer.address));
+                }
+                exec::add_coins(&mut accrued, &referrer.accrued);
+                REFERRERS.save(
+                    deps.storage,
+                    &address,
+                    &Referrer {
+                        donors: referrer.donors,
+                        donations: referrer.donations,
+                        earned: referrer.earned,
+                        accrued: referrer.accrued,
+                    },
+                )?;
+            }
+            REFERRAL_ACCRUED.save(deps.storage, &accrued)?;
+            imported
+        }
+        LedgerPage::Referrals(referrals) => {
+            for referral in &referrals {
+                REFERRED_BY.save(
+                    deps.storage,
+                    &deps.api.addr_validate(&referral.donor)?,
+                    &deps.api.addr_validate(&referral.referrer)?,
+                )?;
+            }
+            referrals.len()
+        }
+    };
 
-         state.counter = counter;
-          STATE.save(deps.storage, &state)?;
+    let resp = Response::new()
+        .add_attribute("action", "import_ledger")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("imported", imported.to_string());
 
-        let resp = Response::new()
-            .add_attribute("action", "reset")
-            .add_attribute("sender", info.sender.as_str())
-            .add_attribute("counter", counter.to_string());
+    Ok(resp)
+}
 
-                    Ok(resp)
-  } // reset counter to 0
-  // Withdraws unthouched
+fn imported_twice(key: &str) -> ContractError {
+    ContractError::InvalidSnapshot {
+        reason: format!("{} is imported twice", key),
+    }
+} // importing a donation or a referrer twice would break the totals checked when finishing
 
-  pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn finish_restore(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let owner = STATE.load(deps.storage)?.owner;
     if info.sender != owner {
-      return Err(ContractError::Unauthorized {
-       owner: owner.to_string(),
-      });
-    } // checking if the sender of the message is the owner/creator of the contract
-    // instead of returning a generic error (StdError::generic_error(...)), we return a custom error, which is a ContractError::Unauthorized.
+        return Err(ContractError::Unauthorized {
+            owner: owner.to_string(),
+        });
+    }
 
-    let balance = deps.querier.query_all_balances(&env.contract.address)?;
-    let bank_msg = BankMsg::Send {
-      to_address: info.sender.to_string(),
-      amount: balance,
-    }; // queried all the balances of the contract, getting contract address using env and sent them to the sender of the message
-    // uses BankMsg::Send to send the balance to the sender of the message to the owner, which is the sender of the message
+    let restoring = RESTORING.may_load(deps.storage)?.ok_or(ContractError::NotRestoring)?;
+
+    let total_donated = TOTAL_DONATED.load(deps.storage)?;
+    if total_donated != restoring.total_donated {
+        return Err(ContractError::InvalidSnapshot {
+            reason: format!(
+                "imported donations add up to {}, the snapshot has {}",
+                total_donated, restoring.total_donated
+            ),
+        });
+    }
+
+    let mut accrued = REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default();
+    let mut expected = restoring.referral_accrued;
+    accrued.sort_by(|a, b| a.denom.cmp(&b.denom));
+    expected.sort_by(|a, b| a.denom.cmp(&b.denom));
+    if accrued != expected {
+        return Err(ContractError::InvalidSnapshot {
+            reason: "imported referrers don't add up to the accrued referral rewards".to_owned(),
+        });
+    } // a missing page would leave referrers unable to claim what the old contract owed them
+
+    if restoring.paused {
+        PAUSED.save(deps.storage, &true)?;
+    }
+    RESTORING.remove(deps.storage);
 
     let resp = Response::new()
-    // add_message function, which takes a Cosmos SDK message as an argument and adds it to the Response object
-        .add_message(bank_msg) 
-        .add_attribute("action", "withdraw")
+        .add_attribute("action", "finish_restore")
         .add_attribute("sender", info.sender.as_str());
 
-        Ok(resp)
-  } 
-  // withdraw all funds from contract while adding a message to the response, submessages must be processed first, then the Response object is returned. If submessages are not processed, the Response object is not returned.
-  // uses bank_msg to send the balance to the sender of the message to the owner, which is the sender of the message, adding it to the response object
+    Ok(resp)
+}
 
-  pub fn withdraw_to(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    receiver: String,
-    funds: Vec<Coin>,
-  ) -> Result<Response, ContractError> {
-    let owner = STATE.load(deps.storage)?.owner;
-    if info.sender != owner {
-      return Err(ContractError::Unauthorized {
-        owner: owner.to_string(),
-      });
-    } // checking if the sender of the message is the owner/creator of the contract
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
 
-    let mut balance = deps.querier.query_all_balances(&env.contract.address)?; // assign balance to the balance of the contract
+    let (stored, adapt) = if contract_version.contract == CONTRACT_NAME {
+        (migration::parse_version(&contract_version.version)?, None)
+    } else {
+        migration::find_alias(&contract_version.contract, &contract_version.version)?
+    };
+    // loaded version of the contract, then we validate if the contract name didn't change
+    // other names are only accepted from the alias table, foreign layouts come with an adapter into our own storage
 
-    if !funds.is_empty() { // if funds is not empty
-      for coin in &mut balance {
-         // for each coin in balance
-        let limit = funds 
-        // limit is the amount of the coin in funds
-        .iter() 
-        // iterates through the funds
-        .find(|c| c.denom == coin.denom) 
-        // finds the coin with the same denom as the coin in funds
-        .map(|c| c.amount) 
-        // maps the amount of the coin in funds
-        .unwrap_or(Uint128::zero()); 
-        // if there is no coin with the same denom as the coin in funds, set the amount to zero
+    let current = migration::parse_version(CONTRACT_VERSION)?;
+    // versions are compared as semver, so 0.10.0 is newer than 0.9.0
 
-        coin.amount = std::cmp::min(coin.amount, limit);
-        // set the amount to the minimum of the two amounts to prevent withdrawing more than the limit
-      }
-    } // if funds is not empty, iterate through the balance and find the coin with the same denom as the coin in funds, and set the amount to the minimum of the two amounts
+    if stored > current {
+        return Err(ContractError::DowngradeNotAllowed {
+            from: contract_version.version,
+            to: CONTRACT_VERSION.into(),
+        });
+    } // older code could misread storage written by a newer version
 
-    let bank_msg = BankMsg::Send {
-      to_address: receiver,
-      amount: balance,
-    }; // uses BankMsg::Send to send the balance to the receiver, adding it to the response object
+    if let Some(adapt) = adapt {
+        adapt(deps.branch(), &env)?;
+    }
 
-    let resp = Response::new()
-        .add_message(bank_msg)
-        .add_attribute("action", "withdraw")
-        // withdraw in add_attribut is the action that allows the owner to withdraw funds from the contract
-        .add_attribute("sender", info.sender.as_str());
-        // sender is the owner of the contract, which is the sender of the message, which is the only one who can execute this function
-        Ok(resp) 
-    } 
+    let steps = migration::plan(&stored, &current)?;
+    for step in &steps {
+        (step.run)(deps.branch(), &env)?;
+        // branch function we call on deps, utility that allows having another copy of a mutable state in a single contract, like a clone() function
+    } // every hop is applied in order, so 0.1.0 goes through 0.2.0 and 0.3.0 before reaching 0.4.0
+    // there are no steps for a same version migration, it only updates the config below
+
+    let mut state = STATE.load(deps.storage)?;
+
+    match msg.parent {
+        Update::Keep => (),
+        Update::Set(parent) => {
+            state.donating_parent = Some(parent.donating_period);
+            let parent = parent_donation(deps.as_ref(), parent)?;
+            PARENT_DONATION.save(deps.storage, &parent)?;
+            exec::check_split(deps.as_ref())?;
+        }
+        Update::Clear => {
+            state.donating_parent = None;
+            PARENT_DONATION.remove(deps.storage);
+        }
+    } // the parent is set once, after the storage reached the current layout
+
+    match msg.minimal_donation {
+        Update::Keep => (),
+        Update::Set(minimal_donation) => state.minimal_donation = minimal_donation,
+        Update::Clear => state.minimal_donation.amount = Uint128::zero(),
+        // zero minimal donation means every donation is counted
+    }
+
+    match msg.owner {
+        Update::Keep => (),
+        Update::Set(owner) => state.owner = deps.api.addr_validate(&owner)?,
+        Update::Clear => {
+            return Err(ContractError::CannotClear {
+                field: "owner".to_owned(),
+            })
+        }
+    }
+
+    match msg.counter {
+        Update::Keep => (),
+        Update::Set(counter) => COUNTER.save(deps.storage, &counter, env.block.height)?,
+        Update::Clear => COUNTER.save(deps.storage, &0, env.block.height)?,
+    } // recorded in the counter history like any other change
+
+    STATE.save(deps.storage, &state)?;
+
+    migration::check_invariants(deps.as_ref())?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    // finally, we update the contract version to the new value so the contract version would be valid on future migrations
+
+    let resp = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", contract_version.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    Ok(resp)
+  } // migrate contract state from any older version to the current one, and update the config on the way
+
+// every storage version has its own step in here, the migrate function above only performs the version dispatch
+mod migration {
+    use cosmwasm_std::{Addr, Coin, Decimal, Deps, DepsMut, Env, StdResult};
+    use cw_storage_plus::Item;
+    use semver::{Version, VersionReq};
+    use serde::{Deserialize, Serialize};
+
+    use crate::error::ContractError;
+    use crate::state::{State, COUNTER, COUNTER_NAMESPACE, PARENT_DONATION, STATE};
+
+    pub type Run = fn(DepsMut, &Env) -> StdResult<()>;
+    pub type Keys = fn() -> Vec<String>;
+
+    pub struct Step {
+        pub from: &'static str,
+        pub to: &'static str,
+        pub reads: Keys,
+        pub writes: Keys,
+        pub run: Run,
+    } // upgrades the storage from one version to the next one, reads and writes are the storage keys it touches
+    // the keys are taken from the items the step uses, so the plan can't drift from what the step does
+
+    const STEPS: &[Step] = &[
+        Step {
+            from: "0.1.0",
+            to: "0.2.0",
+            reads: || keys(&[COUNTER_0_1_0.as_slice(), MINIMAL_DONATION_0_1_0.as_slice(), OWNER_0_1_0.as_slice()]),
+            writes: || {
+                keys(&[
+                    COUNTER_0_1_0.as_slice(),
+                    MINIMAL_DONATION_0_1_0.as_slice(),
+                    OWNER_0_1_0.as_slice(),
+                    STATE_0_2_0.as_slice(),
+                ])
+            },
+            run: migrate_0_1_0,
+        },
+        Step {
+            from: "0.2.0",
+            to: "0.3.0",
+            reads: || keys(&[STATE_0_2_0.as_slice()]),
+            writes: || keys(&[STATE_0_3_0.as_slice()]),
+            run: migrate_0_2_0,
+        },
+        Step {
+            from: "0.3.0",
+            to: "0.4.0",
+            reads: || keys(&[STATE_0_3_0.as_slice()]),
+            writes: || keys(&[STATE.as_slice(), COUNTER_NAMESPACE.as_bytes(), COUNTER.changelog().namespace()]),
+            run: migrate_0_3_0,
+        },
+    ];
+    // a new storage version only needs a new step at the end of this list
+
+    fn keys(namespaces: &[&[u8]]) -> Vec<String> {
+        namespaces
+            .iter()
+            .map(|namespace| String::from_utf8_lossy(namespace).into_owned())
+            .collect()
+    }
+
+    pub fn finish_reads() -> Vec<String> {
+        keys(&[cw2::CONTRACT.as_slice(), STATE.as_slice()])
+    }
+
+    pub fn finish_writes() -> Vec<String> {
+        keys(&[
+            STATE.as_slice(),
+            PARENT_DONATION.as_slice(),
+            COUNTER_NAMESPACE.as_bytes(),
+            COUNTER.changelog().namespace(),
+            cw2::CONTRACT.as_slice(),
+        ])
+    }
+    // keys the migrate function touches itself after the steps, the parent and the counter only when the message updates them
+
+    pub enum Adapter {
+        Same,
+        Foreign {
+            to: &'static str,
+            run: Run,
+        },
+    } // Same is for contracts which only differ by name, Foreign maps another storage layout into ours at the `to` version
+
+    pub struct Alias {
+        pub contract: &'static str,
+        pub versions: &'static str,
+        pub adapter: Adapter,
+    } // versions is a semver requirement, like "*" or "^1.0"
+
+    const ALIASES: &[Alias] = &[
+        Alias {
+            contract: "crates.io:counting-contract",
+            versions: "*",
+            adapter: Adapter::Same,
+        },
+        Alias {
+            contract: "donation-counter",
+            versions: "^1.0",
+            adapter: Adapter::Foreign {
+                to: "0.3.0",
+                run: adapt_donation_counter_1,
+            },
+        },
+    ];
+    // contract names other than ours that we accept to migrate from
+
+    pub fn find_alias(
+        contract: &str,
+        version: &str,
+    ) -> Result<(Version, Option<Run>), ContractError> {
+        let parsed = parse_version(version)?;
+
+        let alias = ALIASES
+            .iter()
+            .filter(|alias| alias.contract == contract)
+            .find(|alias| {
+                VersionReq::parse(alias.versions)
+                    .map(|req| req.matches(&parsed))
+                    .unwrap_or(false)
+            });
+
+        match alias {
+            Some(Alias {
+                adapter: Adapter::Same,
+                ..
+            }) => Ok((parsed, None)),
+            Some(Alias {
+                adapter: Adapter::Foreign { to, run },
+                ..
+            }) => Ok((parse_version(to)?, Some(*run))),
+            None if ALIASES.iter().any(|alias| alias.contract == contract) => {
+                Err(ContractError::InvalidContractVersion {
+                    version: version.into(),
+                })
+            }
+            None => Err(ContractError::InvalidContract {
+                contract: contract.into(),
+            }),
+        }
+    }
+    // returns the version of our own storage the contract is at once the adapter (if any) runs
+
+    pub fn parse_version(version: &str) -> Result<Version, ContractError> {
+        Version::parse(version).map_err(|_| ContractError::InvalidContractVersion {
+            version: version.into(),
+        })
+    }
+
+    pub fn plan(from: &Version, to: &Version) -> Result<Vec<&'static Step>, ContractError> {
+        let mut version = from.clone();
+        let mut steps = vec![];
+
+        while version < *to {
+            let step = STEPS
+                .iter()
+                .find(|step| parse_version(step.from).ok().as_ref() == Some(&version))
+                .ok_or_else(|| ContractError::InvalidContractVersion {
+                    version: version.to_string(),
+                })?;
+            // there has to be a step starting exactly at the version we are at
+
+            version = parse_version(step.to)?;
+            steps.push(step);
+        }
+
+        Ok(steps)
+    }
+    // chains the steps needed to get from one version to the other
+
+    pub fn check_invariants(deps: Deps) -> Result<(), ContractError> {
+        let state = STATE.load(deps.storage)?;
+        let parent_donation = PARENT_DONATION.may_load(deps.storage)?;
+
+        let reason = match (state.donating_parent, parent_donation) {
+            (Some(_), None) => "donating_parent is set, but there is no parent stored",
+            (None, Some(_)) => "parent is stored, but donating_parent is not set",
+            (Some(countdown), Some(parent)) if countdown == 0 || countdown > parent.donating_parent_period => {
+                "donating_parent is outside of the parent donating period"
+            }
+            (Some(_), Some(parent)) if parent.part > Decimal::one() => {
+                "parent part is greater than the whole donation"
+            }
+            _ => return Ok(()),
+        };
+
+        Err(ContractError::InvariantViolated {
+            reason: reason.to_owned(),
+        })
+    }
+    // run after all the steps, so a migration never leaves the storage in a state the contract can't work with
+
+    const COUNTER_0_1_0: Item<u64> = Item::new("counter");
+    const MINIMAL_DONATION_0_1_0: Item<Coin> = Item::new("minimal_donation");
+    const OWNER_0_1_0: Item<Addr> = Item::new("owner");
+    // 0.1.0 kept every field under its own key
+
+    fn migrate_0_1_0(deps: DepsMut, _env: &Env) -> StdResult<()> {
+        let counter = COUNTER_0_1_0.load(deps.storage)?;
+        let minimal_donation = MINIMAL_DONATION_0_1_0.load(deps.storage)?;
+        let owner = OWNER_0_1_0.load(deps.storage)?;
+
+        COUNTER_0_1_0.remove(deps.storage);
+        MINIMAL_DONATION_0_1_0.remove(deps.storage);
+        OWNER_0_1_0.remove(deps.storage);
+
+        STATE_0_2_0.save(
+            deps.storage,
+            &State0_2_0 {
+                counter,
+                minimal_donation,
+                owner,
+            },
+        )
+    } // migrate from 0.1.0 to 0.2.0
+
+    fn adapt_donation_counter_1(deps: DepsMut, _env: &Env) -> StdResult<()> {
+        #[derive(Serialize, Deserialize)]
+        struct Config {
+            owner: Addr,
+            min_donation: Coin,
+        }
+
+        const CONFIG: Item<Config> = Item::new("config");
+        const COUNT: Item<u64> = Item::new("count");
+
+        let Config {
+            owner,
+            min_donation,
+        } = CONFIG.load(deps.storage)?;
+        let counter = COUNT.load(deps.storage)?;
+
+        CONFIG.remove(deps.storage);
+        COUNT.remove(deps.storage);
+
+        STATE_0_3_0.save(
+            deps.storage,
+            &State0_3_0 {
+                counter,
+                minimal_donation: min_donation,
+                owner,
+                donating_parent: None,
+            },
+        )
+    } // donation-counter 1.x keeps the owner and minimal donation in a config, and the counter on its own
+
+    #[derive(Serialize, Deserialize)]
+    struct State0_2_0 {
+        counter: u64,
+        minimal_donation: Coin,
+        owner: Addr,
+    }
+
+    const STATE_0_2_0: Item<State0_2_0> = Item::new("state");
+    // 0.2.0 kept everything in a single state, but without the parent
+
+    fn migrate_0_2_0(deps: DepsMut, _env: &Env) -> StdResult<()> {
+        let State0_2_0 {
+            counter,
+            minimal_donation,
+            owner,
+        } = STATE_0_2_0.load(deps.storage)?;
+        // we have to use the same names as the fields in the State struct, so we can use the shorthand syntax
+
+        STATE_0_3_0.save(
+            deps.storage,
+            &State0_3_0 {
+                counter,
+                minimal_donation,
+                owner,
+                donating_parent: None,
+            },
+        )
+        // the parent is set by the migrate function once all the steps are done
+    } // migrate from 0.2.0 to 0.3.0
+
+    #[derive(Serialize, Deserialize)]
+    struct State0_3_0 {
+        counter: u64,
+        minimal_donation: Coin,
+        owner: Addr,
+        donating_parent: Option<u64>,
+    }
+
+    const STATE_0_3_0: Item<State0_3_0> = Item::new("state");
+    // 0.3.0 kept the counter in the state, without its history
+
+    fn migrate_0_3_0(deps: DepsMut, env: &Env) -> StdResult<()> {
+        let State0_3_0 {
+            counter,
+            minimal_donation,
+            owner,
+            donating_parent,
+        } = STATE_0_3_0.load(deps.storage)?;
+
+        COUNTER.save(deps.storage, &counter, env.block.height)?;
+        // the history starts at the migration, older values were never recorded
+
+        STATE.save(
+            deps.storage,
+            &State {
+                minimal_donation,
+                owner,
+                donating_parent,
+            },
+        )
+    } // migrate from 0.3.0 to 0.4.0
+}
+
+// query is a read operation
+pub mod query {
+  use cosmwasm_std::{Addr, Coin, Deps, Env, Order, OverflowError, OverflowOperation, StdError, StdResult, Uint128};
+  use cw2::get_contract_version;
+  use cw_storage_plus::Bound;
+
+  use super::{migration, snapshot_checksum, CONTRACT_VERSION, SNAPSHOT_FORMAT};
+  use crate::error::ContractError;
+  use crate::msg::{
+    AccessListResp, AccessMode, AllowanceResp, AllowanceSnapshot, CampaignInfo, Contribution, ContributionResp, ContributionsResp, DelegationInfo, DonationSnapshot,
+    FailedTransferInfo, FailedTransfersResp, GovernanceConfig, HistoryEntry, HistoryResp, HookInfo, HooksResp, LimitsResp, MerkleRootResp, MigrationPlanResp, ProposalResp, ProposalStatus, ReceiptConfig, RewardRate, RewardRateResp,
+    Ledger, LedgerPage, MigrationStep, Parent, ReceiverPolicySnapshot, ReceiverSnapshot, ReferralSnapshot, ReferralsResp, ReferrerResp, ReferrerSnapshot, Peer, PeersResp, RemoteCounter, RemoteCountersResp, Snapshot, SnapshotData,
+    StakingConfig, StakingResp, ValueResp, VoucherSignerResp,
+  };
+  use crate::state::{
+    donor_list, Campaign, ParentDonation, ACCESS_MODE, ALLOWANCES, CAMPAIGNS, COUNTER, DEFAULT_CAMPAIGN, DONATIONS, DONOR_ACTIVITY, FAILED_TRANSFERS, GOVERNANCE, HOOKS, LIMITS, MERKLE_ROOT, MERKLE_VERIFIED, PARENT_DONATION, PEERS, PROPOSALS, REMOTE_COUNTERS, RECEIPTS, RECEIPTS_MINTED, RECEIVERS,
+    PAUSED, RECEIVER_POLICY, REFERRALS, REFERRAL_ACCRUED, REFERRED_BY, REFERRERS, REWARD_RATES, REWARD_TOKEN, STAKING, STATE, TOTAL_DONATED, USED_NONCES, VOUCHER_SIGNER,
+  };
+
+  const DEFAULT_HISTORY_LIMIT: u32 = 10;
+  const MAX_HISTORY_LIMIT: u32 = 30;
+
+    pub fn value(deps: Deps) -> StdResult<ValueResp> { // Deps to access contract/bc storage
+      let value = COUNTER.load(deps.storage)?;
+      // error handling, so we use ?
+      // load function, loading from the state, taking state accessor as an arguement
+      Ok(ValueResp { value })
+    }
+
+    pub fn campaign_value(deps: Deps, id: String) -> StdResult<ValueResp> {
+      let campaign = CAMPAIGNS.load(deps.storage, &id)?;
+      Ok(ValueResp {
+        value: campaign.counter,
+      })
+    }
+
+    pub fn campaign(deps: Deps, env: Env, id: String) -> StdResult<CampaignInfo> {
+      if id == DEFAULT_CAMPAIGN {
+        let state = STATE.load(deps.storage)?;
+        return Ok(CampaignInfo {
+          id,
+          owner: state.owner.to_string(),
+          minimal_donation: state.minimal_donation,
+          counter: COUNTER.load(deps.storage)?,
+          donating_parent: state.donating_parent,
+          parent: PARENT_DONATION.may_load(deps.storage)?.map(parent_info),
+          balance: super::exec::default_balance(deps, &env)?,
+        });
+      } // the default campaign is kept in the storage the contract had before campaigns
+
+      let campaign = CAMPAIGNS.load(deps.storage, &id)?;
+      Ok(campaign_info(id, campaign))
+    }
+
+    fn campaign_info(id: String, campaign: Campaign) -> CampaignInfo {
+      CampaignInfo {
+        id,
+        owner: campaign.owner.to_string(),
+        minimal_donation: campaign.minimal_donation,
+        counter: campaign.counter,
+        donating_parent: campaign.donating_parent,
+        parent: campaign.parent.map(parent_info),
+        balance: campaign.balance,
+      }
+    }
+
+    fn parent_info(parent: ParentDonation) -> Parent {
+      Parent {
+        addr: parent.address.to_string(),
+        donating_period: parent.donating_parent_period,
+        part: parent.part,
+        ibc: parent.ibc,
+      }
+    }
+
+    pub fn value_at(deps: Deps, height: u64) -> StdResult<ValueResp> {
+      let value = COUNTER
+        .may_load_at_height(deps.storage, height)?
+        .ok_or_else(|| StdError::generic_err(format!("no counter value recorded at height {}", height)))?;
+      // None means the height is before the counter was first saved, by the instantiation or the migration to 0.4.0
+
+      Ok(ValueResp { value })
+    }
+    // value at the beginning of the block, before any of its transactions changed it
+
+    pub fn history(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<HistoryResp> {
+      let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+      let start = start_after.map(Bound::exclusive);
+
+      let changes = COUNTER
+        .changelog()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+      // the changelog keeps the value from before the change, so the value after it is in the next entry
+      // one more entry is loaded to get the value after the last one returned
+
+      let current = COUNTER.load(deps.storage)?;
+
+      let entries = changes
+        .iter()
+        .enumerate()
+        .take(limit)
+        .map(|(idx, (height, _))| HistoryEntry {
+          height: *height,
+          value: changes
+            .get(idx + 1)
+            .map(|(_, next)| next.old.unwrap_or_default())
+            .unwrap_or(current),
+          // the latest change is still the current value
+        })
+        .collect();
+
+      Ok(HistoryResp { entries })
+    }
+    // heights at which the counter changed, with the value it had at the end of that block
+
+    pub fn allowance(deps: Deps, env: Env, spender: String) -> StdResult<AllowanceResp> {
+      let spender = deps.api.addr_validate(&spender)?;
+      let mut allowance = match ALLOWANCES.may_load(deps.storage, &spender)? {
+        Some(allowance) => allowance,
+        None => {
+          return Ok(AllowanceResp {
+            limit: vec![],
+            period: 0,
+            remaining: vec![],
+            resets_at: env.block.time,
+          })
+        }
+      }; // no allowance is reported as an empty one instead of an error
+
+      allowance.refresh(env.block.time);
+      // the query sees the window as the next withdrawal would
+
+      let remaining = allowance
+        .limit
+        .iter()
+        .map(|c| Coin {
+          denom: c.denom.clone(),
+          amount: allowance.remaining(&c.denom),
+        })
+        .collect();
+
+      Ok(AllowanceResp {
+        limit: allowance.limit,
+        period: allowance.period,
+        remaining,
+        resets_at: allowance.period_start.plus_seconds(allowance.period),
+      })
+    }
+
+    pub fn hooks(deps: Deps) -> StdResult<HooksResp> {
+      let hooks = hook_infos(deps)?;
+      Ok(HooksResp { hooks })
+    }
+
+    fn hook_infos(deps: Deps) -> StdResult<Vec<HookInfo>> {
+      HOOKS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (hook, subscription) = item?;
+          Ok(HookInfo {
+            hook: hook.to_string(),
+            on_reset: subscription.on_reset,
+            on_withdraw: subscription.on_withdraw,
+          })
+        })
+        .collect()
+    }
+    // shared with the export, hooks are listed in the same form in both
+
+    pub fn reward_rate(deps: Deps) -> StdResult<RewardRateResp> {
+      Ok(RewardRateResp {
+        token: REWARD_TOKEN.may_load(deps.storage)?.map(|token| token.to_string()),
+        rates: reward_rates(deps)?,
+      })
+    }
+
+    fn reward_rates(deps: Deps) -> StdResult<Vec<RewardRate>> {
+      REWARD_RATES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (denom, rate) = item?;
+          Ok(RewardRate { denom, rate })
+        })
+        .collect()
+    }
+
+    pub fn proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResp> {
+      let proposal = PROPOSALS.load(deps.storage, id)?;
+
+      let status = if proposal.executed {
+        ProposalStatus::Executed
+      } else if proposal.passed() {
+        ProposalStatus::Passed
+      } else if env.block.time >= proposal.expires {
+        ProposalStatus::Rejected
+      } else {
+        ProposalStatus::Open
+      };
+
+      Ok(ProposalResp {
+        id,
+        proposer: proposal.proposer.to_string(),
+        description: proposal.description,
+        payouts: proposal.payouts,
+        expires: proposal.expires,
+        total_weight: proposal.total_weight,
+        yes: proposal.yes,
+        no: proposal.no,
+        status,
+        governance: proposal.governance,
+      })
+    }
+
+    pub fn contribution(deps: Deps, donor: String) -> StdResult<ContributionResp> {
+      let donor = deps.api.addr_validate(&donor)?;
+
+      Ok(ContributionResp {
+        amount: DONATIONS.may_load(deps.storage, &donor)?.unwrap_or_default(),
+        total: TOTAL_DONATED.may_load(deps.storage)?.unwrap_or_default(),
+        donor: donor.to_string(),
+      })
+    }
+
+    pub fn contributions(
+      deps: Deps,
+      from_height: Option<u64>,
+      to_height: Option<u64>,
+      start_after: Option<String>,
+      limit: Option<u32>,
+    ) -> StdResult<ContributionsResp> {
+      let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+      let start_after = start_after.map(|donor| deps.api.addr_validate(&donor)).transpose()?;
+      let start = start_after.as_ref().map(Bound::exclusive);
+
+      let donors = DONATIONS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+      let contributions = donors
+        .into_iter()
+        .map(|donor| {
+          let to = match to_height {
+            Some(height) => DONATIONS.may_load_at_height(deps.storage, &donor, height)?,
+            None => DONATIONS.may_load(deps.storage, &donor)?,
+          }
+          .unwrap_or_default();
+          let from = match from_height {
+            Some(height) => DONATIONS.may_load_at_height(deps.storage, &donor, height)?.unwrap_or_default(),
+            None => Uint128::zero(),
+          };
+
+          Ok(Contribution {
+            donor: donor.to_string(),
+            amount: to.saturating_sub(from),
+          })
+        })
+        .collect::<StdResult<_>>()?;
+      // the ledger only grows, saturating keeps a reversed range at zero
+
+      Ok(ContributionsResp {
+        denom: STATE.load(deps.storage)?.minimal_donation.denom,
+        contributions,
+      })
+    }
+
+    pub fn failed_transfers(deps: Deps) -> StdResult<FailedTransfersResp> {
+      let transfers = FAILED_TRANSFERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (id, transfer) = item?;
+          Ok(FailedTransferInfo {
+            id,
+            campaign: transfer.campaign.unwrap_or_else(|| DEFAULT_CAMPAIGN.to_owned()),
+            channel_id: transfer.channel_id,
+            receiver: transfer.receiver,
+            amount: transfer.amount,
+          })
+        })
+        .collect::<StdResult<_>>()?;
+
+      Ok(FailedTransfersResp { transfers })
+    }
+    // not paginated, failed transfers are retried or stay as a record of the refunds
+
+    pub fn remote_counters(deps: Deps) -> StdResult<RemoteCountersResp> {
+      let counters = REMOTE_COUNTERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (channel_id, value) = item?;
+          Ok(RemoteCounter { channel_id, value })
+        })
+        .collect::<StdResult<_>>()?;
+
+      Ok(RemoteCountersResp { counters })
+    }
+
+    pub fn peers(deps: Deps) -> StdResult<PeersResp> {
+      let peers = PEERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| {
+          let (connection_id, port_id) = key?;
+          Ok(Peer { connection_id, port_id })
+        })
+        .collect::<StdResult<_>>()?;
+
+      Ok(PeersResp { peers })
+    }
+    // not paginated, peers are added by the owner one by one
+
+    pub fn global_value(deps: Deps) -> StdResult<ValueResp> {
+      let mut value = COUNTER.load(deps.storage)?;
+      for item in REMOTE_COUNTERS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, remote) = item?;
+        value = value
+          .checked_add(remote)
+          .ok_or_else(|| StdError::overflow(OverflowError::new(OverflowOperation::Add, value, remote)))?;
+      } // the remote counters are what the peers reported, nothing stops them from adding up past u64
+
+      Ok(ValueResp { value })
+    }
+    // a hub reporting further up doesn't double count, it only sends the changes of its own counter
+
+    pub fn voucher_signer(deps: Deps, nonce: Option<u64>) -> StdResult<VoucherSignerResp> {
+      Ok(VoucherSignerResp {
+        pubkey: VOUCHER_SIGNER.may_load(deps.storage)?,
+        used: nonce.is_some_and(|nonce| USED_NONCES.has(deps.storage, nonce)),
+      })
+    }
+
+    pub fn referrals(deps: Deps) -> StdResult<ReferralsResp> {
+      Ok(ReferralsResp {
+        referrals: REFERRALS.may_load(deps.storage)?,
+        accrued: REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default(),
+      })
+    }
+
+    pub fn referrer(deps: Deps, address: String) -> StdResult<ReferrerResp> {
+      let stats = REFERRERS
+        .may_load(deps.storage, &deps.api.addr_validate(&address)?)?
+        .unwrap_or_default();
+
+      Ok(ReferrerResp {
+        address,
+        donors: stats.donors,
+        donations: stats.donations,
+        earned: stats.earned,
+        accrued: stats.accrued,
+      })
+    }
+
+    pub fn merkle_root(deps: Deps, donor: Option<String>) -> StdResult<MerkleRootResp> {
+      let root = MERKLE_ROOT.may_load(deps.storage)?;
+      let verified = match (&root, donor) {
+        (Some(root), Some(donor)) => {
+          MERKLE_VERIFIED.may_load(deps.storage, &deps.api.addr_validate(&donor)?)?.as_ref() == Some(root)
+        }
+        _ => false,
+      };
+
+      Ok(MerkleRootResp { root, verified })
+    }
+
+    pub fn access_list(
+      deps: Deps,
+      list: Option<AccessMode>,
+      start_after: Option<String>,
+      limit: Option<u32>,
+    ) -> StdResult<AccessListResp> {
+      let mode = ACCESS_MODE.may_load(deps.storage)?;
+      let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+      let start_after = start_after.map(|donor| deps.api.addr_validate(&donor)).transpose()?;
+      let start = start_after.as_ref().map(Bound::exclusive);
+
+      let donors = match list.or(mode) {
+        Some(list) => donor_list(list)
+          .keys(deps.storage, start, None, Order::Ascending)
+          .take(limit)
+          .map(|donor| donor.map(Addr::into_string))
+          .collect::<StdResult<_>>()?,
+        None => vec![],
+      };
+
+      Ok(AccessListResp { mode, donors })
+    }
+
+    pub fn export_ledger(deps: Deps, ledger: Ledger, start_after: Option<String>, limit: Option<u32>) -> StdResult<LedgerPage> {
+      let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+      let start_addr = start_after.as_ref().map(|key| Addr::unchecked(key.as_str()));
+      let start = start_addr.as_ref().map(Bound::exclusive);
+      // every ledger but the campaigns is keyed by address, exported addresses are valid so they are not validated again
+
+      let page = match ledger {
+        Ledger::Donations => LedgerPage::Donations(
+          DONATIONS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+              let (donor, amount) = item?;
+              Ok(DonationSnapshot {
+                donor: donor.to_string(),
+                amount,
+              })
+            })
+            .collect::<StdResult<_>>()?,
+        ),
+        Ledger::Campaigns => LedgerPage::Campaigns(
+          CAMPAIGNS
+            .range(deps.storage, start_after.as_deref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+              let (id, campaign) = item?;
+              Ok(campaign_info(id, campaign))
+            })
+            .collect::<StdResult<_>>()?,
+        ),
+        Ledger::Allowlist | Ledger::Blocklist => {
+          let list = if ledger == Ledger::Allowlist { AccessMode::Allowlist } else { AccessMode::Blocklist };
+          let donors = donor_list(list)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|donor| donor.map(Addr::into_string))
+            .collect::<StdResult<_>>()?;
+          match list {
+            AccessMode::Allowlist => LedgerPage::Allowlist(donors),
+            AccessMode::Blocklist => LedgerPage::Blocklist(donors),
+          }
+        }
+        Ledger::Referrers => LedgerPage::Referrers(
+          REFERRERS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+              let (address, referrer) = item?;
+              Ok(ReferrerSnapshot {
+                address: address.to_string(),
+                donors: referrer.donors,
+                donations: referrer.donations,
+                earned: referrer.earned,
+                accrued: referrer.accrued,
+              })
+            })
+            .collect::<StdResult<_>>()?,
+        ),
+        Ledger::Referrals => LedgerPage::Referrals(
+          REFERRED_BY
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+              let (donor, referrer) = item?;
+              Ok(ReferralSnapshot {
+                donor: donor.to_string(),
+                referrer: referrer.to_string(),
+              })
+            })
+            .collect::<StdResult<_>>()?,
+        ),
+      };
+
+      Ok(page)
+    }
+    // page of a ledger for the restore, the last key of a page is where the next one starts
+
+    pub fn limits(deps: Deps, donor: Option<String>) -> StdResult<LimitsResp> {
+      let activity = match donor {
+        Some(donor) => DONOR_ACTIVITY.may_load(deps.storage, &deps.api.addr_validate(&donor)?)?,
+        None => None,
+      };
+
+      Ok(LimitsResp {
+        limits: LIMITS.may_load(deps.storage)?,
+        activity,
+      })
+    }
+
+    pub fn staking(deps: Deps, env: Env) -> StdResult<StakingResp> {
+      let delegations = deps
+        .querier
+        .query_all_delegations(&env.contract.address)?
+        .into_iter()
+        .map(|delegation| DelegationInfo {
+          validator: delegation.validator,
+          amount: delegation.amount,
+        })
+        .collect();
+
+      Ok(StakingResp {
+        staking: STAKING.may_load(deps.storage)?.map(|staking| StakingConfig {
+          validator: staking.validator,
+          fraction: staking.fraction,
+        }),
+        delegations,
+      })
+    }
+
+    pub fn migration_plan(from_version: String) -> StdResult<MigrationPlanResp> {
+      let steps = plan_steps(&from_version).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+      Ok(MigrationPlanResp {
+        from_version,
+        to_version: CONTRACT_VERSION.to_owned(),
+        steps,
+        reads: migration::finish_reads(),
+        writes: migration::finish_writes(),
+      })
+    }
+    // dry run of a migration from the given version to the version of this code
+
+    pub fn export_state(deps: Deps) -> StdResult<Snapshot> {
+      let version = get_contract_version(deps.storage)?;
+      let state = STATE.load(deps.storage)?;
+
+      let parent = PARENT_DONATION.may_load(deps.storage)?.map(parent_info);
+
+      let allowances = ALLOWANCES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (spender, allowance) = item?;
+          Ok(AllowanceSnapshot {
+            spender: spender.to_string(),
+            limit: allowance.limit,
+            period: allowance.period,
+            spent: allowance.spent,
+            period_start: allowance.period_start,
+          })
+        })
+        .collect::<StdResult<_>>()?;
+
+      let receiver_policy = RECEIVER_POLICY.may_load(deps.storage)?.unwrap_or_default();
+
+      let receivers = RECEIVERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+          let (receiver, allowed_from) = item?;
+          Ok(ReceiverSnapshot {
+            receiver: receiver.to_string(),
+            allowed_from,
+          })
+        })
+        .collect::<StdResult<_>>()?;
+
+      let data = SnapshotData {
+        counter: COUNTER.load(deps.storage)?,
+        minimal_donation: state.minimal_donation,
+        owner: state.owner.to_string(),
+        donating_parent: state.donating_parent,
+        parent,
+        allowances,
+        receiver_policy: ReceiverPolicySnapshot {
+          enforce: receiver_policy.enforce,
+          delay: receiver_policy.delay,
+          pending: receiver_policy.pending,
+        },
+        receivers,
+        hooks: hook_infos(deps)?,
+        receipts: RECEIPTS.may_load(deps.storage)?.map(|receipts| ReceiptConfig {
+          cw721: receipts.cw721.to_string(),
+          token_uri: receipts.token_uri,
+          threshold: receipts.threshold,
+        }),
+        receipts_minted: RECEIPTS_MINTED.may_load(deps.storage)?.unwrap_or_default(),
+        reward_token: REWARD_TOKEN.may_load(deps.storage)?.map(|token| token.to_string()),
+        reward_rates: reward_rates(deps)?,
+        governance: GOVERNANCE.may_load(deps.storage)?.map(|governance| GovernanceConfig {
+          quorum: governance.quorum,
+          threshold: governance.threshold,
+          voting_period: governance.voting_period,
+          restrict_withdrawals: governance.restrict_withdrawals,
+        }),
+        staking: STAKING.may_load(deps.storage)?.map(|staking| StakingConfig {
+          validator: staking.validator,
+          fraction: staking.fraction,
+        }),
+        limits: LIMITS.may_load(deps.storage)?,
+        access_mode: ACCESS_MODE.may_load(deps.storage)?,
+        merkle_root: MERKLE_ROOT.may_load(deps.storage)?,
+        voucher_signer: VOUCHER_SIGNER.may_load(deps.storage)?,
+        referrals: REFERRALS.may_load(deps.storage)?,
+        paused: PAUSED.may_load(deps.storage)?.unwrap_or(false),
+        total_donated: TOTAL_DONATED.may_load(deps.storage)?.unwrap_or_default(),
+        referral_accrued: REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default(),
+      };
+      // allowances, receivers and hooks are set by the owner one by one, so they stay small enough for a single query
+
+      let checksum = snapshot_checksum(SNAPSHOT_FORMAT, &version.contract, &version.version, &data)?;
+
+      Ok(Snapshot {
+        format: SNAPSHOT_FORMAT,
+        contract: version.contract,
+        version: version.version,
+        data,
+        checksum,
+      })
+    }
+    // everything the contract keeps in its storage, in a form that can be restored on another chain
+
+    fn plan_steps(from_version: &str) -> Result<Vec<MigrationStep>, ContractError> {
+      let from = migration::parse_version(from_version)?;
+      let to = migration::parse_version(CONTRACT_VERSION)?;
+
+      if from > to {
+        return Err(ContractError::DowngradeNotAllowed {
+          from: from_version.to_owned(),
+          to: CONTRACT_VERSION.into(),
+        });
+      } // the same checks the migrate function does, without touching the storage
+
+      let steps = migration::plan(&from, &to)?
+        .into_iter()
+        .map(|step| MigrationStep {
+          from: step.from.to_owned(),
+          to: step.to.to_owned(),
+          reads: (step.reads)(),
+          writes: (step.writes)(),
+        })
+        .collect();
+
+      Ok(steps)
+    }
+  } 
+  // load vs save: load is a read-only operation, save is a write operation.
+  // we have a function called value, which takes a Deps argument and returns a result of type StdResult<ValueResp>
+  // it is a read-only operation (pub mod query), so we use the load function on the STATE constant to load the value from the contract's storage.
+
+  // sudo is a write operation called by the chain, so there is no sender to check
+  pub mod sudo {
+    use cosmwasm_std::{DepsMut, Env, Response};
+
+    use super::exec::{add_coins, update_campaign_balances};
+    use crate::error::ContractError;
+    use crate::state::{CAMPAIGNS, COUNTER, FAILED_TRANSFERS, FAILED_TRANSFER_COUNT, PAUSED, STATE, TRANSFERS_IN_FLIGHT};
+
+    pub fn transfer_complete(deps: DepsMut, channel: String, sequence: u64, error: Option<String>) -> Result<Response, ContractError> {
+      let transfer = match TRANSFERS_IN_FLIGHT.may_load(deps.storage, (&channel, sequence))? {
+        Some(transfer) => transfer,
+        None => return Ok(Response::new().add_attribute("action", "transfer_unknown")),
+      }; // not one of the parent shares, ibc-hooks calls back on every transfer with the contract in the memo
+      TRANSFERS_IN_FLIGHT.remove(deps.storage, (&channel, sequence));
+
+      let error = match error {
+        Some(error) => error,
+        None => return Ok(Response::new().add_attribute("action", "transfer_acknowledged")),
+      };
+
+      if let Some(id) = &transfer.campaign {
+        if let Some(mut campaign) = CAMPAIGNS.may_load(deps.storage, id)? {
+          let old_balance = campaign.balance.clone();
+          add_coins(&mut campaign.balance, std::slice::from_ref(&transfer.amount));
+          update_campaign_balances(deps.storage, &old_balance, &campaign.balance)?;
+          CAMPAIGNS.save(deps.storage, id, &campaign)?;
+        }
+      } // the transfer module refunded the share to the contract, it goes back to the campaign it was forwarded from
+
+      let id = FAILED_TRANSFER_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+      FAILED_TRANSFER_COUNT.save(deps.storage, &id)?;
+      FAILED_TRANSFERS.save(deps.storage, id, &transfer)?;
+
+      let resp = Response::new()
+          .add_attribute("action", "transfer_failed")
+          .add_attribute("id", id.to_string())
+          .add_attribute("error", error);
+
+      Ok(resp)
+    }
+    // a refund of the default campaign needs no bookkeeping, it is part of the default balance as soon as it is back
+
+    pub fn reset_counter(deps: DepsMut, env: Env, counter: u64) -> Result<Response, ContractError> {
+      COUNTER.save(deps.storage, &counter, env.block.height)?;
+
+      let resp = Response::new()
+          .add_attribute("action", "sudo_reset")
+          .add_attribute("counter", counter.to_string());
+
+      Ok(resp)
+    }
+
+    pub fn replace_owner(deps: DepsMut, owner: String) -> Result<Response, ContractError> {
+      let mut state = STATE.load(deps.storage)?;
+      state.owner = deps.api.addr_validate(&owner)?;
+      STATE.save(deps.storage, &state)?;
+
+      let resp = Response::new()
+          .add_attribute("action", "sudo_replace_owner")
+          .add_attribute("owner", state.owner.as_str());
+
+      Ok(resp)
+    }
+
+    pub fn set_paused(deps: DepsMut, paused: bool) -> Result<Response, ContractError> {
+      PAUSED.save(deps.storage, &paused)?;
+
+      let resp = Response::new()
+          .add_attribute("action", if paused { "sudo_pause" } else { "sudo_unpause" });
+
+      Ok(resp)
+    }
+  }
+
+  // reply is called back by the chain with the result of a submessage we sent
+  pub mod reply {
+    use cosmwasm_std::{DepsMut, Reply, Response, StdError};
+
+    use crate::error::ContractError;
+    use crate::ics20;
+    use crate::state::{PENDING_TRANSFERS, TRANSFERS_IN_FLIGHT};
+
+    pub const HOOK_REPLY_ID: u64 = 1;
+    pub const TRANSFER_REPLY_ID: u64 = 2;
+
+    pub fn hook_failed(reply: Reply) -> Result<Response, ContractError> {
+      let mut resp = Response::new().add_attribute("action", "hook_failed");
+
+      if let Err(err) = reply.result.into_result() {
+        resp = resp.add_attribute("error", err);
+      } // hooks are sent with reply_on_error, so there is always an error here
+
+      Ok(resp)
+    }
+    // returning Ok drops the error, only the changes of the failed hook are reverted
+
+    pub fn transfer_sent(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+      let data = reply
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or_else(|| StdError::generic_err("Transfer reply without data"))?;
+      let sequence = ics20::transfer_sequence(&data)?;
+
+      let mut pending = PENDING_TRANSFERS.load(deps.storage)?;
+      if pending.is_empty() {
+        return Err(StdError::generic_err("No pending transfer for the reply").into());
+      }
+      let transfer = pending.remove(0);
+      PENDING_TRANSFERS.save(deps.storage, &pending)?;
+
+      TRANSFERS_IN_FLIGHT.save(deps.storage, (&transfer.channel_id, sequence), &transfer)?;
+
+      let resp = Response::new()
+          .add_attribute("action", "transfer_sent")
+          .add_attribute("channel_id", &transfer.channel_id)
+          .add_attribute("sequence", sequence.to_string());
+
+      Ok(resp)
+    }
+    // transfers are sent with reply_on_success, the sequence is what the ibc-hooks callback refers to them by
+  }
+
+  // execute is a write operation
+  // the ibc entry points of the counting-v1 channels, over which the counters are reported to a hub
+  pub mod ibc {
+    use cosmwasm_std::{
+      from_binary, to_binary, Deps, DepsMut, StdResult, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg,
+      IbcChannelOpenMsg, IbcChannelOpenResponse, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
+      IbcReceiveResponse, OverflowError, OverflowOperation, StdError,
+    };
+    use crate::error::ContractError;
+    use crate::msg::{CountingPacket, IbcAck};
+    use crate::state::{COUNTING_CHANNELS, HUB, PEERS, REMOTE_COUNTERS, REPORTED};
+
+    pub const COUNTING_VERSION: &str = "counting-v1";
+
+    pub fn channel_open(deps: Deps, msg: IbcChannelOpenMsg) -> Result<IbcChannelOpenResponse, ContractError> {
+      let channel = msg.channel();
+
+      if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::OrderedChannel);
+      }
+
+      let versions = [Some(channel.version.as_str()), msg.counterparty_version()];
+      for version in versions.into_iter().flatten() {
+        if version != COUNTING_VERSION {
+          return Err(ContractError::InvalidIbcVersion {
+            expected: COUNTING_VERSION.to_owned(),
+            version: version.to_owned(),
+          });
+        }
+      } // the counterparty version is only known on the try side of the handshake
+
+      ensure_peer(deps, channel)?;
+
+      Ok(None)
+    }
+
+    pub fn channel_connect(deps: DepsMut, msg: IbcChannelConnectMsg) -> Result<IbcBasicResponse, ContractError> {
+      let channel = msg.channel();
+
+      if let Some(version) = msg.counterparty_version() {
+        if version != COUNTING_VERSION {
+          return Err(ContractError::InvalidIbcVersion {
+            expected: COUNTING_VERSION.to_owned(),
+            version: version.to_owned(),
+          });
+        }
+      } // the init side learns the version of the other end only now
+
+      ensure_peer(deps.as_ref(), channel)?;
+      // the peer may have been removed while the handshake was going on
+
+      COUNTING_CHANNELS.save(deps.storage, &channel.endpoint.channel_id, &channel.counterparty_endpoint)?;
+
+      let resp = IbcBasicResponse::new()
+          .add_attribute("action", "channel_connect")
+          .add_attribute("channel_id", &channel.endpoint.channel_id);
+
+      Ok(resp)
+    }
+
+    fn ensure_peer(deps: Deps, channel: &IbcChannel) -> Result<(), ContractError> {
+      let connection_id = &channel.connection_id;
+      let port_id = &channel.counterparty_endpoint.port_id;
+
+      if !PEERS.has(deps.storage, (connection_id, port_id)) {
+        return Err(ContractError::UnknownPeer {
+          connection_id: connection_id.clone(),
+          port_id: port_id.clone(),
+        });
+      }
+      Ok(())
+    }
+    // any contract on any chain can start a handshake, only the ones the owner trusts get a channel
+
+    pub fn channel_close(deps: DepsMut, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse, ContractError> {
+      let channel_id = &msg.channel().endpoint.channel_id;
+      COUNTING_CHANNELS.remove(deps.storage, channel_id);
+
+      if matches!(HUB.may_load(deps.storage)?, Some(hub) if &hub.channel_id == channel_id) {
+        HUB.remove(deps.storage);
+      } // the owner has to pick a new channel to the hub
+
+      let resp = IbcBasicResponse::new()
+          .add_attribute("action", "channel_close")
+          .add_attribute("channel_id", channel_id);
+
+      Ok(resp)
+    }
+    // the counter reported over a closed channel stays in the remote counters, the donations behind it still happened
+
+    pub fn packet_receive(deps: DepsMut, msg: IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, ContractError> {
+      let channel_id = msg.packet.dest.channel_id;
+
+      let result = from_binary(&msg.packet.data).and_then(|packet: CountingPacket| match packet {
+        CountingPacket::CounterDelta { delta } => {
+          REMOTE_COUNTERS.update(deps.storage, &channel_id, |value| apply_delta(value.unwrap_or_default(), delta))
+        }
+      }); // an overflowing delta is refused in the acknowledgement like a bad packet, so the sender gets it back
+
+      let ack = match result {
+        Ok(value) => IbcAck::Result(to_binary(&value)?),
+        Err(err) => IbcAck::Error(err.to_string()),
+      };
+
+      let resp = IbcReceiveResponse::new()
+          .set_ack(to_binary(&ack)?)
+          .add_attribute("action", "receive_counter")
+          .add_attribute("channel_id", channel_id);
+
+      Ok(resp)
+    }
+
+    pub fn packet_ack(deps: DepsMut, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse, ContractError> {
+      match from_binary(&msg.acknowledgement.data) {
+        Ok(IbcAck::Error(error)) => revert_delta(deps, &msg.original_packet, &error),
+        _ => Ok(IbcBasicResponse::new().add_attribute("action", "counter_synced")),
+      } // anything but an explicit error means the packet was handled
+    }
+    // only the counting-v1 packets come back here, the ics20 transfers to the ibc parent are acknowledged to the transfer module
+
+    pub fn packet_timeout(deps: DepsMut, msg: IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, ContractError> {
+      revert_delta(deps, &msg.packet, "timeout")
+    }
+
+    pub fn apply_delta(value: u64, delta: i64) -> StdResult<u64> {
+      let (result, operation) = if delta >= 0 {
+        (value.checked_add(delta.unsigned_abs()), OverflowOperation::Add)
+      } else {
+        (value.checked_sub(delta.unsigned_abs()), OverflowOperation::Sub)
+      };
+      result.ok_or_else(|| OverflowError::new(operation, value, delta.unsigned_abs()).into())
+    }
+    // a counter can't go below zero, so a delta taking it there is as wrong as one overflowing it
+
+    fn revert_delta(deps: DepsMut, packet: &IbcPacket, error: &str) -> Result<IbcBasicResponse, ContractError> {
+      let data: CountingPacket = from_binary(&packet.data)?;
+      let CountingPacket::CounterDelta { delta } = data;
+
+      if !matches!(HUB.may_load(deps.storage)?, Some(hub) if hub.channel_id == packet.src.channel_id) {
+        return Ok(IbcBasicResponse::new().add_attribute("action", "counter_sync_failed"));
+      } // sent to a hub which was replaced since, the new one gets the whole counter anyway
+
+      let reported = REPORTED.may_load(deps.storage)?.unwrap_or_default();
+      let delta = delta
+        .checked_neg()
+        .ok_or_else(|| StdError::overflow(OverflowError::new(OverflowOperation::Sub, 0, delta)))?;
+      REPORTED.save(deps.storage, &apply_delta(reported, delta)?)?;
+      // the hub didn't count it, so the next sync sends it again
+
+      let resp = IbcBasicResponse::new()
+          .add_attribute("action", "counter_sync_failed")
+          .add_attribute("error", error);
+
+      Ok(resp)
+    }
+  }
+
+  pub mod exec {
+    use cosmwasm_std::{
+      Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, DistributionMsg, Empty, Env, IbcMsg, MessageInfo, Order, OverflowError, OverflowOperation, Response, StakingMsg,
+      StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg, to_binary, to_vec,
+    };
+
+    use super::reply::{HOOK_REPLY_ID, TRANSFER_REPLY_ID};
+    use crate::error::ContractError;
+    use crate::ics20;
+    use cw20::Cw20ExecuteMsg;
+
+    use crate::msg::{
+      AccessMode, CountingPacket, Cw721ExecMsg, DonationHookMsg, DonationLimits, DonorActivity, ExecMsg, Parent, GovernanceConfig, HookExecMsg, HubConfig, IbcParent,
+      LimitPolicy, Payout, Peer, PayoutAmount, PendingReceiverPolicy, ReceiptConfig, ReferralConfig, ReferralPayout, RewardRate, StakingConfig, Update, Voucher, VoucherPayload,
+    };
+    use crate::state::{
+      donor_list, Allowance, Campaign, Governance, Hook, ParentTransfer, Proposal, ReceiverPolicy, Receipts, Staking, ACCESS_MODE, ALLOWANCES, ALLOWLIST,
+      BLOCKLIST, CAMPAIGN_BALANCES, CAMPAIGNS, COUNTER, FAILED_TRANSFERS, MERKLE_ROOT, MERKLE_VERIFIED, PENDING_TRANSFERS,
+      COUNTING_CHANNELS, DEFAULT_CAMPAIGN, DONOR_ACTIVITY,
+      DONATIONS, GOVERNANCE, HOOKS, HUB, REPORTED, PAUSED, PEERS, PROPOSALS, PROPOSAL_COUNT, RECEIPTS, RECEIPTS_MINTED, RECEIVERS, RECEIVER_POLICY, REWARD_RATES,
+      REFERRALS, REFERRAL_ACCRUED, REFERRED_BY, REFERRERS, RESTORING, REWARD_TOKEN, STAKING, STATE, LIMITS, PARENT_DONATION, TOTAL_DONATED, USED_NONCES, VOTES,
+      VOUCHER_SIGNER,
+    };
+
+    use sha2::{Digest, Sha256};
+
+    const HOOK_GAS_LIMIT: u64 = 500_000;
+    // a hook running out of gas is handled like any other hook error, instead of using up the whole transaction
+
+     pub fn donate(
+      mut deps: DepsMut,
+      env: Env,
+      info: MessageInfo,
+      proof: Option<Vec<String>>,
+      referrer: Option<String>,
+     ) -> Result<Response, ContractError> {
+      // we use DepsMut to access contract/bc storage, and we use it to write to the storage
+      // we use Env to access the blockchain context, and we use it to get the current block height
+      // we use MessageInfo to access the message sender, and we use it to get the sender's address
+
+      check_access(deps.as_ref(), &info.sender)?;
+      check_merkle_proof(deps.branch(), &info.sender, proof)?;
+      // the access list and the merkle root are checked before anything is counted
+
+      let referral = check_referrer(deps.as_ref(), &info.sender, referrer)?;
+
+      let mut state = STATE.load(deps.storage)?;
+      let mut counter = COUNTER.load(deps.storage)?;
+      let mut resp = Response::new();
+      // setting the state to the value of the load function, which takes deps.storage as an argument
+      // setting the response to a new Response struct, which is a struct from cosmwasm_std that is used to build a response
+
+      let given = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == state.minimal_donation.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+
+      // |coin| is a closure, a function that can be passed as an argument to another function
+      let mut counted = state.minimal_donation.amount.is_zero() || info.funds.iter().any(|coin| {
+        coin.denom == state.minimal_donation.denom && coin.amount >= state.minimal_donation.amount
+      });
+
+      let mut limited = false;
+      if counted {
+        if let Some(reason) = check_limits(deps.branch(), &env, &info.sender, given, &state.minimal_donation.denom)? {
+          counted = false;
+          limited = true;
+          resp = resp.add_attribute("not_counted", reason);
+        }
+      } // a donation over the limits is either rejected, or kept without being counted nor credited to the donor
+
+      let mut paid_to_referrer = vec![];
+      if let (true, Some((referrer, config))) = (counted, referral) {
+        let (msg, paid) = referral_reward(deps.branch(), &env, &info, referrer, config)?;
+        resp = resp.add_messages(msg);
+        paid_to_referrer = paid;
+      } // only counted donations are rewarded, as they are the ones the referrer brought in
+
+      if counted {
+        counter += 1;
+      // if the minimal donation amount is zero, or if the funds in the message info are greater than or equal to the minimal donation amount, then we increment the counter by 1
+
+        if let Some(parent) = &mut state.donating_parent {
+          *parent -= 1;
+      // if the donating parent is not empty, then we decrement the parent by 1
+
+       if *parent == 0 {
+      let parent_donation = PARENT_DONATION.load(deps.storage)?;
+      *parent = parent_donation.donating_parent_period;
+      // if the parent is equal to 0, then we set the parent to the donating parent period, which is a field in the parent donation struct
+
+      // *parent is a dereference operator that allows you to access the value stored in the memory location pointed to by a reference.
+      // this is different from &parent, which is a reference operator that allows you to access the memory location of a value. 
+      // * is pointed to by a reference, & is a reference to a value.
+        // in this example, we are pointing to parent, which is a reference to a value which is a u64, and we are setting it to the donating parent period, which is a field in the parent donation struct
+
+  let mut balance = default_balance(deps.as_ref(), &env)?;
+  subtract_coins(&mut balance, &paid_to_referrer);
+  // the referral share sent with this donation is still on the contract balance
+
+  let funds: Vec<_> = balance
+    .into_iter()
+    .map(|mut coin| {
+      coin.amount = coin.amount * parent_donation.part;
+      coin
+    })
+    .collect();
+  // we are setting the funds to a vector of coins, which is a struct from cosmwasm_std that is used to represent a coin
+  // we then use the query_all_balances function from the querier, which is a struct from cosmwasm_std that is used to query the blockchain
+  // we use the contract address from the env struct, which is a struct from cosmwasm_std that is used to access the blockchain context
+  // we then use the into_iter function to iterate over the balances, which is a vector of coins
+  // we then use the map function to map over the balances, which is a vector of coins, we map using a closure, which is a function that can be passed as an argument to another function
+  // we then use the amount field from the coin struct, which is a struct from cosmwasm_std that is used to represent a coin
+  // we then use the part field from the parent donation struct, which is a field in the parent donation struct
+  // we multiply the amount by the part, which is a field in the parent donation struct and we set it to the amount field of the coin struct
+  // we then use the collect function to collect the results of the map function, which is a vector of coins
+
+    if let Some(ibc) = &parent_donation.ibc {
+      resp = resp.add_submessages(parent_transfer_msgs(deps.storage, &env, None, ibc, parent_donation.address.as_str(), funds)?);
+      // the parent is on another chain, its share goes through the ics20 channel
+    } else {
+    let msg = WasmMsg::Execute {
+      contract_addr: parent_donation.address.to_string(),
+      msg: to_binary(&ExecMsg::Donate { campaign: None, proof: None, referrer: None })?,
+      funds,
+    }; 
+    // here we just set the message to a WasmMsg::Execute struct, which is a struct from cosmwasm_std that is used to build a wasm message
+    // we set the contract address to the address field from the parent donation struct, which is a field in the parent donation struct
+    // we set the message to the donate message, which is a field in the exec message struct, which is a struct from the crate::msg module, this would be turned into a binary using the to_binary function from cosmwasm_std
+    // we set the funds to the funds vector, which is a vector of coins
+    // the purpose of msg is to send a message to another contract, which is the parent donation address, which is a field in the parent donation struct
+
+    resp = resp.add_message(msg);
+    }
+
+    resp = resp
+        .add_attribute("donated_to_parent", parent_donation.address.to_string());
+    // we add the message to the response, which is a struct from cosmwasm_std that is used to build a response
+    // we also add an attribute to the response, which is a struct from cosmwasm_std that is used to build a response
+      // the purpose of the attribute is to add a key-value pair to the response, which is a struct from cosmwasm_std that is used to build a response
+      // the key is donated_to_parent, and the value is the address field from the parent donation struct, which is a field in the parent donation struct
+    // finally, we convert the response to string and return it, assigning it to the response variable
+  }      
+}
+
+      STATE.save(deps.storage, &state)?;
+      // we save the state to the storage, which is a field in the deps struct, which is a struct from cosmwasm_std that is used to access the blockchain context
+      COUNTER.save(deps.storage, &counter, env.block.height)?;
+      // the counter is saved with the block height, so the value from before this block stays queryable
+
+      resp = resp.add_submessages(hook_msgs(
+        deps.as_ref(),
+        HookExecMsg::DonationHook(DonationHookMsg {
+          donor: info.sender.to_string(),
+          funds: info.funds.clone(),
+          counter,
+          campaign: None,
+        }),
+      )?);
+      // only counted donations are passed to the hooks
+
+      let donated = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == state.minimal_donation.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+      // the receipt is for the part of the donation which is counted
+
+      if let Some(msg) = mint_receipt(deps.branch(), &env, &info.sender, donated, &state.minimal_donation.denom, counter)? {
+        resp = resp.add_message(msg);
+      }
+      }
+
+  if let Some(msg) = reward_msg(deps.as_ref(), &info)? {
+    resp = resp.add_message(msg);
+  } // rewards are for everything given, also for donations too small to be counted
+
+  if let Some(msg) = delegate_msg(deps.as_ref(), &info)? {
+    resp = resp.add_message(msg);
+  } // part of the bond denom donation is staked right away, instead of sitting idle on the contract
+
+  if !given.is_zero() && !limited {
+    DONATIONS.update(deps.storage, &info.sender, env.block.height, |amount| -> StdResult<_> {
+      Ok(amount.unwrap_or_default() + given)
+    })?;
+    TOTAL_DONATED.update(deps.storage, env.block.height, |total| -> StdResult<_> {
+      Ok(total.unwrap_or_default() + given)
+    })?;
+  } // the donor ledger gives the voting weight in governance, only the minimal donation denom is recorded
+  // donations kept over the limits are not credited, otherwise they would still buy voting weight
+
+  resp = resp 
+    .add_attribute("action", "donate")
+    .add_attribute("sender", info.sender.to_string())
+    .add_attribute("counter", counter.to_string());
+    // adding attributes to the wasm event (only default event type that is emitted from every execution)
+      Ok(resp)
+      } 
+
+        pub fn reset(deps: DepsMut, env: Env, info: MessageInfo, counter: u64) -> Result<Response, ContractError> {
+         let state = STATE.load(deps.storage)?;
+         if info.sender != state.owner {
+           return Err(ContractError::Unauthorized {
+            owner: state.owner.to_string(),
+           });
+         } 
+
+         COUNTER.save(deps.storage, &counter, env.block.height)?;
+
+        let hooks = hook_msgs(
+          deps.as_ref(),
+          HookExecMsg::ResetHook(DonationHookMsg {
+            donor: info.sender.to_string(),
+            funds: vec![],
+            counter,
+            campaign: None,
+          }),
+        )?;
+
+        let resp = Response::new()
+            .add_submessages(hooks)
+            .add_attribute("action", "reset")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter", counter.to_string());
+
+                    Ok(resp)
+  } // reset counter to 0
+  // Withdraws unthouched
+
+  pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+       owner: owner.to_string(),
+      });
+    } // checking if the sender of the message is the owner/creator of the contract
+    // instead of returning a generic error (StdError::generic_error(...)), we return a custom error, which is a ContractError::Unauthorized.
+
+    ensure_withdrawals_allowed(deps.as_ref())?;
+    check_receiver(deps.as_ref(), &env, &info.sender)?;
+    // with the receiver policy enforced, the owner has to be on the allowlist too
+
+    let balance = default_balance(deps.as_ref(), &env)?;
+    // only the liquid funds, delegated donations have to be undelegated before they can be withdrawn
+    let hooks = withdraw_hook_msgs(deps.as_ref(), &info.sender, &balance)?;
+    let bank_msg = BankMsg::Send {
+      to_address: info.sender.to_string(),
+      amount: balance,
+    }; // queried all the balances of the contract, getting contract address using env and sent them to the sender of the message
+    // uses BankMsg::Send to send the balance to the sender of the message to the owner, which is the sender of the message
+
+    let resp = Response::new()
+    // add_message function, which takes a Cosmos SDK message as an argument and adds it to the Response object
+        .add_message(bank_msg) 
+        .add_submessages(hooks)
+        .add_attribute("action", "withdraw")
+        .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+  } 
+  // withdraw all funds from contract while adding a message to the response, submessages must be processed first, then the Response object is returned. If submessages are not processed, the Response object is not returned.
+  // uses bank_msg to send the balance to the sender of the message to the owner, which is the sender of the message, adding it to the response object
+
+  pub fn withdraw_to(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: String,
+    funds: Vec<Coin>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    let allowance = if info.sender == owner {
+      None
+    } else {
+      match ALLOWANCES.may_load(deps.storage, &info.sender)? {
+        Some(allowance) => Some(allowance),
+        None => {
+          return Err(ContractError::Unauthorized {
+            owner: owner.to_string(),
+          })
+        }
+      }
+    }; // checking if the sender of the message is the owner/creator of the contract, or a spender with an allowance
+
+    ensure_withdrawals_allowed(deps.as_ref())?;
+
+    let receiver = deps.api.addr_validate(&receiver)?;
+    check_receiver(deps.as_ref(), &env, &receiver)?;
+
+    let mut balance = default_balance(deps.as_ref(), &env)?; // assign balance to the funds of the default campaign
+
+    if !funds.is_empty() { // if funds is not empty
+      for coin in &mut balance {
+         // for each coin in balance
+        let limit = funds 
+        // limit is the amount of the coin in funds
+        .iter() 
+        // iterates through the funds
+        .find(|c| c.denom == coin.denom) 
+        // finds the coin with the same denom as the coin in funds
+        .map(|c| c.amount) 
+        // maps the amount of the coin in funds
+        .unwrap_or(Uint128::zero()); 
+        // if there is no coin with the same denom as the coin in funds, set the amount to zero
+
+        coin.amount = std::cmp::min(coin.amount, limit);
+        // set the amount to the minimum of the two amounts to prevent withdrawing more than the limit
+      }
+    } // if funds is not empty, iterate through the balance and find the coin with the same denom as the coin in funds, and set the amount to the minimum of the two amounts
+
+    if let Some(mut allowance) = allowance {
+      allowance.refresh(env.block.time);
+
+      for coin in balance.iter().filter(|c| !c.amount.is_zero()) {
+        if coin.amount > allowance.remaining(&coin.denom) {
+          return Err(ContractError::AllowanceExceeded {
+            denom: coin.denom.clone(),
+          });
+        }
+
+        match allowance.spent.iter_mut().find(|c| c.denom == coin.denom) {
+          Some(spent) => spent.amount += coin.amount,
+          None => allowance.spent.push(coin.clone()),
+        }
+      }
+
+      ALLOWANCES.save(deps.storage, &info.sender, &allowance)?;
+    } // spenders can only withdraw what is left of their allowance in the current period
+
+    let hooks = withdraw_hook_msgs(deps.as_ref(), &info.sender, &balance)?;
+    let bank_msg = BankMsg::Send {
+      to_address: receiver.to_string(),
+      amount: balance,
+    }; // uses BankMsg::Send to send the balance to the receiver, adding it to the response object
+
+    let resp = Response::new()
+        .add_message(bank_msg)
+        .add_submessages(hooks)
+        .add_attribute("action", "withdraw")
+        // withdraw in add_attribut is the action that allows the owner to withdraw funds from the contract
+        .add_attribute("sender", info.sender.as_str());
+        // sender is the owner of the contract, which is the sender of the message, which is the only one who can execute this function
+        Ok(resp) 
+    } 
+
+  pub fn withdraw_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    payouts: Vec<Payout>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    } // same owner check as in withdraw and withdraw_to
+
+    ensure_withdrawals_allowed(deps.as_ref())?;
+
+    let (msgs, total) = payout_msgs(deps.as_ref(), &env, payouts)?;
+
+    let resp = Response::new()
+        .add_messages(msgs)
+        .add_submessages(withdraw_hook_msgs(deps.as_ref(), &info.sender, &total)?)
+        .add_attribute("action", "withdraw_many")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+  // withdraw funds to many receivers at once, each payout is either exact funds or a share of the balance
+
+  fn payout_msgs(deps: Deps, env: &Env, payouts: Vec<Payout>) -> Result<(Vec<BankMsg>, Vec<Coin>), ContractError> {
+    let balance = default_balance(deps, env)?;
+    let mut total: Vec<Coin> = vec![];
+    let mut msgs = vec![];
+
+    for payout in payouts {
+      let receiver = deps.api.addr_validate(&payout.receiver)?;
+      check_receiver(deps, env, &receiver)?;
+      // every receiver is validated before anything is sent
+
+      let amount: Vec<Coin> = match payout.amount {
+        PayoutAmount::Funds { funds } => funds,
+        PayoutAmount::Share { share } => balance
+          .iter()
+          .map(|coin| Coin {
+            denom: coin.denom.clone(),
+            amount: coin.amount * share,
+          })
+          .collect(),
+      } // a share is taken from every denom of the balance, rounding down
+      .into_iter()
+      .filter(|coin| !coin.amount.is_zero())
+      .collect();
+
+      for coin in &amount {
+        match total.iter_mut().find(|c| c.denom == coin.denom) {
+          Some(c) => c.amount += coin.amount,
+          None => total.push(coin.clone()),
+        }
+      } // summing up what all the payouts request, per denom
+
+      if amount.is_empty() {
+        continue;
+      } // nothing to send to this receiver
+
+      msgs.push(BankMsg::Send {
+        to_address: receiver.to_string(),
+        amount,
+      });
+    }
+
+    for coin in &total {
+      let available = balance
+        .iter()
+        .find(|c| c.denom == coin.denom)
+        .map(|c| c.amount)
+        .unwrap_or_default();
+
+      if coin.amount > available {
+        return Err(ContractError::InsufficientFunds {
+          denom: coin.denom.clone(),
+        });
+      }
+    } // the payouts together can't exceed the contract balance
+
+    Ok((msgs, total))
+  }
+  // bank messages of the payouts and the total they send, shared by withdraw_many and the executed proposals
+
+  fn ensure_withdrawals_allowed(deps: Deps) -> Result<(), ContractError> {
+    let restricted = GOVERNANCE
+      .may_load(deps.storage)?
+      .map(|governance| governance.restrict_withdrawals)
+      .unwrap_or(false);
+
+    if restricted {
+      return Err(ContractError::WithdrawalsRestricted);
+    }
+    Ok(())
+  }
+
+  pub fn set_governance(
+    deps: DepsMut,
+    info: MessageInfo,
+    governance: Option<GovernanceConfig>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let current = GOVERNANCE.may_load(deps.storage)?;
+    if current.is_some_and(|current| current.loosened_by(governance.as_ref())) {
+      return Err(ContractError::GovernanceLocked);
+    }
+
+    save_governance(deps.storage, governance)?;
+    // proposals which are already open keep their own quorum and threshold
+
+    let resp = Response::new()
+        .add_attribute("action", "set_governance")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  fn save_governance(storage: &mut dyn Storage, governance: Option<GovernanceConfig>) -> StdResult<()> {
+    match governance {
+      Some(governance) => GOVERNANCE.save(
+        storage,
+        &Governance {
+          quorum: governance.quorum,
+          threshold: governance.threshold,
+          voting_period: governance.voting_period,
+          restrict_withdrawals: governance.restrict_withdrawals,
+        },
+      ),
+      None => {
+        GOVERNANCE.remove(storage);
+        Ok(())
+      }
+    }
+  }
+
+  pub fn propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    payouts: Vec<Payout>,
+    description: String,
+    governance: Update<GovernanceConfig>,
+  ) -> Result<Response, ContractError> {
+    let config = GOVERNANCE
+      .may_load(deps.storage)?
+      .ok_or(ContractError::GovernanceDisabled)?;
+
+    let owner = STATE.load(deps.storage)?.owner;
+    let donated = DONATIONS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    if info.sender != owner && donated.is_zero() {
+      return Err(ContractError::NoVotingPower);
+    } // anyone with a stake in the funds can propose how to spend them
+
+    for payout in &payouts {
+      deps.api.addr_validate(&payout.receiver)?;
+    } // the receiver policy and the balance are checked on execution, when the funds are sent
+
+    let id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_COUNT.save(deps.storage, &id)?;
+
+    let total_weight = TOTAL_DONATED
+      .may_load_at_height(deps.storage, env.block.height)?
+      .unwrap_or_default();
+    // weights are taken from before this block, so donating after the proposal gives no extra votes
+
+    PROPOSALS.save(
+      deps.storage,
+      id,
+      &Proposal {
+        proposer: info.sender.clone(),
+        description,
+        payouts,
+        start_height: env.block.height,
+        expires: env.block.time.plus_seconds(config.voting_period),
+        quorum: config.quorum,
+        threshold: config.threshold,
+        total_weight,
+        yes: Uint128::zero(),
+        no: Uint128::zero(),
+        executed: false,
+        governance,
+      },
+    )?;
+
+    let resp = Response::new()
+        .add_attribute("action", "propose")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("proposal_id", id.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn vote(deps: DepsMut, env: Env, info: MessageInfo, id: u64, yes: bool) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS.load(deps.storage, id)?;
+
+    if proposal.executed || env.block.time >= proposal.expires {
+      return Err(ContractError::VotingClosed { id });
+    }
+
+    if VOTES.has(deps.storage, (id, &info.sender)) {
+      return Err(ContractError::AlreadyVoted { id });
+    }
+
+    let weight = DONATIONS
+      .may_load_at_height(deps.storage, &info.sender, proposal.start_height)?
+      .unwrap_or_default();
+    if weight.is_zero() {
+      return Err(ContractError::NoVotingPower);
+    } // the weight is what the donor gave before the proposal was made
+
+    if yes {
+      proposal.yes += weight;
+    } else {
+      proposal.no += weight;
+    }
+
+    VOTES.save(deps.storage, (id, &info.sender), &yes)?;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    let resp = Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("weight", weight.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn execute_proposal(deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS.load(deps.storage, id)?;
+
+    if proposal.executed {
+      return Err(ContractError::ProposalExecuted { id });
+    }
+
+    if !proposal.passed() {
+      return Err(ContractError::ProposalNotPassed { id });
+    } // votes are only taken before the deadline, so this is the result at the deadline once it passes
+
+    let (msgs, total) = payout_msgs(deps.as_ref(), &env, proposal.payouts.clone())?;
+
+    match proposal.governance.clone() {
+      Update::Keep => (),
+      Update::Set(governance) => save_governance(deps.storage, Some(governance))?,
+      Update::Clear => save_governance(deps.storage, None)?,
+    } // the payouts are still checked against the config they were voted under
+
+    proposal.executed = true;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    let resp = Response::new()
+        .add_messages(msgs)
+        .add_submessages(withdraw_hook_msgs(deps.as_ref(), &info.sender, &total)?)
+        .add_attribute("action", "execute_proposal")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("proposal_id", id.to_string());
+
+    Ok(resp)
+  }
+  // sends the payouts of a passed proposal, this is the way out for the funds when withdrawals are restricted
+
+  pub fn add_receiver(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receiver: String,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let receiver = deps.api.addr_validate(&receiver)?;
+    let mut policy = RECEIVER_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    policy.refresh(env.block.time);
+    let delay = policy.delay;
+    let allowed_from = env.block.time.plus_seconds(delay);
+    RECEIVERS.save(deps.storage, &receiver, &allowed_from)?;
+    // the receiver can't be used until the delay passes, giving time to notice a compromised owner key
+
+    let resp = Response::new()
+        .add_attribute("action", "add_receiver")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("allowed_from", allowed_from.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn remove_receiver(
+    deps: DepsMut,
+    info: MessageInfo,
+    receiver: String,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let receiver = deps.api.addr_validate(&receiver)?;
+    RECEIVERS.remove(deps.storage, &receiver);
+
+    let resp = Response::new()
+        .add_attribute("action", "remove_receiver")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("receiver", receiver.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn set_receiver_policy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    enforce: bool,
+    delay: u64,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let mut policy = RECEIVER_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    policy.refresh(env.block.time);
+
+    let effective = if policy.loosened_by(enforce, delay) {
+      let effective = env.block.time.plus_seconds(policy.delay);
+      policy.pending = Some(PendingReceiverPolicy { enforce, delay, effective });
+      effective
+    } else {
+      policy = ReceiverPolicy { enforce, delay, pending: None };
+      env.block.time
+    }; // a stricter policy applies right away, and replaces a looser one still waiting
+    RECEIVER_POLICY.save(deps.storage, &policy)?;
+
+    let resp = Response::new()
+        .add_attribute("action", "set_receiver_policy")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("enforce", enforce.to_string())
+        .add_attribute("delay", delay.to_string())
+        .add_attribute("effective", effective.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    hook: String,
+    on_reset: bool,
+    on_withdraw: bool,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let hook = deps.api.addr_validate(&hook)?;
+    HOOKS.save(deps.storage, &hook, &Hook { on_reset, on_withdraw })?;
+    // adding a hook again only changes its subscriptions
+
+    let resp = Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("hook", hook.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn remove_hook(deps: DepsMut, info: MessageInfo, hook: String) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let hook = deps.api.addr_validate(&hook)?;
+    HOOKS.remove(deps.storage, &hook);
+
+    let resp = Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("hook", hook.as_str());
+
+    Ok(resp)
+  }
+
+  fn hook_msgs(deps: Deps, msg: HookExecMsg) -> StdResult<Vec<SubMsg>> {
+    let binary = to_binary(&msg)?;
+
+    HOOKS
+      .range(deps.storage, None, None, Order::Ascending)
+      .filter(|item| match (item, &msg) {
+        (Ok((_, hook)), HookExecMsg::ResetHook(_)) => hook.on_reset,
+        (Ok((_, hook)), HookExecMsg::WithdrawHook(_)) => hook.on_withdraw,
+        _ => true,
+      }) // every hook gets the donations, resets and withdrawals only go to the subscribed ones
+      .map(|item| {
+        let (hook, _) = item?;
+        let msg = WasmMsg::Execute {
+          contract_addr: hook.to_string(),
+          msg: binary.clone(),
+          funds: vec![],
+        };
+        Ok(SubMsg::reply_on_error(msg, HOOK_REPLY_ID).with_gas_limit(HOOK_GAS_LIMIT))
+      })
+      .collect()
+  }
+  // every hook is a separate submessage replying on error, so a failing hook is reverted alone and doesn't fail the whole message
+
+  fn withdraw_hook_msgs(deps: Deps, sender: &Addr, funds: &[Coin]) -> StdResult<Vec<SubMsg>> {
+    hook_msgs(
+      deps,
+      HookExecMsg::WithdrawHook(DonationHookMsg {
+        donor: sender.to_string(),
+        funds: funds.to_vec(),
+        counter: COUNTER.load(deps.storage)?,
+        campaign: None,
+      }),
+    )
+  }
+
+  pub fn set_receipts(
+    deps: DepsMut,
+    info: MessageInfo,
+    receipts: Option<ReceiptConfig>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let mut resp = Response::new()
+        .add_attribute("action", "set_receipts")
+        .add_attribute("sender", info.sender.as_str());
+
+    match receipts {
+      Some(receipts) => {
+        let cw721 = deps.api.addr_validate(&receipts.cw721)?;
+        resp = resp.add_attribute("cw721", cw721.as_str());
+        RECEIPTS.save(
+          deps.storage,
+          &Receipts {
+            cw721,
+            token_uri: receipts.token_uri,
+            threshold: receipts.threshold,
+          },
+        )?;
+      }
+      None => RECEIPTS.remove(deps.storage),
+    }
+
+    Ok(resp)
+  }
+
+  fn mint_receipt(
+    deps: DepsMut,
+    env: &Env,
+    donor: &Addr,
+    amount: Uint128,
+    denom: &str,
+    counter: u64,
+  ) -> StdResult<Option<WasmMsg>> {
+    let receipts = match RECEIPTS.may_load(deps.storage)? {
+      Some(receipts) => receipts,
+      None => return Ok(None),
+    }; // receipts are not configured
+
+    if matches!(receipts.threshold, Some(threshold) if amount < threshold) {
+      return Ok(None);
+    } // too small for a receipt
+
+    let token_id = RECEIPTS_MINTED.may_load(deps.storage)?.unwrap_or_default() + 1;
+    RECEIPTS_MINTED.save(deps.storage, &token_id)?;
+
+    let token_uri = receipts
+      .token_uri
+      .replace("{donor}", donor.as_str())
+      .replace("{amount}", &amount.to_string())
+      .replace("{denom}", denom)
+      .replace("{counter}", &counter.to_string())
+      .replace("{time}", &env.block.time.seconds().to_string());
+
+    let msg = WasmMsg::Execute {
+      contract_addr: receipts.cw721.to_string(),
+      msg: to_binary(&Cw721ExecMsg::Mint {
+        token_id: token_id.to_string(),
+        owner: donor.to_string(),
+        token_uri: Some(token_uri),
+        extension: None,
+      })?,
+      funds: vec![],
+    };
+
+    Ok(Some(msg))
+  }
+  // a failing mint fails the donation too, so no donation is counted without its receipt
+
+  pub fn set_reward_rates(
+    deps: DepsMut,
+    info: MessageInfo,
+    rates: Vec<RewardRate>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    for rate in rates {
+      if rate.rate.is_zero() {
+        REWARD_RATES.remove(deps.storage, &rate.denom);
+      } else {
+        REWARD_RATES.save(deps.storage, &rate.denom, &rate.rate)?;
+      }
+    } // denoms not in the message keep their rates
+
+    let resp = Response::new()
+        .add_attribute("action", "set_reward_rates")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  fn reward_msg(deps: Deps, info: &MessageInfo) -> StdResult<Option<WasmMsg>> {
+    let token = match REWARD_TOKEN.may_load(deps.storage)? {
+      Some(token) => token,
+      None => return Ok(None),
+    };
+
+    let mut amount = Uint128::zero();
+    for coin in &info.funds {
+      if let Some(rate) = REWARD_RATES.may_load(deps.storage, &coin.denom)? {
+        amount += coin.amount * rate;
+      }
+    } // denoms without a rate are not rewarded
+
+    if amount.is_zero() {
+      return Ok(None);
+    } // cw20 refuses to mint nothing
+
+    let msg = WasmMsg::Execute {
+      contract_addr: token.to_string(),
+      msg: to_binary(&Cw20ExecuteMsg::Mint {
+        recipient: info.sender.to_string(),
+        amount,
+      })?,
+      funds: vec![],
+    };
+
+    Ok(Some(msg))
+  }
+  // supporter tokens for the donor, in proportion to the donated funds
+
+  pub fn default_balance(deps: Deps, env: &Env) -> StdResult<Vec<Coin>> {
+    let mut balance = deps.querier.query_all_balances(&env.contract.address)?;
+
+    subtract_coins(&mut balance, &CAMPAIGN_BALANCES.may_load(deps.storage)?.unwrap_or_default());
+    subtract_coins(&mut balance, &REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default());
+
+    Ok(balance)
+  }
+  // the liquid funds of the default campaign, what is left of the contract balance after the other campaigns and the referrers took theirs
+
+  pub fn update_campaign_balances(storage: &mut dyn Storage, old: &[Coin], new: &[Coin]) -> StdResult<()> {
+    let mut total = CAMPAIGN_BALANCES.may_load(storage)?.unwrap_or_default();
+    subtract_coins(&mut total, old);
+    add_coins(&mut total, new);
+    CAMPAIGN_BALANCES.save(storage, &total)
+  }
+  // replaces the old balance of a campaign with the new one in the running total, called on every change of a campaign balance
+
+  fn subtract_coins(balance: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+      if let Some(c) = balance.iter_mut().find(|c| c.denom == coin.denom) {
+        c.amount = c.amount.saturating_sub(coin.amount);
+      }
+    }
+    balance.retain(|coin| !coin.amount.is_zero());
+  }
+
+  pub fn add_coins(balance: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+      match balance.iter_mut().find(|c| c.denom == coin.denom) {
+        Some(c) => c.amount += coin.amount,
+        None => balance.push(coin.clone()),
+      }
+    }
+  }
+
+  // the referrer passed with a donation, None if there is none
+  fn check_referrer(deps: Deps, donor: &Addr, referrer: Option<String>) -> Result<Option<(Addr, ReferralConfig)>, ContractError> {
+    let (referrer, config) = match (referrer, REFERRALS.may_load(deps.storage)?) {
+      (Some(referrer), Some(config)) => (deps.api.addr_validate(&referrer)?, config),
+      (Some(_), None) => {
+        return Err(ContractError::InvalidReferrer {
+          reason: "referrals are off".to_owned(),
+        })
+      }
+      (None, _) => return Ok(None),
+    }; // a referrer which would not be paid is rejected, like on the other campaigns
+
+    if referrer == *donor {
+      return Err(ContractError::InvalidReferrer {
+        reason: "donors can't refer themselves".to_owned(),
+      });
+    }
+
+    match REFERRED_BY.may_load(deps.storage, donor)? {
+      Some(current) if current != referrer => {
+        return Err(ContractError::InvalidReferrer {
+          reason: format!("{} was already referred by {}", donor, current),
+        })
+      }
+      Some(_) => return Ok(Some((referrer, config))),
+      None => (),
+    } // a donor keeps the referrer of its first referred donation
+
+    let mut current = referrer.clone();
+    while let Some(next) = REFERRED_BY.may_load(deps.storage, &current)? {
+      if next == *donor {
+        return Err(ContractError::InvalidReferrer {
+          reason: format!("{} is already up the referral chain of {}", donor, referrer),
+        });
+      }
+      current = next;
+    } // the referrers form a tree, so going up from the new referrer ends without coming back to it
+
+    Ok(Some((referrer, config)))
+  }
+
+  // pays or accrues the referral share of a counted donation, and returns what was sent right away
+  fn referral_reward(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    referrer: Addr,
+    config: ReferralConfig,
+  ) -> Result<(Vec<BankMsg>, Vec<Coin>), ContractError> {
+    let mut stats = REFERRERS.may_load(deps.storage, &referrer)?.unwrap_or_default();
+
+    if !REFERRED_BY.has(deps.storage, &info.sender) {
+      REFERRED_BY.save(deps.storage, &info.sender, &referrer)?;
+      stats.donors += 1;
+    }
+    stats.donations += 1;
+
+    let reward: Vec<_> = info
+      .funds
+      .iter()
+      .map(|coin| Coin {
+        denom: coin.denom.clone(),
+        amount: coin.amount * config.share,
+      })
+      .filter(|coin| !coin.amount.is_zero())
+      .collect();
+    add_coins(&mut stats.earned, &reward);
+
+    let payable = ensure_withdrawals_allowed(deps.as_ref()).is_ok() && check_receiver(deps.as_ref(), env, &referrer).is_ok();
+    // funds leave the contract only to receivers the policy allows and while withdrawals are not restricted
+
+    let (msgs, paid) = match config.payout {
+      ReferralPayout::Immediate if !reward.is_empty() && payable => {
+        let msg = BankMsg::Send {
+          to_address: referrer.to_string(),
+          amount: reward.clone(),
+        };
+        (vec![msg], reward)
+      }
+      ReferralPayout::Immediate if reward.is_empty() => (vec![], vec![]),
+      ReferralPayout::Immediate | ReferralPayout::Accrue => {
+        add_coins(&mut stats.accrued, &reward);
+        let mut accrued = REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default();
+        add_coins(&mut accrued, &reward);
+        REFERRAL_ACCRUED.save(deps.storage, &accrued)?;
+        (vec![], vec![])
+      } // accrued rewards stay on the contract, out of the default campaign funds until claimed
+      // an immediate reward the referrer can't receive yet is accrued instead, so the donation still goes through
+    };
+
+    REFERRERS.save(deps.storage, &referrer, &stats)?;
+    Ok((msgs, paid))
+  }
+
+  pub fn set_referrals(deps: DepsMut, info: MessageInfo, referrals: Option<ReferralConfig>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match referrals {
+      Some(referrals) if referrals.share > Decimal::one() => return Err(ContractError::InvalidReferralShare),
+      Some(referrals) => {
+        REFERRALS.save(deps.storage, &referrals)?;
+        check_split(deps.as_ref())?;
+      }
+      None => REFERRALS.remove(deps.storage),
+    }
+
+    let resp = Response::new()
+        .add_attribute("action", "set_referrals")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn claim_referral_rewards(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_withdrawals_allowed(deps.as_ref())?;
+    check_receiver(deps.as_ref(), &env, &info.sender)?;
+
+    let mut stats = REFERRERS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let claimed = std::mem::take(&mut stats.accrued);
+
+    let mut resp = Response::new()
+        .add_attribute("action", "claim_referral_rewards")
+        .add_attribute("sender", info.sender.as_str());
+
+    if !claimed.is_empty() {
+      REFERRERS.save(deps.storage, &info.sender, &stats)?;
+
+      let mut accrued = REFERRAL_ACCRUED.may_load(deps.storage)?.unwrap_or_default();
+      subtract_coins(&mut accrued, &claimed);
+      REFERRAL_ACCRUED.save(deps.storage, &accrued)?;
+
+      resp = resp.add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: claimed,
+      });
+    } // nothing to send, when the referrer claimed everything already
+
+    Ok(resp)
+  }
+
+  pub fn create_campaign(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    minimal_donation: Coin,
+    owner: Option<String>,
+    parent: Option<Parent>,
+  ) -> Result<Response, ContractError> {
+    let contract_owner = STATE.load(deps.storage)?.owner;
+    if info.sender != contract_owner {
+      return Err(ContractError::Unauthorized {
+        owner: contract_owner.to_string(),
+      });
+    } // every campaign is stored on the contract, so creating them is not left open to anyone
+
+    if id == DEFAULT_CAMPAIGN || CAMPAIGNS.has(deps.storage, &id) {
+      return Err(ContractError::CampaignExists { id });
+    }
+
+    let owner = match owner {
+      Some(owner) => deps.api.addr_validate(&owner)?,
+      None => info.sender.clone(),
+    };
+
+    let campaign = Campaign {
+      owner,
+      minimal_donation,
+      counter: 0,
+      donating_parent: parent.as_ref().map(|parent| parent.donating_period),
+      parent: parent.map(|parent| super::parent_donation(deps.as_ref(), parent)).transpose()?,
+      balance: vec![],
+    }; // same parent setup as on instantiation
+    CAMPAIGNS.save(deps.storage, &id, &campaign)?;
+
+    let resp = Response::new()
+        .add_attribute("action", "create_campaign")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("campaign", id)
+        .add_attribute("owner", campaign.owner.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn donate_campaign(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    proof: Option<Vec<String>>,
+    referrer: Option<String>,
+  ) -> Result<Response, ContractError> {
+    if referrer.is_some() {
+      return Err(ContractError::InvalidReferrer {
+        reason: "referrals are paid on the default campaign only".to_owned(),
+      });
+    } // rejected like a referrer passed while referrals are off, instead of keeping a donation the referrer is not paid for
+
+    check_access(deps.as_ref(), &info.sender)?;
+    check_merkle_proof(deps.branch(), &info.sender, proof)?;
+
+    let mut campaign = CAMPAIGNS
+      .may_load(deps.storage, &id)?
+      .ok_or_else(|| ContractError::UnknownCampaign { id: id.clone() })?;
+    let old_balance = campaign.balance.clone();
+    let mut resp = Response::new();
+
+    for coin in &info.funds {
+      match campaign.balance.iter_mut().find(|c| c.denom == coin.denom) {
+        Some(c) => c.amount += coin.amount,
+        None => campaign.balance.push(coin.clone()),
+      }
+    } // everything given is kept for the campaign, also donations too small to be counted
+
+    let minimal_donation = &campaign.minimal_donation;
+    let mut counted = minimal_donation.amount.is_zero() || info.funds.iter().any(|coin| {
+      coin.denom == minimal_donation.denom && coin.amount >= minimal_donation.amount
+    });
+
+    if counted {
+      let denom = STATE.load(deps.storage)?.minimal_donation.denom;
+      let given = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+      if let Some(reason) = check_limits(deps.branch(), &env, &info.sender, given, &denom)? {
+        counted = false;
+        resp = resp.add_attribute("not_counted", reason);
+      }
+    } // the limits are per donor over all the campaigns, so spreading the donations over them doesn't get around it
+    // the total is kept in the denom of the default campaign, a donation in another denom only counts against the window
+
+    if counted {
+      campaign.counter += 1;
+
+      if let (Some(countdown), Some(parent)) = (&mut campaign.donating_parent, &campaign.parent) {
+        *countdown -= 1;
+
+        if *countdown == 0 {
+          *countdown = parent.donating_parent_period;
+
+          let mut funds = vec![];
+          for coin in &mut campaign.balance {
+            let share = coin.amount * parent.part;
+            coin.amount -= share;
+            funds.push(Coin {
+              denom: coin.denom.clone(),
+              amount: share,
+            });
+          } // the parent gets its part of what the campaign collected, not of the whole contract balance
+
+          resp = match &parent.ibc {
+            Some(ibc) => resp.add_submessages(parent_transfer_msgs(deps.storage, &env, Some(&id), ibc, parent.address.as_str(), funds)?),
+            None => resp.add_message(WasmMsg::Execute {
+              contract_addr: parent.address.to_string(),
+              msg: to_binary(&ExecMsg::Donate { campaign: None, proof: None, referrer: None })?,
+              funds,
+            }),
+          };
+          resp = resp.add_attribute("donated_to_parent", parent.address.to_string());
+        }
+      }
+    }
+
+    campaign.balance.retain(|coin| !coin.amount.is_zero());
+    update_campaign_balances(deps.storage, &old_balance, &campaign.balance)?;
+    CAMPAIGNS.save(deps.storage, &id, &campaign)?;
+
+    resp = resp
+      .add_attribute("action", "donate")
+      .add_attribute("sender", info.sender.as_str())
+      .add_attribute("campaign", id)
+      .add_attribute("counter", campaign.counter.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn reset_campaign(deps: DepsMut, info: MessageInfo, id: String, counter: u64) -> Result<Response, ContractError> {
+    let mut campaign = CAMPAIGNS
+      .may_load(deps.storage, &id)?
+      .ok_or_else(|| ContractError::UnknownCampaign { id: id.clone() })?;
+    if info.sender != campaign.owner {
+      return Err(ContractError::Unauthorized {
+        owner: campaign.owner.to_string(),
+      });
+    } // only the owner of the campaign, not the owner of the contract
+
+    campaign.counter = counter;
+    CAMPAIGNS.save(deps.storage, &id, &campaign)?;
+
+    let hooks = hook_msgs(
+      deps.as_ref(),
+      HookExecMsg::ResetHook(DonationHookMsg {
+        donor: info.sender.to_string(),
+        funds: vec![],
+        counter,
+        campaign: Some(id.clone()),
+      }),
+    )?;
+
+    let resp = Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "reset")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("campaign", id)
+        .add_attribute("counter", counter.to_string());
+
+    Ok(resp)
+  }
+
+  pub fn withdraw_campaign(deps: DepsMut, env: Env, info: MessageInfo, id: String) -> Result<Response, ContractError> {
+    let mut campaign = CAMPAIGNS
+      .may_load(deps.storage, &id)?
+      .ok_or_else(|| ContractError::UnknownCampaign { id: id.clone() })?;
+    if info.sender != campaign.owner {
+      return Err(ContractError::Unauthorized {
+        owner: campaign.owner.to_string(),
+      });
+    }
+
+    ensure_withdrawals_allowed(deps.as_ref())?;
+    check_receiver(deps.as_ref(), &env, &info.sender)?;
+    // same gate as the default campaign, the owner of a campaign is often the owner of the contract
+
+    let balance = std::mem::take(&mut campaign.balance);
+    update_campaign_balances(deps.storage, &balance, &[])?;
+    CAMPAIGNS.save(deps.storage, &id, &campaign)?;
+
+    let hooks = hook_msgs(
+      deps.as_ref(),
+      HookExecMsg::WithdrawHook(DonationHookMsg {
+        donor: info.sender.to_string(),
+        funds: balance.clone(),
+        counter: campaign.counter,
+        campaign: Some(id.clone()),
+      }),
+    )?;
+
+    let mut resp = Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "withdraw")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("campaign", id);
+
+    if !balance.is_empty() {
+      resp = resp.add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: balance,
+      });
+    } // the bank module refuses to send nothing
+
+    Ok(resp)
+  }
+
+  fn parent_transfer_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    campaign: Option<&str>,
+    ibc: &IbcParent,
+    to_address: &str,
+    funds: Vec<Coin>,
+  ) -> StdResult<Vec<SubMsg>> {
+    let mut pending = PENDING_TRANSFERS.may_load(storage)?.unwrap_or_default();
+    let timeout = env.block.time.plus_seconds(ibc.timeout);
+
+    let msgs = funds
+      .into_iter()
+      .filter(|coin| !coin.amount.is_zero())
+      .map(|amount| {
+        let msg = ics20::transfer_msg(&ibc.channel_id, env.contract.address.as_str(), to_address, &amount, timeout);
+        pending.push(ParentTransfer {
+          campaign: campaign.map(str::to_owned),
+          channel_id: ibc.channel_id.clone(),
+          receiver: to_address.to_owned(),
+          amount,
+        });
+        SubMsg::reply_on_success(msg, TRANSFER_REPLY_ID)
+      })
+      .collect();
+
+    PENDING_TRANSFERS.save(storage, &pending)?;
+    Ok(msgs)
+  }
+  // a transfer carries a single coin, so every denom goes in its own packet, and empty ones are not sent at all
+  // the replies come in the order the transfers were sent, each of them takes the first pending transfer
+
+  pub fn retry_transfer(deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let transfer = FAILED_TRANSFERS
+      .may_load(deps.storage, id)?
+      .ok_or(ContractError::UnknownTransfer { id })?;
+
+    let (address, ibc) = match &transfer.campaign {
+      Some(campaign_id) => {
+        let mut campaign = CAMPAIGNS
+          .may_load(deps.storage, campaign_id)?
+          .ok_or_else(|| ContractError::UnknownCampaign { id: campaign_id.clone() })?;
+        let parent = campaign.parent.clone().ok_or(ContractError::NoIbcParent)?;
+        let ibc = parent.ibc.ok_or(ContractError::NoIbcParent)?;
+
+        let old_balance = campaign.balance.clone();
+        take_coin(&mut campaign.balance, &transfer.amount)?;
+        update_campaign_balances(deps.storage, &old_balance, &campaign.balance)?;
+        CAMPAIGNS.save(deps.storage, campaign_id, &campaign)?;
+        (parent.address, ibc)
+      }
+      None => {
+        let parent = PARENT_DONATION.may_load(deps.storage)?.ok_or(ContractError::NoIbcParent)?;
+        let ibc = parent.ibc.ok_or(ContractError::NoIbcParent)?;
+
+        take_coin(&mut default_balance(deps.as_ref(), &env)?, &transfer.amount)?;
+        (parent.address, ibc)
+      }
+    }; // the refund goes to the parent the campaign has now, so a fixed wrong address or channel gets it this time
+    // and it is taken from the campaign again, unless its owner withdrew it in the meantime
+
+    FAILED_TRANSFERS.remove(deps.storage, id);
+    // a transfer failing again is recorded under a new id
+
+    let msgs = parent_transfer_msgs(deps.storage, &env, transfer.campaign.as_deref(), &ibc, address.as_str(), vec![transfer.amount])?;
+
+    let resp = Response::new()
+        .add_submessages(msgs)
+        .add_attribute("action", "retry_transfer")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("id", id.to_string());
+
+    Ok(resp)
+  }
+
+  fn take_coin(balance: &mut [Coin], coin: &Coin) -> Result<(), ContractError> {
+    let insufficient = || ContractError::InsufficientFunds {
+      denom: coin.denom.clone(),
+    };
+    let available = balance.iter_mut().find(|c| c.denom == coin.denom).ok_or_else(insufficient)?;
+    available.amount = available.amount.checked_sub(coin.amount).map_err(|_| insufficient())?;
+    Ok(())
+  }
+
+  pub fn set_hub(deps: DepsMut, info: MessageInfo, hub: Option<HubConfig>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match hub {
+      Some(hub) => {
+        if !COUNTING_CHANNELS.has(deps.storage, &hub.channel_id) {
+          return Err(ContractError::UnknownChannel {
+            channel_id: hub.channel_id,
+          });
+        }
+        HUB.save(deps.storage, &hub)?;
+        REPORTED.save(deps.storage, &0)?;
+        // a new hub knows nothing yet, so the next sync reports the whole counter
+      }
+      None => HUB.remove(deps.storage),
+    }
+
+    let resp = Response::new()
+        .add_attribute("action", "set_hub")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn add_peer(deps: DepsMut, info: MessageInfo, peer: Peer) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    PEERS.save(deps.storage, (&peer.connection_id, &peer.port_id), &Empty {})?;
+    // ids on another chain can't be validated here, a wrong one just never matches a handshake
+
+    let resp = Response::new()
+        .add_attribute("action", "add_peer")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("connection_id", peer.connection_id)
+        .add_attribute("port_id", peer.port_id);
+
+    Ok(resp)
+  }
+
+  pub fn remove_peer(deps: DepsMut, info: MessageInfo, peer: Peer) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    PEERS.remove(deps.storage, (&peer.connection_id, &peer.port_id));
+
+    let resp = Response::new()
+        .add_attribute("action", "remove_peer")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("connection_id", peer.connection_id)
+        .add_attribute("port_id", peer.port_id);
+
+    Ok(resp)
+  }
+
+  pub fn sync_counter(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let hub = HUB.may_load(deps.storage)?.ok_or(ContractError::NoHub)?;
+
+    let counter = COUNTER.load(deps.storage)?;
+    let reported = REPORTED.may_load(deps.storage)?.unwrap_or_default();
+    let delta = i64::try_from(i128::from(counter) - i128::from(reported))
+      .map_err(|_| StdError::overflow(OverflowError::new(OverflowOperation::Sub, counter, reported)))?;
+    // a change too big for the packet is refused, instead of wrapping around into a wrong delta
+
+    let mut resp = Response::new()
+        .add_attribute("action", "sync_counter")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("delta", delta.to_string());
+
+    if delta == 0 {
+      return Ok(resp);
+    } // nothing changed since the last report
+
+    REPORTED.save(deps.storage, &counter)?;
+    // counted as reported right away, a failed packet gives the delta back in the ack
+
+    let msg = IbcMsg::SendPacket {
+      channel_id: hub.channel_id,
+      data: to_binary(&CountingPacket::CounterDelta { delta })?,
+      timeout: env.block.time.plus_seconds(hub.timeout).into(),
+    };
+    resp = resp.add_message(msg);
+
+    Ok(resp)
+  }
+
+  // checks a donation which would be counted against the limits, Some is the reason it is not counted after all
+  fn check_limits(deps: DepsMut, env: &Env, donor: &Addr, given: Uint128, denom: &str) -> Result<Option<String>, ContractError> {
+    let limits = LIMITS.may_load(deps.storage)?;
+    let mut activity = DONOR_ACTIVITY.may_load(deps.storage, donor)?.unwrap_or(DonorActivity {
+      window_start: env.block.time,
+      donations: 0,
+      total: Uint128::zero(),
+      last_height: None,
+    });
+
+    if let Some(limits) = limits {
+      if let Some(window) = &limits.window {
+        if env.block.time >= activity.window_start.plus_seconds(window.period) {
+          activity.window_start = env.block.time;
+          activity.donations = 0;
+        }
+      } // the window starts with the first counted donation after the previous one passed
+
+      let too_soon = activity
+        .last_height
+        .is_some_and(|height| env.block.height < height.saturating_add(limits.min_blocks));
+      let window_full = limits.window.as_ref().filter(|window| activity.donations >= window.max_donations);
+      let over_total = limits.max_total.filter(|max| activity.total.saturating_add(given) > *max);
+
+      let reason = match (too_soon, window_full, over_total) {
+        (true, _, _) => Some(format!("{} blocks have to pass between counted donations", limits.min_blocks)),
+        (_, Some(window), _) => Some(format!("at most {} donations are counted every {} seconds", window.max_donations, window.period)),
+        (_, _, Some(max)) => Some(format!("at most {}{} is counted per donor", max, denom)),
+        _ => None,
+      };
+
+      if let Some(reason) = reason {
+        return match limits.policy {
+          LimitPolicy::Reject => Err(ContractError::RateLimited { reason }),
+          LimitPolicy::KeepUncounted => Ok(Some(reason)),
+        };
+      } // a donation which is not counted doesn't use up the limits
+    }
+
+    activity.donations += 1;
+    activity.total += given;
+    activity.last_height = Some(env.block.height);
+    DONOR_ACTIVITY.save(deps.storage, donor, &activity)?;
+
+    Ok(None)
+  }
+
+  pub fn validate_limits(limits: &DonationLimits) -> Result<(), ContractError> {
+    match &limits.window {
+      Some(window) if window.period == 0 => Err(ContractError::InvalidLimits {
+        reason: "the window period can't be 0".to_owned(),
+      }),
+      Some(window) if window.max_donations == 0 => Err(ContractError::InvalidLimits {
+        reason: "the window has to count at least one donation".to_owned(),
+      }),
+      _ => Ok(()),
+    }
+  } // a zero period would restart the window on every donation, zero donations would never count anything
+
+  fn check_access(deps: Deps, donor: &Addr) -> Result<(), ContractError> {
+    let allowed = match ACCESS_MODE.may_load(deps.storage)? {
+      Some(AccessMode::Allowlist) => ALLOWLIST.has(deps.storage, donor),
+      Some(AccessMode::Blocklist) => !BLOCKLIST.has(deps.storage, donor),
+      None => true,
+    };
+
+    if !allowed {
+      return Err(ContractError::DonorNotAllowed {
+        donor: donor.to_string(),
+      });
+    }
+    Ok(())
+  }
+
+  pub fn donate_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    beneficiary: String,
+    voucher: Voucher,
+    proof: Option<Vec<String>>,
+  ) -> Result<Response, ContractError> {
+    let signer = VOUCHER_SIGNER
+      .may_load(deps.storage)?
+      .ok_or(ContractError::NoVoucherSigner)?;
+
+    if env.block.time > voucher.expiry {
+      return Err(ContractError::InvalidVoucher {
+        reason: "expired".to_owned(),
+      });
+    }
+
+    if USED_NONCES.has(deps.storage, voucher.nonce) {
+      return Err(ContractError::VoucherUsed { nonce: voucher.nonce });
+    }
+
+    if info.funds != [voucher.amount.clone()] {
+      return Err(ContractError::InvalidVoucher {
+        reason: format!("funds sent have to be exactly {}", voucher.amount),
+      });
+    } // the signer vouches for this exact amount, nothing more can be credited with it
+
+    let beneficiary = deps.api.addr_validate(&beneficiary)?;
+    let payload = to_vec(&VoucherPayload {
+      contract: env.contract.address.to_string(),
+      beneficiary: beneficiary.to_string(),
+      amount: voucher.amount,
+      nonce: voucher.nonce,
+      expiry: voucher.expiry,
+    })?;
+    let hash = Sha256::digest(payload);
+
+    let valid = deps
+      .api
+      .secp256k1_verify(&hash, &voucher.signature, &signer)
+      .map_err(StdError::from)?;
+    if !valid {
+      return Err(ContractError::InvalidVoucher {
+        reason: "not signed by the voucher signer".to_owned(),
+      });
+    }
+
+    USED_NONCES.save(deps.storage, voucher.nonce, &Empty {})?;
+
+    let donor = MessageInfo {
+      sender: beneficiary,
+      funds: info.funds,
+    };
+    let resp = donate(deps, env, donor, proof, None)?.add_attribute("donated_by", info.sender.as_str());
+    // donated as if the beneficiary sent it, so the counter, the ledgers, the hooks and the receipts credit the beneficiary
+    // the beneficiary still has to pass the access list, and be verified against the merkle root with the proof passed along
+
+    Ok(resp)
+  }
+
+  pub fn set_voucher_signer(deps: DepsMut, info: MessageInfo, pubkey: Option<Binary>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match pubkey {
+      Some(pubkey) if pubkey.len() == 33 || pubkey.len() == 65 => VOUCHER_SIGNER.save(deps.storage, &pubkey)?,
+      Some(_) => return Err(ContractError::InvalidPubkey),
+      None => VOUCHER_SIGNER.remove(deps.storage),
+    } // compressed or uncompressed keys, as accepted by secp256k1_verify
+
+    let resp = Response::new()
+        .add_attribute("action", "set_voucher_signer")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  // the donor has to be a leaf of the merkle tree, leaves are sha256 of the addresses and pairs are sorted before hashing
+  fn check_merkle_proof(deps: DepsMut, donor: &Addr, proof: Option<Vec<String>>) -> Result<(), ContractError> {
+    let root = match MERKLE_ROOT.may_load(deps.storage)? {
+      Some(root) => root,
+      None => return Ok(()),
+    };
+
+    if MERKLE_VERIFIED.may_load(deps.storage, donor)?.as_ref() == Some(&root) {
+      return Ok(());
+    } // proven before, so the proof is not needed anymore
+
+    let proof = proof.ok_or_else(|| ContractError::DonorNotAllowed {
+      donor: donor.to_string(),
+    })?;
+
+    let mut hash: [u8; 32] = Sha256::digest(donor.as_bytes()).into();
+    for sibling in proof {
+      let sibling: [u8; 32] = hex::decode(sibling)
+        .ok()
+        .and_then(|sibling| sibling.try_into().ok())
+        .ok_or(ContractError::InvalidProof)?;
+
+      let mut pair = [hash, sibling];
+      pair.sort_unstable();
+      hash = Sha256::digest(pair.concat()).into();
+    }
+
+    if hex::encode(hash) != root {
+      return Err(ContractError::InvalidProof);
+    }
+
+    MERKLE_VERIFIED.save(deps.storage, donor, &root)?;
+    Ok(())
+  }
+
+  pub fn validate_merkle_root(root: &str) -> Result<String, ContractError> {
+    match hex::decode(root) {
+      Ok(bytes) if bytes.len() == 32 => Ok(root.to_lowercase()),
+      _ => Err(ContractError::InvalidMerkleRoot),
+    }
+  }
+  // kept lowercase, to be compared with the hex encoded hash of the proof
+
+  pub fn set_merkle_root(deps: DepsMut, info: MessageInfo, root: Option<String>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match root {
+      Some(root) => MERKLE_ROOT.save(deps.storage, &validate_merkle_root(&root)?)?,
+      None => MERKLE_ROOT.remove(deps.storage),
+    } // donors verified against the previous root have to prove they are in the new one
+
+    let resp = Response::new()
+        .add_attribute("action", "set_merkle_root")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn set_access_mode(deps: DepsMut, info: MessageInfo, mode: Option<AccessMode>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match mode {
+      Some(mode) => ACCESS_MODE.save(deps.storage, &mode)?,
+      None => ACCESS_MODE.remove(deps.storage),
+    }
+
+    let resp = Response::new()
+        .add_attribute("action", "set_access_mode")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn update_access_list(
+    deps: DepsMut,
+    info: MessageInfo,
+    list: AccessMode,
+    donors: Vec<String>,
+    add: bool,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    for donor in &donors {
+      let donor = deps.api.addr_validate(donor)?;
+      if add {
+        donor_list(list).save(deps.storage, &donor, &Empty {})?;
+      } else {
+        donor_list(list).remove(deps.storage, &donor);
+      }
+    }
+
+    let resp = Response::new()
+        .add_attribute("action", if add { "add_to_access_list" } else { "remove_from_access_list" })
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("donors", donors.len().to_string());
+
+    Ok(resp)
+  }
+  // AddToAccessList and RemoveFromAccessList only differ in what is done with every donor
+
+  pub fn set_limits(deps: DepsMut, info: MessageInfo, limits: Option<DonationLimits>) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match limits {
+      Some(limits) => {
+        validate_limits(&limits)?;
+        LIMITS.save(deps.storage, &limits)?
+      }
+      None => LIMITS.remove(deps.storage),
+    }
+
+    let resp = Response::new()
+        .add_attribute("action", "set_limits")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn set_staking(
+    deps: DepsMut,
+    info: MessageInfo,
+    staking: Option<StakingConfig>,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    match staking {
+      Some(staking) => {
+        let staking = validate_staking(deps.as_ref(), staking)?;
+        STAKING.save(deps.storage, &staking)?;
+        check_split(deps.as_ref())?;
+      }
+      None => STAKING.remove(deps.storage),
+    } // changing the validator doesn't move what is already delegated, that is what Redelegate is for
+
+    let resp = Response::new()
+        .add_attribute("action", "set_staking")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn validate_staking(deps: Deps, staking: StakingConfig) -> Result<Staking, ContractError> {
+    if staking.fraction > Decimal::one() {
+      return Err(ContractError::InvalidStakingFraction);
+    }
+
+    if deps.querier.query_validator(&staking.validator)?.is_none() {
+      return Err(ContractError::UnknownValidator {
+        validator: staking.validator,
+      });
+    }
+
+    Ok(Staking {
+      validator: staking.validator,
+      fraction: staking.fraction,
+      denom: deps.querier.query_bonded_denom()?,
+    })
+  }
+  // the bond denom is stored with the config, so donating doesn't need to query it every time
+
+  pub fn check_split(deps: Deps) -> Result<(), ContractError> {
+    let share = REFERRALS.may_load(deps.storage)?.map(|referrals| referrals.share).unwrap_or_default();
+    let fraction = STAKING.may_load(deps.storage)?.map(|staking| staking.fraction).unwrap_or_default();
+    let part = PARENT_DONATION.may_load(deps.storage)?.map(|parent| parent.part).unwrap_or_default();
+
+    match share.checked_add(fraction).and_then(|split| split.checked_add(part)) {
+      Ok(split) if split <= Decimal::one() => Ok(()),
+      _ => Err(ContractError::InvalidSplit),
+    }
+  }
+  // checked against what is stored, after the referral share, the staking config or the parent changed
+
+  fn delegate_msg(deps: Deps, info: &MessageInfo) -> StdResult<Option<StakingMsg>> {
+    let staking = match STAKING.may_load(deps.storage)? {
+      Some(staking) => staking,
+      None => return Ok(None),
+    };
+
+    let amount = info
+      .funds
+      .iter()
+      .find(|coin| coin.denom == staking.denom)
+      .map(|coin| coin.amount * staking.fraction)
+      .unwrap_or_default();
+
+    if amount.is_zero() {
+      return Ok(None);
+    } // the staking module refuses to delegate nothing
+
+    Ok(Some(StakingMsg::Delegate {
+      validator: staking.validator,
+      amount: Coin {
+        denom: staking.denom,
+        amount,
+      },
+    }))
+  }
+
+  pub fn redelegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    src_validator: String,
+    dst_validator: String,
+    amount: Coin,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let msg = StakingMsg::Redelegate {
+      src_validator: src_validator.clone(),
+      dst_validator: dst_validator.clone(),
+      amount,
+    };
+
+    let resp = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "redelegate")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("src_validator", src_validator)
+        .add_attribute("dst_validator", dst_validator);
+
+    Ok(resp)
+  }
+
+  pub fn undelegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    validator: String,
+    amount: Coin,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let msg = StakingMsg::Undelegate {
+      validator: validator.clone(),
+      amount,
+    };
+
+    let resp = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "undelegate")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("validator", validator);
+
+    Ok(resp)
+  }
+  // the staking module sends the funds back to the contract after the unbonding period, then they can be withdrawn
+
+  pub fn claim_rewards(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let msgs: Vec<_> = deps
+      .querier
+      .query_all_delegations(&env.contract.address)?
+      .into_iter()
+      .map(|delegation| DistributionMsg::WithdrawDelegatorReward {
+        validator: delegation.validator,
+      })
+      .collect();
+    // one message per validator, the rewards are paid to the contract as liquid funds
+
+    let resp = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("sender", info.sender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn ensure_restored(deps: Deps) -> Result<(), ContractError> {
+    if RESTORING.may_load(deps.storage)?.is_some() {
+      return Err(ContractError::Restoring);
+    }
+    Ok(())
+  }
+
+  pub fn ensure_not_paused(deps: Deps) -> Result<(), ContractError> {
+    if PAUSED.may_load(deps.storage)?.unwrap_or(false) {
+      return Err(ContractError::Paused);
+    }
+    Ok(())
+  }
+
+  fn check_receiver(deps: Deps, env: &Env, receiver: &Addr) -> Result<(), ContractError> {
+    let mut policy = RECEIVER_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    policy.refresh(env.block.time);
+    if !policy.enforce {
+      return Ok(());
+    } // without the policy every valid address can receive funds
+
+    match RECEIVERS.may_load(deps.storage, receiver)? {
+      Some(allowed_from) if allowed_from <= env.block.time => Ok(()),
+      _ => Err(ContractError::ReceiverNotAllowed {
+        receiver: receiver.to_string(),
+      }),
+    }
+  }
+  // receiver is allowed if the policy is not enforced, or it was added and its delay has passed
+
+  pub fn set_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    limit: Vec<Coin>,
+    period: u64,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    validate_allowance(&limit, period)?;
+
+    let spender = deps.api.addr_validate(&spender)?;
+    ALLOWANCES.save(
+      deps.storage,
+      &spender,
+      &Allowance {
+        limit,
+        period,
+        spent: vec![],
+        period_start: env.block.time,
+      },
+    )?; // setting an allowance again replaces the old one and starts a fresh period
+
+    let resp = Response::new()
+        .add_attribute("action", "set_allowance")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("spender", spender.as_str());
+
+    Ok(resp)
+  }
+
+  pub fn validate_allowance(limit: &[Coin], period: u64) -> Result<(), ContractError> {
+    let reason = if period == 0 {
+      "the period can't be zero"
+    } else if limit.is_empty() {
+      "the limit can't be empty"
+    } else if limit.iter().enumerate().any(|(i, coin)| limit[..i].iter().any(|c| c.denom == coin.denom)) {
+      "the limit lists a denom twice"
+    } else {
+      return Ok(());
+    }; // a zero period would start a new window on every withdrawal, so the limit would never be reached
+
+    Err(ContractError::InvalidAllowance {
+      reason: reason.to_owned(),
+    })
+  }
+
+  pub fn revoke_allowance(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: String,
+  ) -> Result<Response, ContractError> {
+    let owner = STATE.load(deps.storage)?.owner;
+    if info.sender != owner {
+      return Err(ContractError::Unauthorized {
+        owner: owner.to_string(),
+      });
+    }
+
+    let spender = deps.api.addr_validate(&spender)?;
+    ALLOWANCES.remove(deps.storage, &spender);
+
+    let resp = Response::new()
+        .add_attribute("action", "revoke_allowance")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("spender", spender.as_str());
+
+    Ok(resp)
+  }
   }
 
 
@@ -25,6 +25,179 @@ pub enum ContractError {
   #[error("Unsupported contract version for migration: {version}")]
   InvalidContractVersion { version: String },
   // InvalidContractVersion varient in the enum type
+
+  #[error("Downgrading from {from} to {to} is not allowed")]
+  DowngradeNotAllowed { from: String, to: String },
+  // DowngradeNotAllowed varient, returned when the stored contract version is newer than the migrated code
+
+  #[error("Storage invariant violated: {reason}")]
+  InvariantViolated { reason: String },
+  // InvariantViolated varient, returned when a migration would leave the storage inconsistent
+
+  #[error("Invalid parent: {reason}")]
+  InvalidParent { reason: String },
+  // InvalidParent varient, returned for a parent with a zero donating period or a part greater than the whole donation
+
+  #[error("{field} can't be cleared")]
+  CannotClear { field: String },
+  // CannotClear varient, returned when a migration tries to clear a field the contract can't work without
+
+  #[error("Invalid snapshot: {reason}")]
+  InvalidSnapshot { reason: String },
+  // InvalidSnapshot varient, returned when a snapshot fails the integrity checks on restore
+
+  #[error("Contract is paused")]
+  Paused,
+  // Paused varient, returned for every execute message while governance keeps the contract paused
+
+  #[error("Contract is being restored, only its ledgers can be imported")]
+  Restoring,
+  // Restoring varient, returned for every other execute message until the owner finishes the restore
+
+  #[error("Contract is not being restored")]
+  NotRestoring,
+  // NotRestoring varient, returned when importing a ledger into a contract which was not restored, or is already finished
+
+  #[error("Donation not counted - {reason}")]
+  RateLimited { reason: String },
+  // RateLimited varient, returned when a donation would break the donation limits and the policy is to reject it
+
+  #[error("Invalid donation limits: {reason}")]
+  InvalidLimits { reason: String },
+  // InvalidLimits varient, returned for a donation window with a zero period or zero donations
+
+  #[error("{donor} is not allowed to donate")]
+  DonorNotAllowed { donor: String },
+  // DonorNotAllowed varient, returned when the donor is not on the allowlist, or is on the blocklist
+  // the whole transaction reverts, so the funds never leave the donor
+
+  #[error("Invalid referrer: {reason}")]
+  InvalidReferrer { reason: String },
+  // InvalidReferrer varient, returned for self-referrals, referral cycles, and a referrer other than the one the donor already has
+
+  #[error("Referral share can't be more than the whole donation")]
+  InvalidReferralShare,
+  // InvalidReferralShare varient, returned when the referral share is above 1
+
+  #[error("Referral share, staked fraction and parent part together can't be more than the whole donation")]
+  InvalidSplit,
+  // InvalidSplit varient, returned when the referrer, the validator and the parent together would take more than 1 of a donation
+
+  #[error("No voucher signer is configured")]
+  NoVoucherSigner,
+  // NoVoucherSigner varient, returned when DonateFor is called before the owner set a signer
+
+  #[error("Voucher signer has to be a 33 or 65 bytes secp256k1 public key")]
+  InvalidPubkey,
+  // InvalidPubkey varient, returned when the owner sets a signer key of the wrong length
+
+  #[error("Invalid voucher: {reason}")]
+  InvalidVoucher { reason: String },
+  // InvalidVoucher varient, returned when a voucher is expired, doesn't match the funds, or is not signed by the signer
+
+  #[error("Voucher with nonce {nonce} was already used")]
+  VoucherUsed { nonce: u64 },
+  // VoucherUsed varient, returned when a voucher is replayed
+
+  #[error("Merkle root has to be a hex encoded sha256 hash")]
+  InvalidMerkleRoot,
+  // InvalidMerkleRoot varient, returned when the owner sets a root which is not 32 hex encoded bytes
+
+  #[error("Merkle proof doesn't lead to the root")]
+  InvalidProof,
+  // InvalidProof varient, returned when the proof of the donor is malformed, or is for another donor or root
+
+  #[error("Not enough {denom} on the contract to cover the payouts")]
+  InsufficientFunds { denom: String },
+  // InsufficientFunds varient, returned when the requested payouts exceed the contract balance
+
+  #[error("Invalid allowance: {reason}")]
+  InvalidAllowance { reason: String },
+  // InvalidAllowance varient, returned when an allowance has no period, or its limit is empty or lists a denom twice
+
+  #[error("Allowance exceeded - not enough {denom} left in this period")]
+  AllowanceExceeded { denom: String },
+  // AllowanceExceeded varient, returned when a spender tries to withdraw more than its remaining allowance
+
+  #[error("Receiver {receiver} is not on the allowlist")]
+  ReceiverNotAllowed { receiver: String },
+  // ReceiverNotAllowed varient, returned when the receiver policy is enforced and the receiver is not (yet) allowed
+
+  #[error("Governance is not configured")]
+  GovernanceDisabled,
+  // GovernanceDisabled varient, returned when proposing without a governance config
+
+  #[error("Withdrawals are restricted to proposals passed by the donors")]
+  WithdrawalsRestricted,
+  // WithdrawalsRestricted varient, returned for direct withdrawals while governance restricts them
+
+  #[error("Only a passed proposal can loosen a governance config which restricts withdrawals")]
+  GovernanceLocked,
+  // GovernanceLocked varient, returned when the owner lifts the withdrawal restriction or lowers the quorum or threshold
+
+  #[error("Sender has no voting power")]
+  NoVotingPower,
+  // NoVotingPower varient, returned when someone who didn't donate tries to propose or vote
+
+  #[error("Already voted on proposal {id}")]
+  AlreadyVoted { id: u64 },
+  // AlreadyVoted varient, returned on a second vote of the same donor
+
+  #[error("Voting on proposal {id} is closed")]
+  VotingClosed { id: u64 },
+  // VotingClosed varient, returned when voting after the deadline or on an executed proposal
+
+  #[error("Proposal {id} didn't pass")]
+  ProposalNotPassed { id: u64 },
+  // ProposalNotPassed varient, returned when executing a proposal without enough votes
+
+  #[error("Proposal {id} was already executed")]
+  ProposalExecuted { id: u64 },
+  // ProposalExecuted varient, returned when executing a proposal for the second time
+
+  #[error("Unknown validator: {validator}")]
+  UnknownValidator { validator: String },
+  // UnknownValidator varient, returned when delegating to an address which is not an active validator
+
+  #[error("Staked fraction must not be greater than 1")]
+  InvalidStakingFraction,
+  // InvalidStakingFraction varient, returned when the staking config would delegate more than was donated
+
+  #[error("No failed transfer with id {id}")]
+  UnknownTransfer { id: u64 },
+  // UnknownTransfer varient, returned when retrying a transfer which didn't fail or was already retried
+
+  #[error("Parent is not on another chain")]
+  NoIbcParent,
+  // NoIbcParent varient, returned when retrying a transfer without an ibc parent to send it to
+
+  #[error("Only the {expected} version is supported, got {version}")]
+  InvalidIbcVersion { expected: String, version: String },
+  // InvalidIbcVersion varient, returned when opening a channel with another protocol
+
+  #[error("Only unordered channels are supported")]
+  OrderedChannel,
+  // OrderedChannel varient, returned when opening an ordered channel, a lost packet would close it
+
+  #[error("Unknown counting channel: {channel_id}")]
+  UnknownChannel { channel_id: String },
+  // UnknownChannel varient, returned when the hub is set to a channel which is not an open counting-v1 channel
+
+  #[error("Port {port_id} over {connection_id} is not allowed to open counting channels")]
+  UnknownPeer { connection_id: String, port_id: String },
+  // UnknownPeer varient, returned when a channel is opened by a contract the owner didn't add as a peer
+
+  #[error("Counting hub is not configured")]
+  NoHub,
+  // NoHub varient, returned when syncing the counter without a hub to send it to
+
+  #[error("Campaign {id} already exists")]
+  CampaignExists { id: String },
+  // CampaignExists varient, returned when creating a campaign with a taken id, "default" is always taken
+
+  #[error("Unknown campaign: {id}")]
+  UnknownCampaign { id: String },
+  // UnknownCampaign varient, returned when donating to, resetting or withdrawing from a campaign which was never created
 }
 // This way, we can use the ContractError type in our smart contract, still being able to return errors occurring in cosmwasm-std. 
 // The additional #[from] attribute tells thiserror to generate the From trait, converting the underlying type to the error variant (in this case: impl From<StdError> for ContractError). 
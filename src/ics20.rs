@@ -0,0 +1,88 @@
+use cosmwasm_std::{Binary, Coin, CosmosMsg, StdError, StdResult, Timestamp};
+
+pub const TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+pub const TRANSFER_PORT: &str = "transfer";
+
+// ics20 transfer with a memo, IbcMsg::Transfer can't carry one
+pub fn transfer_msg(channel_id: &str, sender: &str, receiver: &str, token: &Coin, timeout: Timestamp) -> CosmosMsg {
+  let mut coin = vec![];
+  put_string(&mut coin, 1, &token.denom);
+  put_string(&mut coin, 2, &token.amount.to_string());
+
+  let memo = format!(r#"{{"ibc_callback":"{}"}}"#, sender);
+  // ibc-hooks calls the sender back with the outcome of the transfer, see SudoMsg::IbcLifecycleComplete
+
+  let mut value = vec![];
+  put_string(&mut value, 1, TRANSFER_PORT);
+  put_string(&mut value, 2, channel_id);
+  put_bytes(&mut value, 3, &coin);
+  put_string(&mut value, 4, sender);
+  put_string(&mut value, 5, receiver);
+  put_varint_field(&mut value, 7, timeout.nanos());
+  put_string(&mut value, 8, &memo);
+
+  CosmosMsg::Stargate {
+    type_url: TRANSFER_TYPE_URL.to_owned(),
+    value: Binary::from(value),
+  }
+}
+// the timeout height (field 6) is left out, the transfer times out by the timestamp only
+
+// sequence of the packet from the MsgTransferResponse in the reply data
+pub fn transfer_sequence(data: &[u8]) -> StdResult<u64> {
+  let mut data = data;
+  while !data.is_empty() {
+    let key = take_varint(&mut data)?;
+    match (key >> 3, key & 7) {
+      (1, 0) => return take_varint(&mut data),
+      (_, 0) => {
+        take_varint(&mut data)?;
+      }
+      (_, 2) => {
+        let len = take_varint(&mut data)? as usize;
+        data = data.get(len..).ok_or_else(|| StdError::parse_err("MsgTransferResponse", "truncated field"))?;
+      }
+      (_, wire) => return Err(StdError::parse_err("MsgTransferResponse", format!("unsupported wire type {}", wire))),
+    }
+  }
+  Ok(0)
+}
+// a missing sequence is the default value of protobuf, sequences start at 1 so it is never sent
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    buf.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  buf.push(value as u8);
+}
+
+fn put_varint_field(buf: &mut Vec<u8>, field: u64, value: u64) {
+  put_varint(buf, field << 3);
+  put_varint(buf, value);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+  put_varint(buf, (field << 3) | 2);
+  put_varint(buf, bytes.len() as u64);
+  buf.extend_from_slice(bytes);
+}
+
+fn put_string(buf: &mut Vec<u8>, field: u64, value: &str) {
+  put_bytes(buf, field, value.as_bytes());
+}
+
+fn take_varint(data: &mut &[u8]) -> StdResult<u64> {
+  let mut value = 0u64;
+  for shift in (0..64).step_by(7) {
+    let (byte, rest) = data
+      .split_first()
+      .ok_or_else(|| StdError::parse_err("MsgTransferResponse", "truncated varint"))?;
+    *data = rest;
+    value |= u64::from(byte & 0x7f) << shift;
+    if byte & 0x80 == 0 {
+      return Ok(value);
+    }
+  }
+  Err(StdError::parse_err("MsgTransferResponse", "varint too long"))
+}
@@ -1,7 +1,11 @@
 #[cfg(not(feature = "library"))] // only compile the entry_point macro if the library feature is not enabled
 use cosmwasm_std::entry_point; // import the entry_point macro from cosmwasm_std
 
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo,
+    Reply, Response, StdError, StdResult,
+};
 
 use error::ContractError; 
 // using module file error.rs for ContractError
@@ -11,6 +15,7 @@ use msg::InstantiateMsg;
 
 mod contract; // private because contract contains internal logic functions, contains all msg handlers 
 pub mod error; // using module file error.rs
+mod ics20; // encoding of the ics20 transfers to an ibc parent, which cosmwasm_std can't send with a memo
 pub mod msg; // using module file msg.rs
 #[cfg(any(test, feature = "tests"))]
 pub mod multitest; // compile only when running tests, only when the feature tests is enabled
@@ -26,12 +31,22 @@ mod state;
 // Hence, the function will be an entry point only if the feature library is not enabled.
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
-    contract::instantiate(deps, info, msg.counter, msg.minimal_donation, msg.parent)
-    // calls the instantiate function for contract.rs, if the feature library is not enabled
+) -> Result<Response, ContractError> {
+    match msg {
+        InstantiateMsg::New {
+            counter,
+            minimal_donation,
+            parent,
+            rewards,
+            owner,
+            limits,
+        } => contract::instantiate(deps, env, info, counter, minimal_donation, parent, rewards, owner, limits),
+        // calls the instantiate function for contract.rs, if the feature library is not enabled
+        InstantiateMsg::FromSnapshot { snapshot } => contract::restore(deps, env, *snapshot),
+    }
 } // entry point instantiate function for contract.rs, if the feature library is not enabled
 // saves the state and owner to the blockchain, response is empty, but it is a success
 
@@ -45,36 +60,246 @@ pub fn execute(
     use contract::exec;
     use msg::ExecMsg::*;
 
+    if !matches!(msg, ImportLedger { .. } | FinishRestore {}) {
+        exec::ensure_restored(deps.as_ref())?;
+    } // a restored contract only takes its ledgers until the owner finishes the restore
+
+    exec::ensure_not_paused(deps.as_ref())?;
+    // nothing can be executed while governance keeps the contract paused
+
  match msg {
 
-        Donate {} => exec::donate(deps, env, info).map_err(ContractError::Std),
+        Donate { campaign, proof, referrer } => match named_campaign(campaign) {
+            None => exec::donate(deps, env, info, proof, referrer),
+            Some(id) => exec::donate_campaign(deps, env, info, id, proof, referrer),
+        },
+
+        Reset { counter, campaign } => match named_campaign(campaign) {
+            None => exec::reset(deps, env, info, counter),
+            Some(id) => exec::reset_campaign(deps, info, id, counter),
+        },
 
-        Reset { counter } => exec::reset(deps, info, counter),
+        Withdraw { campaign } => match named_campaign(campaign) {
+            None => exec::withdraw(deps, env, info),
+            Some(id) => exec::withdraw_campaign(deps, env, info, id),
+        },
 
-        Withdraw {} => exec::withdraw(deps, env, info),
+        CreateCampaign {
+            id,
+            minimal_donation,
+            owner,
+            parent,
+        } => exec::create_campaign(deps, info, id, minimal_donation, owner, parent),
 
         WithdrawTo { receiver, funds } => {
             exec::withdraw_to(deps, env, info, receiver, funds)
         }
         // added map for ContractError instead of using default StdError
         // removed if all fn always return Error being ContractError
+
+        WithdrawMany { payouts } => exec::withdraw_many(deps, env, info, payouts),
+
+        SetAllowance {
+            spender,
+            limit,
+            period,
+        } => exec::set_allowance(deps, env, info, spender, limit, period),
+
+        RevokeAllowance { spender } => exec::revoke_allowance(deps, info, spender),
+
+        AddReceiver { receiver } => exec::add_receiver(deps, env, info, receiver),
+
+        RemoveReceiver { receiver } => exec::remove_receiver(deps, info, receiver),
+
+        SetReceiverPolicy { enforce, delay } => {
+            exec::set_receiver_policy(deps, env, info, enforce, delay)
+        }
+
+        AddHook {
+            hook,
+            on_reset,
+            on_withdraw,
+        } => exec::add_hook(deps, info, hook, on_reset, on_withdraw),
+
+        RemoveHook { hook } => exec::remove_hook(deps, info, hook),
+
+        SetReceipts { receipts } => exec::set_receipts(deps, info, receipts),
+
+        SetRewardRates { rates } => exec::set_reward_rates(deps, info, rates),
+
+        SetGovernance { governance } => exec::set_governance(deps, info, governance),
+
+        Propose {
+            payouts,
+            description,
+            governance,
+        } => exec::propose(deps, env, info, payouts, description, governance),
+
+        Vote { id, yes } => exec::vote(deps, env, info, id, yes),
+
+        Execute { id } => exec::execute_proposal(deps, env, info, id),
+
+        SetStaking { staking } => exec::set_staking(deps, info, staking),
+
+        Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        } => exec::redelegate(deps, info, src_validator, dst_validator, amount),
+
+        Undelegate { validator, amount } => exec::undelegate(deps, info, validator, amount),
+
+        ClaimRewards {} => exec::claim_rewards(deps, env, info),
+
+        RetryTransfer { id } => exec::retry_transfer(deps, env, info, id),
+
+
+        SetHub { hub } => exec::set_hub(deps, info, hub),
+
+        AddPeer { peer } => exec::add_peer(deps, info, peer),
+
+        RemovePeer { peer } => exec::remove_peer(deps, info, peer),
+
+        SyncCounter {} => exec::sync_counter(deps, env, info),
+        SetLimits { limits } => exec::set_limits(deps, info, limits),
+        DonateFor { beneficiary, voucher, proof } => exec::donate_for(deps, env, info, beneficiary, voucher, proof),
+        SetVoucherSigner { pubkey } => exec::set_voucher_signer(deps, info, pubkey),
+        SetReferrals { referrals } => exec::set_referrals(deps, info, referrals),
+        ClaimReferralRewards {} => exec::claim_referral_rewards(deps, env, info),
+        SetMerkleRoot { root } => exec::set_merkle_root(deps, info, root),
+        SetAccessMode { mode } => exec::set_access_mode(deps, info, mode),
+        AddToAccessList { list, donors } => exec::update_access_list(deps, info, list, donors, true),
+        RemoveFromAccessList { list, donors } => exec::update_access_list(deps, info, list, donors, false),
+        ImportLedger { page } => contract::import_ledger(deps, env, info, page),
+        FinishRestore {} => contract::finish_restore(deps, info),
+    }
+}
+
+// None for the default campaign, which keeps the storage the contract had before campaigns
+fn named_campaign(campaign: Option<String>) -> Option<String> {
+    campaign.filter(|id| id != state::DEFAULT_CAMPAIGN)
+}
+
+// reply is called after a submessage sent with a reply id finishes
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    use contract::reply;
+
+    match msg.id {
+        reply::HOOK_REPLY_ID => reply::hook_failed(msg),
+        reply::TRANSFER_REPLY_ID => reply::transfer_sent(deps, msg),
+        id => Err(StdError::generic_err(format!("Unknown reply id: {}", id)).into()),
+    }
+}
+
+// the channel entry points negotiate counting-v1 channels, over which contracts report their counters to a hub
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(deps: DepsMut, _env: Env, msg: IbcChannelOpenMsg) -> Result<IbcChannelOpenResponse, ContractError> {
+    contract::ibc::channel_open(deps.as_ref(), msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(deps: DepsMut, _env: Env, msg: IbcChannelConnectMsg) -> Result<IbcBasicResponse, ContractError> {
+    contract::ibc::channel_connect(deps, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(deps: DepsMut, _env: Env, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse, ContractError> {
+    contract::ibc::channel_close(deps, msg)
+}
+
+// a counter delta from another contract, errors are returned to it in the acknowledgement instead of failing the relayer
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(deps: DepsMut, _env: Env, msg: IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, ContractError> {
+    contract::ibc::packet_receive(deps, msg)
+}
+
+// called with the acknowledgement of a counter delta sent to the hub
+// transfers to the ibc parent are acknowledged to the transfer module, the outcome comes back through sudo from ibc-hooks
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(deps: DepsMut, _env: Env, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse, ContractError> {
+    contract::ibc::packet_ack(deps, msg)
+}
+
+// called when a counter delta timed out, handled like an error acknowledgement
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(deps: DepsMut, _env: Env, msg: IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, ContractError> {
+    contract::ibc::packet_timeout(deps, msg)
+}
+
+// sudo can only be called by the chain itself (for example by a governance proposal), never by a regular account
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: msg::SudoMsg) -> Result<Response, ContractError> {
+    use contract::sudo;
+    use msg::SudoMsg::*;
+
+    match msg {
+        ResetCounter { counter } => sudo::reset_counter(deps, env, counter),
+        ReplaceOwner { owner } => sudo::replace_owner(deps, owner),
+        Pause {} => sudo::set_paused(deps, true),
+        Unpause {} => sudo::set_paused(deps, false),
+        IbcLifecycleComplete(msg::IbcLifecycleComplete::IbcAck { channel, sequence, ack, success }) => {
+            sudo::transfer_complete(deps, channel, sequence, (!success).then_some(ack))
+        }
+        IbcLifecycleComplete(msg::IbcLifecycleComplete::IbcTimeout { channel, sequence }) => {
+            sudo::transfer_complete(deps, channel, sequence, Some("timeout".to_owned()))
+        }
     }
 }
 
 // Deps is read-only, DepsMut is read-write on blockchain state
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
     use contract::query;
     use msg::QueryMsg::*;
 
     match msg {
-        Value {} => to_binary(&query::value(deps)?),
+        Value { campaign } => match named_campaign(campaign) {
+            None => to_binary(&query::value(deps)?),
+            Some(id) => to_binary(&query::campaign_value(deps, id)?),
+        },
+        Campaign { id } => to_binary(&query::campaign(deps, env, id)?),
+        ValueAt { height } => to_binary(&query::value_at(deps, height)?),
+        History { start_after, limit } => to_binary(&query::history(deps, start_after, limit)?),
+        Allowance { spender } => to_binary(&query::allowance(deps, env, spender)?),
+        Hooks {} => to_binary(&query::hooks(deps)?),
+        RewardRate {} => to_binary(&query::reward_rate(deps)?),
+        Proposal { id } => to_binary(&query::proposal(deps, env, id)?),
+        Contribution { donor } => to_binary(&query::contribution(deps, donor)?),
+        Contributions {
+            from_height,
+            to_height,
+            start_after,
+            limit,
+        } => to_binary(&query::contributions(deps, from_height, to_height, start_after, limit)?),
+        FailedTransfers {} => to_binary(&query::failed_transfers(deps)?),
+        Staking {} => to_binary(&query::staking(deps, env)?),
+        RemoteCounters {} => to_binary(&query::remote_counters(deps)?),
+        Peers {} => to_binary(&query::peers(deps)?),
+        GlobalValue {} => to_binary(&query::global_value(deps)?),
+        Limits { donor } => to_binary(&query::limits(deps, donor)?),
+        VoucherSigner { nonce } => to_binary(&query::voucher_signer(deps, nonce)?),
+        Referrals {} => to_binary(&query::referrals(deps)?),
+        Referrer { address } => to_binary(&query::referrer(deps, address)?),
+        MerkleRoot { donor } => to_binary(&query::merkle_root(deps, donor)?),
+        AccessList {
+            list,
+            start_after,
+            limit,
+        } => to_binary(&query::access_list(deps, list, start_after, limit)?),
+        MigrationPlan { from_version } => to_binary(&query::migration_plan(from_version)?),
+        ExportState {} => to_binary(&query::export_state(deps)?),
+        ExportLedger {
+            ledger,
+            start_after,
+            limit,
+        } => to_binary(&query::export_ledger(deps, ledger, start_after, limit)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, msg: msg::MigrateMsg) -> Result<Response, ContractError> {
-    contract::migrate(deps, msg.parent)
+pub fn migrate(deps: DepsMut, env: Env, msg: msg::MigrateMsg) -> Result<Response, ContractError> {
+    contract::migrate(deps, env, msg)
 } // entry point migrate function for contract.rs, if the feature library is not enabled
 // returns a StdResult<Response> type, which is an alias for Result<Response, StdError>
 // using contract.rs migrate function with deps as parameter
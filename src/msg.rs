@@ -2,7 +2,7 @@
 // use schemars::JsonSchema;
 // use serde::{Deserialize, Serialize};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Coin, Decimal};
+use cosmwasm_std::{Binary, Coin, Decimal, Empty, Timestamp, Uint128};
 // QueryResponses is a type that represents a list of query responses
 use cosmwasm_schema::QueryResponses; 
 
@@ -12,17 +12,86 @@ pub struct Parent {
     pub addr: String,
     pub donating_period: u64,
     pub part: Decimal,
+    #[serde(default)]
+    pub ibc: Option<IbcParent>,
 }// added parent struct (inlcluded in InstantiateMsg, which is an Option type, meaning it can be None or Some)
 
+#[cw_serde]
+#[derive(Eq)]
+pub struct IbcParent {
+    pub channel_id: String,
+    pub timeout: u64,
+}
+// parent on another chain, the share is sent with an ics20 transfer over the channel instead of a Donate message
+// addr of the Parent is then the address on the other chain, timeout is in seconds from the donation
+// the transfer carries an ibc-hooks callback memo, a failed or timed out transfer is recorded for a retry once it is refunded
+
+#[cw_serde]
+pub enum IbcAck {
+    Result(Binary),
+    Error(String),
+}
+// acknowledgement in the format of ics20, the counting-v1 packets are acknowledged the same way
+
+#[cw_serde]
+pub enum CountingPacket {
+    CounterDelta { delta: i64 },
+}
+// packet of the counting-v1 protocol, delta is how much the counter of the sender changed since its last packet
+// a reset can make it negative
+
+#[cw_serde]
+pub struct HubConfig {
+    pub channel_id: String,
+    pub timeout: u64,
+}
+// counting-v1 channel to the hub contract the counter is reported to, timeout is in seconds from sending
+
+#[cw_serde]
+pub struct Peer {
+    pub connection_id: String,
+    pub port_id: String,
+}
+// contract allowed to open counting-v1 channels, port_id is its port on the chain at the other end of the connection
+
+#[cw_serde]
+pub struct RewardRate {
+    pub denom: String,
+    pub rate: Decimal,
+}
+// supporter tokens minted for every unit of the denom donated
+
+#[cw_serde]
+pub struct RewardConfig {
+    pub token: String,
+    pub rates: Vec<RewardRate>,
+}
+// token is the cw20 contract the rewards are minted on, this contract has to be its minter
+
 // #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 // #[serde(rename_all = "snake_case")]
 #[cw_serde] 
-pub struct InstantiateMsg { // struct to hold the data for the contract initialization (from state.rs)
-    #[serde(default)]
-    pub counter: u64,
-    pub minimal_donation: Coin,
-    pub parent: Option<Parent>,
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)] // New is read once at instantiation, the snapshot is the one worth boxing
+pub enum InstantiateMsg { // data for the contract initialization (from state.rs)
+    New {
+        #[serde(default)]
+        counter: u64,
+        minimal_donation: Coin,
+        parent: Option<Parent>,
+        #[serde(default)]
+        rewards: Option<RewardConfig>,
+        #[serde(default)]
+        owner: Option<String>,
+        #[serde(default)]
+        limits: Option<DonationLimits>,
+    }, // owner defaults to the sender, a factory instantiating the contract passes the creator instead
+    FromSnapshot {
+        snapshot: Box<Snapshot>,
+    }, // restores a contract exported with the ExportState query, for re-deploying instead of migrating
+    // the ledgers follow with ImportLedger, the contract takes nothing else until FinishRestore
 }// added parent field which is an Option type, meaning it can be None or Some
+// untagged, so the fresh instantiation keeps its message shape, and a message with only a snapshot restores it
 // and it is a Parent struct, which is a struct that holds the address of the parent, the donating period and the part of the donation that the parent will receive
 // added embedded struct Parent to the InstantiateMsg struct in order to keep ingo about forwarding (donations) to the parent contract. If this is None, then the contract will not forward any donations to the parent contract.
 
@@ -36,7 +105,96 @@ pub enum QueryMsg {
     #[returns(ValueResp)]
     // #[returns(u64)] // returns a u64 value, #[returns()] comes from cosmwasm_schema. #[derive(QueryResponses)]
     // The #[returns(...)] attribute is now required on every query variant - it describes what response type is returned for the particular query.
-    Value {},
+    Value {
+      #[serde(default, skip_serializing_if = "Option::is_none")]
+      campaign: Option<String>,
+    },
+    // counter of the campaign, the default one without an id
+    #[returns(CampaignInfo)]
+    Campaign { id: String },
+    #[returns(ValueResp)]
+    ValueAt { height: u64 },
+    // value of the counter at the beginning of the given block, before any of its transactions
+    #[returns(HistoryResp)]
+    History {
+      start_after: Option<u64>,
+      limit: Option<u32>,
+    },
+    // heights at which the counter changed, oldest first, paginated by height
+    #[returns(AllowanceResp)]
+    Allowance { spender: String },
+    // spending allowance of the given spender in the current period
+    #[returns(MigrationPlanResp)]
+    MigrationPlan { from_version: String },
+    // steps a migration from the given version to this code would run, and the storage keys they touch
+    #[returns(HooksResp)]
+    Hooks {},
+    // contracts notified about donations, and which other events they subscribed to
+    #[returns(RewardRateResp)]
+    RewardRate {},
+    // cw20 token rewarded to donors, and how much of it is minted per donated denom
+    #[returns(ProposalResp)]
+    Proposal { id: u64 },
+    #[returns(ContributionResp)]
+    Contribution { donor: String },
+    // what the donor gave so far, which is its voting weight in new proposals
+    #[returns(ContributionsResp)]
+    Contributions {
+      from_height: Option<u64>,
+      to_height: Option<u64>,
+      start_after: Option<String>,
+      limit: Option<u32>,
+    },
+    // what every donor gave between the beginning of the from_height and to_height blocks, paginated by donor
+    // missing heights mean since the instantiation and until now, donors who gave nothing in between are listed with zero
+    #[returns(FailedTransfersResp)]
+    FailedTransfers {},
+    // transfers to an ibc parent which were refunded, and can be retried
+    #[returns(StakingResp)]
+    Staking {},
+    // auto-delegation config, and what the contract has delegated to every validator
+    #[returns(RemoteCountersResp)]
+    RemoteCounters {},
+    // counters reported to this contract over counting-v1 channels, by channel
+    #[returns(PeersResp)]
+    Peers {},
+    // contracts allowed to open counting-v1 channels, by connection
+    #[returns(ValueResp)]
+    GlobalValue {},
+    // this counter together with all the remote ones
+    #[returns(LimitsResp)]
+    Limits { donor: Option<String> },
+    // donation limits, and with a donor given what it already used of them
+    #[returns(ReferralsResp)]
+    Referrals {},
+    // referral config, and what is accrued for all the referrers together
+    #[returns(ReferrerResp)]
+    Referrer { address: String },
+    // donors the referrer brought in, and what it earned from their donations
+    #[returns(VoucherSignerResp)]
+    VoucherSigner { nonce: Option<u64> },
+    // public key signing the vouchers, and with a nonce given whether a voucher with it was already used
+    #[returns(MerkleRootResp)]
+    MerkleRoot { donor: Option<String> },
+    // current merkle root, and with a donor given whether it was already verified against it
+    #[returns(AccessListResp)]
+    AccessList {
+      list: Option<AccessMode>,
+      start_after: Option<String>,
+      limit: Option<u32>,
+    },
+    // donors on the list, the one of the current mode if none is given, paginated by donor
+    #[returns(Snapshot)]
+    ExportState {},
+    // snapshot of the contract storage without its ledgers, to be passed to InstantiateMsg::FromSnapshot on a new deployment
+    // queries can't check the sender, so it is meant for the owner but readable by anyone, like the raw storage
+    #[returns(LedgerPage)]
+    ExportLedger {
+        ledger: Ledger,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // page of a ledger too big for the snapshot, to be passed to ImportLedger, an empty page is the end of it
 }
 
 // Execution message to update the internal contract counter
@@ -44,19 +202,297 @@ pub enum QueryMsg {
 // #[serde(rename_all = "snake_case")]
 #[cw_serde] // using this instead of the above, generates all the boilerplate code for us
 pub enum ExecMsg {
-  Donate {},
+  Donate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proof: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    referrer: Option<String>,
+  }, // no campaign (or "default") donates to the default campaign, the one contracts had before campaigns were added
+  // proof is the hex encoded merkle proof of the sender, needed only while there is a merkle root and the sender was not verified yet
+  // referrer gets the referral share of the donation, on the default campaign only and only while referrals are on
   Reset {
     #[serde(default)] 
     counter: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
   }, // Reset taking a counter as an argument
-  Withdraw {},
+  Withdraw {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+  }, // a campaign withdraws only the funds donated to it
+  CreateCampaign {
+    id: String,
+    minimal_donation: Coin,
+    owner: Option<String>,
+    parent: Option<Parent>,
+  }, // owner only, the contract owner owns the campaign unless another owner is given
+
   WithdrawTo {
     receiver: String, 
     #[serde(default)] // default value is an empty vector
     funds: Vec<Coin>, // Vec<Coin> is a vector of coins
   },
+  WithdrawMany {
+    payouts: Vec<Payout>,
+  }, // pays many receivers in a single message, one BankMsg::Send per receiver
+  SetAllowance {
+    spender: String,
+    limit: Vec<Coin>,
+    period: u64, // length of the spending window in seconds, 86400 is 24h
+  }, // owner only, lets the spender call WithdrawTo up to the limit every period
+  RevokeAllowance {
+    spender: String,
+  }, // owner only, removes the allowance of the spender
+  AddReceiver {
+    receiver: String,
+  }, // owner only, the receiver is allowed after the policy delay passes
+  RemoveReceiver {
+    receiver: String,
+  }, // owner only, takes effect immediately
+  SetReceiverPolicy {
+    enforce: bool,
+    delay: u64, // seconds between adding a receiver and being able to withdraw to it
+  }, // owner only, enforce makes Withdraw and WithdrawTo reject receivers not on the list
+  // turning enforce off or shortening the delay only takes effect once the current delay passes
+  AddHook {
+    hook: String,
+    #[serde(default)]
+    on_reset: bool,
+    #[serde(default)]
+    on_withdraw: bool,
+  }, // owner only, the hook contract gets a message on every counted donation, and optionally on resets and withdrawals
+  RemoveHook {
+    hook: String,
+  }, // owner only
+  SetReceipts {
+    receipts: Option<ReceiptConfig>,
+  }, // owner only, None stops minting receipts
+  SetRewardRates {
+    rates: Vec<RewardRate>,
+  }, // owner only, replaces the rates of the given denoms, a zero rate stops rewarding the denom
+  SetGovernance {
+    governance: Option<GovernanceConfig>,
+  }, // owner only, None turns proposals off, a config restricting withdrawals can only be tightened (loosening takes a proposal)
+  Propose {
+    payouts: Vec<Payout>,
+    description: String,
+    #[serde(default)]
+    governance: Update<GovernanceConfig>,
+  }, // donors and the owner only, the payouts are sent like WithdrawMany once the proposal passes, and the governance config is updated
+  Vote {
+    id: u64,
+    yes: bool,
+  }, // donors only, once per proposal and before its deadline
+  Execute {
+    id: u64,
+  }, // anyone, once the proposal passed
+  SetStaking {
+    staking: Option<StakingConfig>,
+  }, // owner only, None stops delegating new donations, already delegated funds stay delegated
+  Redelegate {
+    src_validator: String,
+    dst_validator: String,
+    amount: Coin,
+  }, // owner only
+  Undelegate {
+    validator: String,
+    amount: Coin,
+  }, // owner only, the funds are liquid again once the unbonding period passes
+  ClaimRewards {}, // owner only, withdraws the staking rewards of all delegations to the contract
+  RetryTransfer {
+    id: u64,
+  }, // anyone, sends a failed transfer again to the current ibc parent of its campaign
+  SetHub {
+    hub: Option<HubConfig>,
+  }, // owner only, the channel has to be an open counting-v1 channel, None stops reporting
+  AddPeer {
+    peer: Peer,
+  }, // owner only, lets the peer open counting-v1 channels to this contract
+  RemovePeer {
+    peer: Peer,
+  }, // owner only, stops new channels from the peer, the ones already open stay open until closed
+  SyncCounter {}, // anyone, sends the change of the counter since the last report to the hub, meant to be called periodically
+  SetLimits {
+    limits: Option<DonationLimits>,
+  }, // owner only, None removes the limits, what donors gave so far still counts against new limits
+  SetReferrals {
+    referrals: Option<ReferralConfig>,
+  }, // owner only, None stops paying referrers, what they accrued can still be claimed
+  ClaimReferralRewards {}, // referrers only, sends what the referrer accrued so far
+  DonateFor {
+    beneficiary: String,
+    voucher: Voucher,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proof: Option<Vec<String>>,
+  }, // anyone with a voucher of the signer, the funds sent have to be the voucher amount, the donation is credited to the beneficiary
+  // proof is the merkle proof of the beneficiary, needed like for Donate while there is a merkle root
+  SetVoucherSigner {
+    pubkey: Option<Binary>,
+  }, // owner only, secp256k1 public key signing the vouchers, None stops accepting them
+  SetMerkleRoot {
+    root: Option<String>,
+  }, // owner only, hex encoded sha256 root of the tree of eligible donor addresses, None lets everyone donate again
+  SetAccessMode {
+    mode: Option<AccessMode>,
+  }, // owner only, None lets everyone donate, both lists are kept when the mode changes
+  AddToAccessList {
+    list: AccessMode,
+    donors: Vec<String>,
+  }, // owner only, adds the donors to the allowlist or the blocklist
+  RemoveFromAccessList {
+    list: AccessMode,
+    donors: Vec<String>,
+  }, // owner only, donors not on the list are skipped
+  ImportLedger {
+    page: LedgerPage,
+  }, // owner only, while restoring, adds a page exported with the ExportLedger query
+  FinishRestore {}, // owner only, checks the imported ledgers add up to the snapshot and opens the contract
 } 
 
+#[cw_serde]
+pub struct Payout {
+    pub receiver: String,
+    pub amount: PayoutAmount,
+}
+// a single entry of WithdrawMany, the receiver gets either exact funds or a share of the contract balance
+
+#[cw_serde]
+pub enum PayoutAmount {
+    Funds { funds: Vec<Coin> },
+    // exact coins to be sent to the receiver
+    Share { share: Decimal },
+    // part of the contract balance (of every denom) to be sent to the receiver, Decimal::percent(10) is 10%
+}
+
+#[cw_serde]
+pub struct GovernanceConfig {
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub voting_period: u64,
+    pub restrict_withdrawals: bool,
+}
+// a proposal passes when at least quorum of all donations voted, and at least threshold of the votes are yes
+// voting_period is in seconds, restrict_withdrawals turns off Withdraw, WithdrawTo and WithdrawMany so funds only leave through proposals
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum AccessMode {
+    Allowlist,
+    // only the donors on the allowlist can donate
+    Blocklist,
+    // everyone but the donors on the blocklist can donate
+}
+
+#[cw_serde]
+pub struct DonationWindow {
+    pub max_donations: u32,
+    pub period: u64,
+}
+// at most max_donations of a donor are counted every period, in seconds
+
+#[cw_serde]
+pub enum LimitPolicy {
+    Reject,
+    // the donation fails with RateLimited
+    KeepUncounted,
+    // the funds are kept, but the donation is handled like one below the minimal donation
+}
+
+#[cw_serde]
+pub struct DonationLimits {
+    #[serde(default)]
+    pub window: Option<DonationWindow>,
+    #[serde(default)]
+    pub max_total: Option<Uint128>,
+    #[serde(default)]
+    pub min_blocks: u64,
+    pub policy: LimitPolicy,
+}
+// limits on the counted donations of every single donor, to keep bots from inflating the counter
+// max_total is what a donor can give in the minimal donation denom of the default campaign over all its counted donations
+// min_blocks is the gap between two counted donations of a donor, 0 turns it off
+// the limits apply to the donations of a donor to every campaign together
+
+#[cw_serde]
+pub struct StakingConfig {
+    pub validator: String,
+    pub fraction: Decimal,
+}
+// fraction of every donation in the bond denom which is delegated to the validator, Decimal::percent(50) stakes half of it
+
+#[cw_serde]
+pub struct ReceiptConfig {
+    pub cw721: String,
+    pub token_uri: String,
+    pub threshold: Option<Uint128>,
+}
+// cw721 is the nft contract receipts are minted on, this contract has to be its minter
+// token_uri is a template, {donor}, {amount}, {denom}, {counter} and {time} are replaced with the values of the donation
+// with a threshold, only donations of at least that amount (in the minimal donation denom) get a receipt
+
+#[cw_serde]
+pub enum Cw721ExecMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Option<Empty>,
+    },
+}
+// the only message of cw721-base we send, the receipt data is kept in the token uri
+
+#[cw_serde]
+pub struct DonationHookMsg {
+    pub donor: String,
+    pub funds: Vec<Coin>,
+    pub counter: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+}
+// donor is the sender of the message, funds are what was donated (or withdrawn), counter is the value after the message
+// campaign is set for the resets and withdrawals of the other campaigns, None is the default campaign
+
+#[cw_serde]
+pub enum HookExecMsg {
+    DonationHook(DonationHookMsg),
+    ResetHook(DonationHookMsg),
+    WithdrawHook(DonationHookMsg),
+}
+// execute message sent to the hook contracts, they only need to handle the variants they subscribed to
+
+// messages only the chain (governance) can send, through the sudo entry point
+#[cw_serde]
+pub enum SudoMsg {
+  ResetCounter {
+    #[serde(default)]
+    counter: u64,
+  }, // sets the counter without the owner
+  ReplaceOwner {
+    owner: String,
+  }, // hands the contract over to a new owner
+  Pause {}, // rejects every execute message until unpaused
+  Unpause {},
+  IbcLifecycleComplete(IbcLifecycleComplete),
+  // ibc-hooks callback with the outcome of a transfer to an ibc parent
+}
+
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+  IbcAck {
+    channel: String,
+    sequence: u64,
+    ack: String,
+    success: bool,
+  },
+  IbcTimeout {
+    channel: String,
+    sequence: u64,
+  },
+}
+// channel and sequence are of the packet sent by the contract, as the ibc-hooks middleware reports them
+
 // #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 // #[serde(rename_all = "snake_case")]
 #[cw_serde] // using this instead of the above, generates all the boilerplate code for us
@@ -65,11 +501,402 @@ pub struct ValueResp {
 }
 
 #[cw_serde]
-pub struct MigrateMsg {
+pub struct HistoryEntry {
+    pub height: u64,
+    pub value: u64,
+}
+// value is what the counter was at the end of the block
+
+#[cw_serde]
+pub struct HistoryResp {
+    pub entries: Vec<HistoryEntry>,
+}
+// pass the height of the last entry as start_after to get the next page
+
+#[cw_serde]
+pub struct AllowanceResp {
+    pub limit: Vec<Coin>,
+    pub period: u64,
+    pub remaining: Vec<Coin>,
+    pub resets_at: Timestamp,
+}
+// remaining is what the spender can still withdraw until resets_at, all empty if there is no allowance
+
+#[cw_serde]
+pub struct HookInfo {
+    pub hook: String,
+    pub on_reset: bool,
+    pub on_withdraw: bool,
+}
+
+#[cw_serde]
+pub struct HooksResp {
+    pub hooks: Vec<HookInfo>,
+}
+
+#[cw_serde]
+pub struct RewardRateResp {
+    pub token: Option<String>,
+    pub rates: Vec<RewardRate>,
+}
+// token is None when the contract was instantiated without rewards
+
+#[cw_serde]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+#[cw_serde]
+pub struct ProposalResp {
+    pub id: u64,
+    pub proposer: String,
+    pub description: String,
+    pub payouts: Vec<Payout>,
+    pub expires: Timestamp,
+    pub total_weight: Uint128,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub status: ProposalStatus,
+    pub governance: Update<GovernanceConfig>,
+}
+// a proposal is passed as soon as it has enough votes, and rejected when its deadline passes without them
+
+#[cw_serde]
+pub struct ContributionResp {
+    pub donor: String,
+    pub amount: Uint128,
+    pub total: Uint128,
+}
+// total is what all the donors gave together
+
+#[cw_serde]
+pub struct Contribution {
+    pub donor: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ContributionsResp {
+    pub denom: String,
+    pub contributions: Vec<Contribution>,
+}
+// denom is the minimal donation denom, the only one recorded in the donor ledger
+// pass the last donor as start_after to get the next page, a page shorter than the limit is the last one
+
+#[cw_serde]
+pub struct FailedTransferInfo {
+    pub id: u64,
+    pub campaign: String,
+    pub channel_id: String,
+    pub receiver: String,
+    pub amount: Coin,
+}
+
+#[cw_serde]
+pub struct FailedTransfersResp {
+    pub transfers: Vec<FailedTransferInfo>,
+}
+// the refund is back on the balance of the campaign, a retry sends it to the ibc parent the campaign has at the time
+
+#[cw_serde]
+pub struct CampaignInfo {
+    pub id: String,
+    pub owner: String,
+    pub minimal_donation: Coin,
+    pub counter: u64,
+    pub donating_parent: Option<u64>,
+    pub parent: Option<Parent>,
+    pub balance: Vec<Coin>,
+}
+// balance is what was donated to the campaign and not withdrawn or forwarded to its parent yet
+
+#[cw_serde]
+pub struct RemoteCounter {
+    pub channel_id: String,
+    pub value: u64,
+}
+
+#[cw_serde]
+pub struct RemoteCountersResp {
+    pub counters: Vec<RemoteCounter>,
+}
+
+#[cw_serde]
+pub struct PeersResp {
+    pub peers: Vec<Peer>,
+}
+
+#[cw_serde]
+pub struct DelegationInfo {
+    pub validator: String,
+    pub amount: Coin,
+}
+
+#[cw_serde]
+pub struct DonorActivity {
+    pub window_start: Timestamp,
+    pub donations: u32,
+    pub total: Uint128,
+    pub last_height: Option<u64>,
+}
+// counted donations of a donor since window_start, and over all time in total, last_height is the block of the latest one
+
+#[cw_serde]
+pub enum ReferralPayout {
+    Immediate,
+    // the referral share is sent to the referrer with the donation, or accrued while the referrer can't receive funds
+    Accrue,
+    // the referral share is kept for the referrer until it claims it
+}
+
+#[cw_serde]
+pub struct ReferralConfig {
+    pub share: Decimal,
+    pub payout: ReferralPayout,
+}
+// share of every counted donation to the default campaign the referrer gets, Decimal::percent(5) is 5%
+
+#[cw_serde]
+pub struct ReferralsResp {
+    pub referrals: Option<ReferralConfig>,
+    pub accrued: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ReferrerResp {
+    pub address: String,
+    pub donors: u64,
+    pub donations: u64,
+    pub earned: Vec<Coin>,
+    pub accrued: Vec<Coin>,
+}
+// donors are the ones referred by the address, donations are their counted donations it got a share of
+// earned is everything the referrer got, accrued is the part of it not claimed yet
+
+#[cw_serde]
+pub struct Voucher {
+    pub amount: Coin,
+    pub nonce: u64,
+    pub expiry: Timestamp,
+    pub signature: Binary,
+}
+// signature is the 64 bytes secp256k1 signature of the signer over the sha256 of the json encoded VoucherPayload
+
+#[cw_serde]
+pub struct VoucherPayload {
+    pub contract: String,
+    pub beneficiary: String,
+    pub amount: Coin,
+    pub nonce: u64,
+    pub expiry: Timestamp,
+}
+// what the voucher signer signs, fields are serialized in this order
+// the contract address keeps a voucher from being used on another contract with the same signer
+
+#[cw_serde]
+pub struct VoucherSignerResp {
+    pub pubkey: Option<Binary>,
+    pub used: bool,
+}
+
+#[cw_serde]
+pub struct MerkleRootResp {
+    pub root: Option<String>,
+    pub verified: bool,
+}
+// verified donors can donate without a proof until the root changes
+
+#[cw_serde]
+pub struct AccessListResp {
+    pub mode: Option<AccessMode>,
+    pub donors: Vec<String>,
+}
+// mode is the current one, donors are empty when no list was asked for and anyone can donate
+
+#[cw_serde]
+pub struct LimitsResp {
+    pub limits: Option<DonationLimits>,
+    pub activity: Option<DonorActivity>,
+}
+// activity is None when no donor was asked for, or the donor has no counted donation yet
+
+#[cw_serde]
+pub struct StakingResp {
+    pub staking: Option<StakingConfig>,
+    pub delegations: Vec<DelegationInfo>,
+}
+// staking is None when donations are not delegated, delegations are listed even then
+
+#[cw_serde]
+pub struct MigrationStep {
+    pub from: String,
+    pub to: String,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+}
+// single hop of a migration, reads and writes are the raw storage keys
+
+#[cw_serde]
+pub struct MigrationPlanResp {
+    pub from_version: String,
+    pub to_version: String,
+    pub steps: Vec<MigrationStep>,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+}
+// steps are in the order they run, empty when the contract is already at to_version
+// reads and writes are the keys migrate touches after the steps, while updating the config and the contract version
+
+#[cw_serde]
+pub struct Snapshot {
+    pub format: u32,
+    pub contract: String,
+    pub version: String,
+    pub data: SnapshotData,
+    pub checksum: Binary,
+}
+// format is the version of the snapshot layout itself, contract and version are the cw2 info of the exported contract
+// checksum is the sha256 of everything else in the snapshot, checked before restoring
+
+#[cw_serde]
+pub struct SnapshotData {
+    pub counter: u64,
+    pub minimal_donation: Coin,
+    pub owner: String,
+    pub donating_parent: Option<u64>,
     pub parent: Option<Parent>,
+    pub allowances: Vec<AllowanceSnapshot>,
+    pub receiver_policy: ReceiverPolicySnapshot,
+    pub receivers: Vec<ReceiverSnapshot>,
+    pub hooks: Vec<HookInfo>,
+    pub receipts: Option<ReceiptConfig>,
+    pub receipts_minted: u64,
+    pub reward_token: Option<String>,
+    pub reward_rates: Vec<RewardRate>,
+    pub governance: Option<GovernanceConfig>,
+    pub staking: Option<StakingConfig>,
+    pub limits: Option<DonationLimits>,
+    pub access_mode: Option<AccessMode>,
+    pub merkle_root: Option<String>,
+    pub voucher_signer: Option<Binary>,
+    pub referrals: Option<ReferralConfig>,
+    pub paused: bool,
+    pub total_donated: Uint128,
+    pub referral_accrued: Vec<Coin>,
+}
+// the ledgers are exported with ExportLedger, total_donated and referral_accrued are what the imported ones have to add up to
+// delegations are not exported, they have to be undelegated before moving the funds to the new deployment
+// neither are failed transfers nor the ones in flight, their refunds are liquid funds moved with the rest of the balance
+// ibc channels belong to the deployed contract, so the hub, the remote counters and the peers are not exported either
+// open proposals are not exported, their votes are weighted by heights of the old chain
+// donor activity the limits are checked against is not exported, it depends on the blocks of the old chain
+// verified merkle proofs are not exported, donors pass them again on the new deployment
+// used voucher nonces are not exported, the vouchers are signed for the address of the old deployment anyway
+// the accrued referral rewards stay a debt of the restored contract, their funds have to be moved with the rest of the balance
+
+#[cw_serde]
+pub struct DonationSnapshot {
+    pub donor: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ReferrerSnapshot {
+    pub address: String,
+    pub donors: u64,
+    pub donations: u64,
+    pub earned: Vec<Coin>,
+    pub accrued: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ReferralSnapshot {
+    pub donor: String,
+    pub referrer: String,
+}
+
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum Ledger {
+    Donations,
+    Campaigns,
+    Allowlist,
+    Blocklist,
+    Referrers,
+    Referrals,
+}
+// storage which grows with the donors, exported page by page instead of with the snapshot
+
+#[cw_serde]
+pub enum LedgerPage {
+    Donations(Vec<DonationSnapshot>),
+    Campaigns(Vec<CampaignInfo>),
+    Allowlist(Vec<String>),
+    Blocklist(Vec<String>),
+    Referrers(Vec<ReferrerSnapshot>),
+    Referrals(Vec<ReferralSnapshot>),
+}
+// referrals are the referrer of every referred donor
+
+#[cw_serde]
+pub struct AllowanceSnapshot {
+    pub spender: String,
+    pub limit: Vec<Coin>,
+    pub period: u64,
+    pub spent: Vec<Coin>,
+    pub period_start: Timestamp,
+}
+
+#[cw_serde]
+pub struct ReceiverPolicySnapshot {
+    pub enforce: bool,
+    pub delay: u64,
+    #[serde(default)]
+    pub pending: Option<PendingReceiverPolicy>,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct PendingReceiverPolicy {
+    pub enforce: bool,
+    pub delay: u64,
+    pub effective: Timestamp,
+}
+// looser receiver policy waiting for the current delay to pass before it replaces the current one
+
+#[cw_serde]
+pub struct ReceiverSnapshot {
+    pub receiver: String,
+    pub allowed_from: Timestamp,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum Update<T> {
+    #[default]
+    Keep, // fields missing from the message are kept
+    Set(T),
+    Clear,
+}
+// tri-state field, unlike Option it can tell "leave it as it is" (keep) apart from "remove it" (clear)
+
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {
+    #[serde(default)]
+    pub parent: Update<Parent>,
+    #[serde(default)]
+    pub minimal_donation: Update<Coin>,
+    #[serde(default)]
+    pub owner: Update<String>,
+    #[serde(default)]
+    pub counter: Update<u64>,
 }
-// added parent field which is an Option type, meaning it can be None or Some
-// this message is used to migrate the contract to a new version
+// every field is applied after the storage migration, also when migrating to the same version, so admins can repair the config
+// clearing minimal_donation makes every donation count, clearing the counter sets it to 0, the owner can't be cleared
 
 // --------- ADDITIONAL NOTES ------------ // 
 // Msg is a type that represents a list of execution messages
@@ -1,5 +1,10 @@
 // used to make unit tests more compact and very expressive in terms of test scenarios they implement
 
 pub mod contract; // import the contract module
+pub mod merkle; // merkle roots and proofs of donor addresses, for the tests of the contract and of the contracts using it
+#[cfg(test)]
+mod ibc; // unit tests of the ibc entry points, multitest can't relay packets so they use the cosmwasm_std mocks
+#[cfg(test)]
+mod stubs; // stub contracts used only as a migration source in tests
 #[cfg(test)] // only compile the tests module if we are running tests, builds only on our test run
 mod tests; // import the tests module
\ No newline at end of file
@@ -4,13 +4,18 @@
 // The proxy contract is deployed to the blockchain and can be interacted with by users. 
 // More info in additional notes at the end of the file
 
-use cosmwasm_std::{Addr, Coin, StdResult};
+use cosmwasm_std::{Addr, Binary, Coin, StdResult};
 use cw_multi_test::{App, ContractWrapper, Executor};
 
 use crate::error::ContractError;
-use crate::msg::{ExecMsg, InstantiateMsg, MigrateMsg, Parent, QueryMsg, ValueResp};
-use crate::{execute, instantiate, migrate, query};
+use crate::msg::{
+    AccessListResp, AccessMode, AllowanceResp, Ledger, LedgerPage, CampaignInfo, ContributionResp, ContributionsResp, DonationLimits, ExecMsg, GovernanceConfig, HistoryResp, HooksResp, InstantiateMsg,
+    LimitsResp, MerkleRootResp, MigrateMsg, MigrationPlanResp, Parent, Payout, ProposalResp, QueryMsg, ReceiptConfig, ReferralConfig, ReferrerResp, RewardRate, RewardRateResp, Snapshot,
+    StakingConfig, StakingResp, SudoMsg, Update, ValueResp, Voucher, VoucherSignerResp,
+};
+use crate::{execute, instantiate, migrate, query, reply, sudo};
 
+#[derive(Debug)]
 pub struct CountingContract(Addr);
 // Creating the proxy type
 
@@ -20,8 +25,11 @@ impl CountingContract {
   }
   // Adding utilities to get access to the underlying address
 pub fn store_code(app: &mut App) -> u64 {
-    let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
-    // assigning the contract to the contract wrapper (represents a sc on a blockchain) (with_migrate), and adding the migrate function
+    let contract = ContractWrapper::new(execute, instantiate, query)
+        .with_migrate(migrate)
+        .with_sudo(sudo)
+        .with_reply(reply);
+    // assigning the contract to the contract wrapper (represents a sc on a blockchain) (with_migrate), and adding the migrate, sudo and reply functions
     app.store_code(Box::new(contract)) 
     // use app to store the code of the contract in the blockchain
     // storing the contract in the blockchain, parameter is a Box that contains the new contract
@@ -33,6 +41,7 @@ pub fn store_code(app: &mut App) -> u64 {
 // track_caller: if the test fails, it will show the line number of the test that failed
 // info on 'a: https://doc.rust-lang.org/book/ch10-03-lifetime-syntax.html
 // https://stackoverflow.com/questions/47640550/what-is-a-in-rust-language#:~:text=The%20'a%20reads%20'the%20lifetime,which%20lifetimes%20are%20one%20kind.
+  #[allow(clippy::too_many_arguments)] // mirrors the fields of InstantiateMsg::New
   pub fn instantiate<'a>(
     app:&mut App, // borrows the mutable app from the test (in multitest), we can use it to instantiate the contract
     code_id: u64,
@@ -45,7 +54,7 @@ pub fn store_code(app: &mut App) -> u64 {
  // passing a reference to an address, so we can pass a reference
     // impl is a trait, Into is a trait that converts a value into another type
     // for example, we can pass a string, and convert it into an address
-  ) -> StdResult<Self> {
+  ) -> Result<Self, ContractError> {
     let admin = admin.into();
        // assigning the admin value to admin. into() is a method that converts the value into the usually inferred input type
     let counter = counter.into().unwrap_or_default();
@@ -55,10 +64,13 @@ pub fn store_code(app: &mut App) -> u64 {
   app.instantiate_contract(
             code_id,
             sender.clone(),
-            &InstantiateMsg {
+            &InstantiateMsg::New {
                 counter,
                 minimal_donation,
                 parent,
+                rewards: None,
+                owner: None,
+                limits: None,
             },
             &[],
             label,
@@ -71,15 +83,67 @@ pub fn store_code(app: &mut App) -> u64 {
   // We can eliminate arguments we don't need for our contract, in this case, we don't need funds, so we pass an empty slice, and we don't need an admin, so we pass None
   // .map_err(|err| err.downcast().unwrap()) convert the error type to the one we want, in this case, we want to convert the error type and return the error exactly as it is
 
+  #[track_caller]
+  pub fn restore<'a>(
+    app: &mut App,
+    code_id: u64,
+    sender: &Addr,
+    label: &str,
+    admin: impl Into<Option<&'a Addr>>,
+    snapshot: Snapshot,
+  ) -> Result<Self, ContractError> {
+    let admin = admin.into();
+    app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            &InstantiateMsg::FromSnapshot { snapshot: Box::new(snapshot) },
+            &[],
+            label,
+            admin.map(Addr::to_string),
+        )
+        .map(CountingContract)
+        .map_err(|err| err.downcast().unwrap())
+  }
+  // Instantiate the contract from a snapshot exported by another instance
+
+  #[track_caller]
+  pub fn instantiate_with_msg<'a>(
+    app: &mut App,
+    code_id: u64,
+    sender: &Addr,
+    label: &str,
+    admin: impl Into<Option<&'a Addr>>,
+    msg: InstantiateMsg,
+  ) -> Result<Self, ContractError> {
+    let admin = admin.into();
+    app.instantiate_contract(code_id, sender.clone(), &msg, &[], label, admin.map(Addr::to_string))
+        .map(CountingContract)
+        .map_err(|err| err.downcast().unwrap())
+  }
+  // Instantiate the contract with a message built by the test, for the fields the helper above doesn't take
+
     #[track_caller]
-    pub fn migrate(app: &mut App, contract: Addr, code_id: u64, sender: &Addr, parent: impl Into<Option<Parent>>) -> StdResult<Self> {
+    pub fn migrate(app: &mut App, contract: Addr, code_id: u64, sender: &Addr, parent: impl Into<Option<Parent>>) -> Result<Self, ContractError> {
 
-        let parent = parent.into();
-        
-        app.migrate_contract(sender.clone(), contract.clone(), &MigrateMsg { parent }, code_id)
+        let parent = match parent.into() {
+            Some(parent) => Update::Set(parent),
+            None => Update::Keep,
+        }; // no parent passed keeps the parent as it is
+        let msg = MigrateMsg {
+            parent,
+            ..MigrateMsg::default()
+        };
+
+        Self::migrate_with_msg(app, contract, code_id, sender, msg)
+    }
+
+    #[track_caller]
+    pub fn migrate_with_msg(app: &mut App, contract: Addr, code_id: u64, sender: &Addr, msg: MigrateMsg) -> Result<Self, ContractError> {
+        app.migrate_contract(sender.clone(), contract.clone(), &msg, code_id)
             .map_err(|err| err.downcast().unwrap()) // convert the error type and return the error exactly as it is
             .map(|_| Self(contract)) // map the result to a new instance of the contract
     }
+    // migrate with the full message, to update the config during the migration
   // Migrate the contract, passing the contract, code_id, and sender
   // Here, we don't need to pass any msg arguments, so we pass an empty struct, Empty{}
   // .map(|_| Self(contract)) map the result to a new instance of the contract, passing the contract, returning contract address wrapped in the new helper type
@@ -92,7 +156,7 @@ pub fn store_code(app: &mut App) -> u64 {
         sender: &Addr,
         funds: &[Coin],
     ) -> Result<(), ContractError> {
-        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Donate {}, funds)
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Donate { campaign: None, proof: None, referrer: None }, funds)
             .map_err(|err| err.downcast().unwrap()) // convert the error type and return the error exactly as it is
             .map(|_| ()) // map the result to a unit type, which is a type that has only one value, which is ()
     } // function to donate to the contract, passing the sender, and funds
@@ -110,7 +174,7 @@ pub fn reset(
   app.execute_contract(
     sender.clone(),
     self.0.clone(),
-    &ExecMsg::Reset { counter },
+    &ExecMsg::Reset { counter, campaign: None },
     &[],
   )
 .map_err(|err| err.downcast().unwrap())
@@ -120,7 +184,7 @@ pub fn reset(
 
 #[track_caller]
 pub fn withdraw(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
-  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Withdraw {}, &[])
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Withdraw { campaign: None }, &[])
     .map_err(|err| err.downcast().unwrap())
     .map(|_| ())
 }
@@ -150,11 +214,633 @@ pub fn withdraw_to(
 }
 // function to withdraw the funds to a specific address, passing the sender, receiver, and funds
 
+#[track_caller]
+pub fn withdraw_many(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  payouts: Vec<Payout>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::WithdrawMany { payouts },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to withdraw the funds to many receivers at once, passing the sender and the payouts
+
+
+#[track_caller]
+pub fn set_allowance(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  spender: &Addr,
+  limit: Vec<Coin>,
+  period: u64,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetAllowance {
+      spender: spender.to_string(),
+      limit,
+      period,
+    },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to give the spender an allowance, passing the sender, spender, limit and period in seconds
+
+#[track_caller]
+pub fn revoke_allowance(&self, app: &mut App, sender: &Addr, spender: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::RevokeAllowance {
+      spender: spender.to_string(),
+    },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to remove the allowance of the spender
+
+
+#[track_caller]
+pub fn add_receiver(&self, app: &mut App, sender: &Addr, receiver: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::AddReceiver {
+      receiver: receiver.to_string(),
+    },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to add the receiver to the allowlist, it is allowed once the policy delay passes
+
+#[track_caller]
+pub fn remove_receiver(&self, app: &mut App, sender: &Addr, receiver: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::RemoveReceiver {
+      receiver: receiver.to_string(),
+    },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to remove the receiver from the allowlist
+
+#[track_caller]
+pub fn set_receiver_policy(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  enforce: bool,
+  delay: u64,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetReceiverPolicy { enforce, delay },
+    &[],
+  )
+.map_err(|err| err.downcast().unwrap())
+.map(|_| ())
+}
+// function to turn the receiver allowlist on or off and set the delay for new receivers
+
+
+#[track_caller]
+pub fn sudo(&self, app: &mut App, msg: SudoMsg) -> Result<(), ContractError> {
+  app.wasm_sudo(self.0.clone(), &msg)
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+// function to call the contract as the chain would, for example after a governance proposal passes
+
+
+#[track_caller]
+pub fn add_hook(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  hook: &Addr,
+  on_reset: bool,
+  on_withdraw: bool,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::AddHook {
+      hook: hook.to_string(),
+      on_reset,
+      on_withdraw,
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to subscribe a contract to the donations, and optionally to resets and withdrawals
+
+#[track_caller]
+pub fn remove_hook(&self, app: &mut App, sender: &Addr, hook: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::RemoveHook {
+      hook: hook.to_string(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to unsubscribe a hook contract
+
+
+#[track_caller]
+pub fn set_receipts(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  receipts: impl Into<Option<ReceiptConfig>>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetReceipts {
+      receipts: receipts.into(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to configure the nft receipts minted for donations, None turns them off
+
+
+#[track_caller]
+pub fn set_reward_rates(&self, app: &mut App, sender: &Addr, rates: Vec<RewardRate>) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetRewardRates { rates },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to change how many supporter tokens are minted per donated denom
+
+
+#[track_caller]
+pub fn set_governance(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  governance: impl Into<Option<GovernanceConfig>>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetGovernance {
+      governance: governance.into(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to configure the donor governance, None turns it off
+
+#[track_caller]
+pub fn propose(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  payouts: Vec<Payout>,
+  description: &str,
+) -> Result<u64, ContractError> {
+  let resp = app
+    .execute_contract(
+      sender.clone(),
+      self.0.clone(),
+      &ExecMsg::Propose {
+        payouts,
+        description: description.to_owned(),
+        governance: Update::Keep,
+      },
+      &[],
+    )
+    .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+  let id = resp
+    .events
+    .iter()
+    .flat_map(|event| &event.attributes)
+    .find(|attr| attr.key == "proposal_id")
+    .map(|attr| attr.value.parse().unwrap())
+    .unwrap();
+
+  Ok(id)
+}
+// function to propose payouts to the donors, returns the id of the new proposal
+
+#[track_caller]
+pub fn propose_governance(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  governance: Update<GovernanceConfig>,
+  description: &str,
+) -> Result<u64, ContractError> {
+  let resp = app
+    .execute_contract(
+      sender.clone(),
+      self.0.clone(),
+      &ExecMsg::Propose {
+        payouts: vec![],
+        description: description.to_owned(),
+        governance,
+      },
+      &[],
+    )
+    .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+  let id = resp
+    .events
+    .iter()
+    .flat_map(|event| &event.attributes)
+    .find(|attr| attr.key == "proposal_id")
+    .map(|attr| attr.value.parse().unwrap())
+    .unwrap();
+
+  Ok(id)
+}
+// function to propose a new governance config, the only way to loosen one which restricts withdrawals
+
+#[track_caller]
+pub fn vote(&self, app: &mut App, sender: &Addr, id: u64, yes: bool) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Vote { id, yes }, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+// function to vote on a proposal with the weight of the sender donations
+
+#[track_caller]
+pub fn execute_proposal(&self, app: &mut App, sender: &Addr, id: u64) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Execute { id }, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+// function to send the payouts of a passed proposal
+
+
+#[track_caller]
+pub fn set_staking(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  staking: impl Into<Option<StakingConfig>>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetStaking {
+      staking: staking.into(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to configure which part of the bond denom donations is delegated, None turns it off
+
+#[track_caller]
+pub fn redelegate(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  src_validator: &str,
+  dst_validator: &str,
+  amount: Coin,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Redelegate {
+      src_validator: src_validator.to_owned(),
+      dst_validator: dst_validator.to_owned(),
+      amount,
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to move delegated funds between validators
+
+#[track_caller]
+pub fn undelegate(&self, app: &mut App, sender: &Addr, validator: &str, amount: Coin) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Undelegate {
+      validator: validator.to_owned(),
+      amount,
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to start unbonding delegated funds
+
+#[track_caller]
+pub fn claim_rewards(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::ClaimRewards {}, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+// function to withdraw the staking rewards to the contract
+
+
+#[track_caller]
+pub fn create_campaign(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  id: &str,
+  minimal_donation: Coin,
+  owner: Option<&Addr>,
+  parent: impl Into<Option<Parent>>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::CreateCampaign {
+      id: id.to_owned(),
+      minimal_donation,
+      owner: owner.map(Addr::to_string),
+      parent: parent.into(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to create a campaign, owned by the sender unless another owner is given
+
+#[track_caller]
+pub fn donate_campaign(&self, app: &mut App, sender: &Addr, campaign: &str, funds: &[Coin]) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Donate {
+      campaign: Some(campaign.to_owned()),
+      proof: None,
+      referrer: None,
+    },
+    funds,
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to donate to a campaign other than the default one
+
+#[track_caller]
+pub fn reset_campaign(&self, app: &mut App, sender: &Addr, campaign: &str, counter: u64) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Reset {
+      counter,
+      campaign: Some(campaign.to_owned()),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+#[track_caller]
+pub fn withdraw_campaign(&self, app: &mut App, sender: &Addr, campaign: &str) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Withdraw {
+      campaign: Some(campaign.to_owned()),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to withdraw the funds donated to a campaign
+
+
+#[track_caller]
+pub fn set_limits(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  limits: impl Into<Option<DonationLimits>>,
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetLimits {
+      limits: limits.into(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to limit the counted donations of every donor, None removes the limits
+
+
+#[track_caller]
+pub fn set_access_mode(&self, app: &mut App, sender: &Addr, mode: impl Into<Option<AccessMode>>) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetAccessMode { mode: mode.into() },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to switch between the allowlist and the blocklist, None lets everyone donate
+
+#[track_caller]
+pub fn add_to_access_list(&self, app: &mut App, sender: &Addr, list: AccessMode, donors: &[&Addr]) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::AddToAccessList {
+      list,
+      donors: donors.iter().map(|donor| donor.to_string()).collect(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+#[track_caller]
+pub fn remove_from_access_list(&self, app: &mut App, sender: &Addr, list: AccessMode, donors: &[&Addr]) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::RemoveFromAccessList {
+      list,
+      donors: donors.iter().map(|donor| donor.to_string()).collect(),
+    },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+
+#[track_caller]
+pub fn donate_with_proof(&self, app: &mut App, sender: &Addr, proof: Vec<String>, funds: &[Coin]) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Donate {
+      campaign: None,
+      proof: Some(proof),
+      referrer: None,
+    },
+    funds,
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to donate with a merkle proof of the sender, see the merkle module to build it
+
+#[track_caller]
+pub fn set_merkle_root(&self, app: &mut App, sender: &Addr, root: impl Into<Option<String>>) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetMerkleRoot { root: root.into() },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+
+#[track_caller]
+pub fn donate_for(
+  &self,
+  app: &mut App,
+  sender: &Addr,
+  beneficiary: &Addr,
+  voucher: Voucher,
+  proof: impl Into<Option<Vec<String>>>,
+  funds: &[Coin],
+) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::DonateFor {
+      beneficiary: beneficiary.to_string(),
+      voucher,
+      proof: proof.into(),
+    },
+    funds,
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+// function to donate on behalf of the beneficiary with a signed voucher
+
+#[track_caller]
+pub fn set_voucher_signer(&self, app: &mut App, sender: &Addr, pubkey: impl Into<Option<Binary>>) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetVoucherSigner { pubkey: pubkey.into() },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+
+#[track_caller]
+pub fn set_referrals(&self, app: &mut App, sender: &Addr, referrals: impl Into<Option<ReferralConfig>>) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::SetReferrals { referrals: referrals.into() },
+    &[],
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+#[track_caller]
+pub fn donate_with_referrer(&self, app: &mut App, sender: &Addr, referrer: &Addr, funds: &[Coin]) -> Result<(), ContractError> {
+  app.execute_contract(
+    sender.clone(),
+    self.0.clone(),
+    &ExecMsg::Donate {
+      campaign: None,
+      proof: None,
+      referrer: Some(referrer.to_string()),
+    },
+    funds,
+  )
+  .map_err(|err| err.downcast().unwrap())
+  .map(|_| ())
+}
+
+#[track_caller]
+pub fn claim_referral_rewards(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::ClaimReferralRewards {}, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+
+
+#[track_caller]
+pub fn import_ledger(&self, app: &mut App, sender: &Addr, page: LedgerPage) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::ImportLedger { page }, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+
+#[track_caller]
+pub fn finish_restore(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+  app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::FinishRestore {}, &[])
+    .map_err(|err| err.downcast().unwrap())
+    .map(|_| ())
+}
+
 
     #[track_caller]
     pub fn query_value(&self, app: &App) -> StdResult<ValueResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::Value {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Value { campaign: None })
     }
     // we don't change blockchain state, so we don't need a mutable reference to the app (mut &App)
     // Obviously, for queries, we need to return some reasonable data - the result of the query.
@@ -162,8 +848,231 @@ pub fn withdraw_to(
     // The body of the query_value method calls the query_wasm_smart method on the app argument and passes it the Addr field of the CountingContract struct and a QueryMsg::Value message. 
     // The query_wasm_smart method is a method of the App struct that is used to query a smart contract.
 
+
+    #[track_caller]
+    pub fn query_allowance(&self, app: &App, spender: &Addr) -> StdResult<AllowanceResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Allowance {
+                spender: spender.to_string(),
+            },
+        )
+    }
+    // query the allowance of the spender in the current period
+
+
+    #[track_caller]
+    pub fn query_migration_plan(&self, app: &App, from_version: &str) -> StdResult<MigrationPlanResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::MigrationPlan {
+                from_version: from_version.to_owned(),
+            },
+        )
+    }
+    // query what a migration from the given version would do
+
+
+    #[track_caller]
+    pub fn query_export_state(&self, app: &App) -> StdResult<Snapshot> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::ExportState {})
+    }
+    // export the whole contract storage as a snapshot
+
+
+    #[track_caller]
+    pub fn query_value_at(&self, app: &App, height: u64) -> StdResult<ValueResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::ValueAt { height })
+    }
+    // query the value the counter had at the beginning of the given block
+
+
+    #[track_caller]
+    pub fn query_history(&self, app: &App, start_after: impl Into<Option<u64>>, limit: impl Into<Option<u32>>) -> StdResult<HistoryResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::History {
+                start_after: start_after.into(),
+                limit: limit.into(),
+            },
+        )
+    }
+    // query the heights at which the counter changed
+
+
+    #[track_caller]
+    pub fn query_hooks(&self, app: &App) -> StdResult<HooksResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Hooks {})
+    }
+    // query the hook contracts and their subscriptions
+
+
+    #[track_caller]
+    pub fn query_reward_rate(&self, app: &App) -> StdResult<RewardRateResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::RewardRate {})
+    }
+    // query the reward token and its rates
+
+
+    #[track_caller]
+    pub fn query_proposal(&self, app: &App, id: u64) -> StdResult<ProposalResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Proposal { id })
+    }
+    // query a proposal with its votes and status
+
+
+    #[track_caller]
+    pub fn query_contribution(&self, app: &App, donor: &Addr) -> StdResult<ContributionResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Contribution {
+                donor: donor.to_string(),
+            },
+        )
+    }
+    // query what the donor gave, out of all donations
+
+
+    #[track_caller]
+    pub fn query_staking(&self, app: &App) -> StdResult<StakingResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Staking {})
+    }
+    // query the staking config and the delegations of the contract
+
+
+    #[track_caller]
+    pub fn query_campaign_value(&self, app: &App, campaign: &str) -> StdResult<ValueResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Value {
+                campaign: Some(campaign.to_owned()),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_campaign(&self, app: &App, id: &str) -> StdResult<CampaignInfo> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Campaign { id: id.to_owned() })
+    }
+    // query the config, counter and funds of a campaign
+
+
+    #[track_caller]
+    pub fn query_contributions(
+        &self,
+        app: &App,
+        from_height: impl Into<Option<u64>>,
+        to_height: impl Into<Option<u64>>,
+        start_after: impl Into<Option<String>>,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<ContributionsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Contributions {
+                from_height: from_height.into(),
+                to_height: to_height.into(),
+                start_after: start_after.into(),
+                limit: limit.into(),
+            },
+        )
+    }
+    // query what every donor gave between two heights
+
+
+    #[track_caller]
+    pub fn query_limits(&self, app: &App, donor: impl Into<Option<&'static str>>) -> StdResult<LimitsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Limits {
+                donor: donor.into().map(str::to_owned),
+            },
+        )
+    }
+    // query the donation limits, and what the donor used of them
+
+
+    #[track_caller]
+    pub fn query_access_list(
+        &self,
+        app: &App,
+        list: impl Into<Option<AccessMode>>,
+        start_after: impl Into<Option<String>>,
+        limit: impl Into<Option<u32>>,
+    ) -> StdResult<AccessListResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::AccessList {
+                list: list.into(),
+                start_after: start_after.into(),
+                limit: limit.into(),
+            },
+        )
+    }
+    // query a page of the allowlist or the blocklist
+
+
+    #[track_caller]
+    pub fn query_merkle_root(&self, app: &App, donor: &Addr) -> StdResult<MerkleRootResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::MerkleRoot {
+                donor: Some(donor.to_string()),
+            },
+        )
+    }
+    // query the merkle root, and whether the donor is verified against it
+
+
+    #[track_caller]
+    pub fn query_voucher_signer(&self, app: &App, nonce: impl Into<Option<u64>>) -> StdResult<VoucherSignerResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::VoucherSigner { nonce: nonce.into() },
+        )
+    }
+    // query the voucher signer, and whether the nonce was already used
+
+
+    #[track_caller]
+    pub fn query_referrer(&self, app: &App, address: &Addr) -> StdResult<ReferrerResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Referrer {
+                address: address.to_string(),
+            },
+        )
+    }
+    // query the stats of a referrer
+
+
+    #[track_caller]
+    pub fn query_export_ledger(&self, app: &App, ledger: Ledger, start_after: Option<String>, limit: impl Into<Option<u32>>) -> StdResult<LedgerPage> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ExportLedger {
+                ledger,
+                start_after,
+                limit: limit.into(),
+            },
+        )
+    }
+    // export a page of a ledger, starting after the given key
+
 } // all the methods are in the impl block
 
+impl From<Addr> for CountingContract {
+  fn from(addr: Addr) -> Self {
+    Self(addr)
+  }
+} // wraps a contract instantiated by another contract, like the factory
+
 impl From<CountingContract> for Addr {
   fn from(contract: CountingContract) -> Self {
     contract.0
@@ -0,0 +1,571 @@
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_ibc_channel, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack, mock_ibc_channel_open_init,
+    mock_ibc_channel_open_try, mock_ibc_packet_ack, mock_ibc_packet_recv, mock_ibc_packet_timeout, mock_info,
+    MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Binary, CosmosMsg, Decimal, Deps, DepsMut, IbcAcknowledgement, IbcChannelConnectMsg, IbcMsg, IbcOrder,
+    IbcTimeout, Reply, SubMsg, SubMsgResponse, SubMsgResult,
+};
+
+use crate::error::ContractError;
+use crate::msg::{
+    CampaignInfo, CountingPacket, ExecMsg, FailedTransferInfo, FailedTransfersResp, HubConfig, IbcAck, IbcLifecycleComplete, IbcParent, InstantiateMsg, Parent,
+    Peer, PeersResp, QueryMsg, RemoteCounter, RemoteCountersResp, SudoMsg, ValueResp,
+};
+use crate::{
+    execute, ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_receive,
+    ibc_packet_timeout, ics20, instantiate, query, reply, sudo,
+};
+
+const ATOM: &str = "atom";
+const CHANNEL: &str = "channel-0";
+const REMOTE_PARENT: &str = "cosmos1parent";
+const COUNTING: &str = "counting-v1";
+const CONNECTION: &str = "connection-2";
+const PEER_PORT: &str = "their_port";
+
+fn instantiate_counter(deps: DepsMut, counter: u64) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg::New {
+            counter,
+            minimal_donation: coin(10, ATOM),
+            parent: None,
+            rewards: None,
+            owner: None,
+            limits: None,
+        },
+    )
+    .unwrap();
+}
+
+fn add_peer(deps: DepsMut) {
+    execute(
+        deps,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::AddPeer {
+            peer: Peer {
+                connection_id: CONNECTION.to_owned(),
+                port_id: PEER_PORT.to_owned(),
+            },
+        },
+    )
+    .unwrap();
+} // the mocked channels all come from this port over this connection
+
+fn connect(deps: DepsMut, channel_id: &str) {
+    ibc_channel_connect(
+        deps,
+        mock_env(),
+        mock_ibc_channel_connect_ack(channel_id, IbcOrder::Unordered, COUNTING),
+    )
+    .unwrap();
+}
+
+fn ibc_parent() -> Parent {
+    Parent {
+        addr: REMOTE_PARENT.to_owned(),
+        donating_period: 1,
+        part: Decimal::percent(10),
+        ibc: Some(IbcParent {
+            channel_id: CHANNEL.to_owned(),
+            timeout: 600,
+        }),
+    }
+}
+
+fn instantiate_with_ibc_parent(deps: DepsMut) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg::New {
+            counter: 0,
+            minimal_donation: coin(10, ATOM),
+            parent: Some(ibc_parent()),
+            rewards: None,
+            owner: None,
+            limits: None,
+        },
+    )
+    .unwrap();
+} // donating_period of 1 forwards the share on every counted donation
+
+fn transfer_msg(amount: u128) -> SubMsg {
+    let msg = ics20::transfer_msg(
+        CHANNEL,
+        MOCK_CONTRACT_ADDR,
+        REMOTE_PARENT,
+        &coin(amount, ATOM),
+        mock_env().block.time.plus_seconds(600),
+    );
+    SubMsg::reply_on_success(msg, 2)
+}
+
+fn transfer_sent(deps: DepsMut, sequence: u8) {
+    let msg = Reply {
+        id: 2,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(vec![8, sequence])),
+        }),
+    };
+    reply(deps, mock_env(), msg).unwrap();
+} // MsgTransferResponse with the sequence in its first field, as the transfer module replies
+
+fn transfer_complete(deps: DepsMut, msg: IbcLifecycleComplete) {
+    sudo(deps, mock_env(), SudoMsg::IbcLifecycleComplete(msg)).unwrap();
+}
+
+fn failed_transfers(deps: Deps) -> Vec<FailedTransferInfo> {
+    let resp: FailedTransfersResp =
+        from_binary(&query(deps, mock_env(), QueryMsg::FailedTransfers {}).unwrap()).unwrap();
+    resp.transfers
+}
+
+#[test]
+fn donation_forwarded_over_ibc() {
+    let mut deps = mock_dependencies();
+    instantiate_with_ibc_parent(deps.as_mut());
+
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(100, ATOM));
+    // the donation is already on the contract when it is executed
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("donor", &coins(100, ATOM)),
+        ExecMsg::Donate { campaign: None, proof: None, referrer: None },
+    )
+    .unwrap();
+
+    assert_eq!(resp.messages, vec![transfer_msg(10)]);
+
+    let CosmosMsg::Stargate { value, .. } = &resp.messages[0].msg else {
+        panic!("not an ics20 transfer: {:?}", resp.messages[0].msg);
+    };
+    let memo = format!(r#"{{"ibc_callback":"{}"}}"#, MOCK_CONTRACT_ADDR);
+    assert!(value.windows(memo.len()).any(|window| window == memo.as_bytes()));
+    // the memo asks ibc-hooks to call the contract back with the outcome
+}
+
+#[test]
+fn failed_transfers_recorded_and_retried() {
+    let mut deps = mock_dependencies();
+    instantiate_with_ibc_parent(deps.as_mut());
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(100, ATOM));
+
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donor", &coins(10, ATOM)),
+            ExecMsg::Donate { campaign: None, proof: None, referrer: None },
+        )
+        .unwrap();
+    }
+    transfer_sent(deps.as_mut(), 1);
+    transfer_sent(deps.as_mut(), 2);
+
+    transfer_complete(
+        deps.as_mut(),
+        IbcLifecycleComplete::IbcAck {
+            channel: CHANNEL.to_owned(),
+            sequence: 1,
+            ack: "e30=".to_owned(),
+            success: true,
+        },
+    );
+    transfer_complete(
+        deps.as_mut(),
+        IbcLifecycleComplete::IbcTimeout {
+            channel: CHANNEL.to_owned(),
+            sequence: 7,
+        },
+    );
+    assert_eq!(failed_transfers(deps.as_ref()), vec![]);
+    // an acknowledged transfer, and one the contract didn't send as a parent share
+
+    transfer_complete(
+        deps.as_mut(),
+        IbcLifecycleComplete::IbcTimeout {
+            channel: CHANNEL.to_owned(),
+            sequence: 2,
+        },
+    );
+    assert_eq!(
+        failed_transfers(deps.as_ref()),
+        vec![FailedTransferInfo {
+            id: 1,
+            campaign: "default".to_owned(),
+            channel_id: CHANNEL.to_owned(),
+            receiver: REMOTE_PARENT.to_owned(),
+            amount: coin(10, ATOM),
+        }]
+    );
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecMsg::RetryTransfer { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(resp.messages, vec![transfer_msg(10)]);
+    assert_eq!(failed_transfers(deps.as_ref()), vec![]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecMsg::RetryTransfer { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnknownTransfer { id: 1 });
+
+    transfer_sent(deps.as_mut(), 3);
+    transfer_complete(
+        deps.as_mut(),
+        IbcLifecycleComplete::IbcAck {
+            channel: CHANNEL.to_owned(),
+            sequence: 3,
+            ack: "unknown receiver".to_owned(),
+            success: false,
+        },
+    );
+    assert_eq!(failed_transfers(deps.as_ref())[0].id, 2);
+    // failing again, the retried transfer is recorded under a new id
+}
+
+#[test]
+fn transfer_sequence_decoded() {
+    assert_eq!(ics20::transfer_sequence(&[8, 1]).unwrap(), 1);
+    assert_eq!(ics20::transfer_sequence(&[8, 0xac, 0x02]).unwrap(), 300);
+    assert_eq!(ics20::transfer_sequence(&[18, 2, 1, 2, 8, 5]).unwrap(), 5);
+    // unknown fields before the sequence are skipped
+    assert!(ics20::transfer_sequence(&[8, 0x80]).is_err());
+}
+
+#[test]
+fn failed_campaign_transfer_refunded_to_campaign() {
+    let mut deps = mock_dependencies();
+    instantiate_counter(deps.as_mut(), 0);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::CreateCampaign {
+            id: "relief".to_owned(),
+            minimal_donation: coin(10, ATOM),
+            owner: None,
+            parent: Some(ibc_parent()),
+        },
+    )
+    .unwrap();
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("donor", &coins(100, ATOM)),
+        ExecMsg::Donate { campaign: Some("relief".to_owned()), proof: None, referrer: None },
+    )
+    .unwrap();
+    assert_eq!(resp.messages, vec![transfer_msg(10)]);
+    transfer_sent(deps.as_mut(), 1);
+
+    let campaign = |deps: Deps| -> CampaignInfo {
+        from_binary(&query(deps, mock_env(), QueryMsg::Campaign { id: "relief".to_owned() }).unwrap()).unwrap()
+    };
+    assert_eq!(campaign(deps.as_ref()).balance, coins(90, ATOM));
+
+    transfer_complete(
+        deps.as_mut(),
+        IbcLifecycleComplete::IbcTimeout {
+            channel: CHANNEL.to_owned(),
+            sequence: 1,
+        },
+    );
+    assert_eq!(campaign(deps.as_ref()).balance, coins(100, ATOM));
+    assert_eq!(failed_transfers(deps.as_ref())[0].campaign, "relief");
+    // the refund goes back to the campaign, not to the default balance
+
+    let resp = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecMsg::RetryTransfer { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(resp.messages, vec![transfer_msg(10)]);
+    assert_eq!(campaign(deps.as_ref()).balance, coins(90, ATOM));
+}
+
+#[test]
+fn counting_channel_handshake() {
+    let mut deps = mock_dependencies();
+    instantiate_counter(deps.as_mut(), 0);
+
+    let err = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        mock_ibc_channel_open_init("channel-1", IbcOrder::Unordered, "ics20-1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidIbcVersion {
+            expected: COUNTING.to_owned(),
+            version: "ics20-1".to_owned(),
+        }
+    );
+
+    let err = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        mock_ibc_channel_open_try("channel-1", IbcOrder::Ordered, COUNTING),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OrderedChannel);
+
+    let err = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, COUNTING),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownPeer {
+            connection_id: CONNECTION.to_owned(),
+            port_id: PEER_PORT.to_owned(),
+        }
+    );
+    // a contract the owner didn't approve can't open a channel
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecMsg::AddPeer {
+            peer: Peer {
+                connection_id: CONNECTION.to_owned(),
+                port_id: PEER_PORT.to_owned(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: "owner".to_owned()
+        }
+    );
+
+    add_peer(deps.as_mut());
+    let resp: PeersResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Peers {}).unwrap()).unwrap();
+    assert_eq!(
+        resp.peers,
+        vec![Peer {
+            connection_id: CONNECTION.to_owned(),
+            port_id: PEER_PORT.to_owned(),
+        }]
+    );
+
+    ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, COUNTING),
+    )
+    .unwrap();
+
+    let err = ibc_channel_connect(
+        deps.as_mut(),
+        mock_env(),
+        IbcChannelConnectMsg::new_ack(mock_ibc_channel("channel-1", IbcOrder::Unordered, COUNTING), "ics20-1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidIbcVersion {
+            expected: COUNTING.to_owned(),
+            version: "ics20-1".to_owned(),
+        }
+    );
+    // the counterparty version of the ack has to match too
+
+    connect(deps.as_mut(), "channel-1");
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::SetHub {
+            hub: Some(HubConfig {
+                channel_id: "channel-9".to_owned(),
+                timeout: 600,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownChannel {
+            channel_id: "channel-9".to_owned()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::SetHub {
+            hub: Some(HubConfig {
+                channel_id: "channel-1".to_owned(),
+                timeout: 600,
+            }),
+        },
+    )
+    .unwrap();
+
+    ibc_channel_close(
+        deps.as_mut(),
+        mock_env(),
+        mock_ibc_channel_close_init("channel-1", IbcOrder::Unordered, COUNTING),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecMsg::SyncCounter {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoHub);
+    // closing the channel to the hub stops the reports
+}
+
+#[test]
+fn hub_aggregates_remote_counters() {
+    let mut deps = mock_dependencies();
+    instantiate_counter(deps.as_mut(), 5);
+    add_peer(deps.as_mut());
+    connect(deps.as_mut(), "channel-1");
+    connect(deps.as_mut(), "channel-2");
+
+    for (channel_id, delta) in [("channel-1", 3), ("channel-2", 4), ("channel-1", -1)] {
+        let msg = mock_ibc_packet_recv(channel_id, &CountingPacket::CounterDelta { delta }).unwrap();
+        let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: IbcAck = from_binary(&resp.acknowledgement).unwrap();
+        assert!(matches!(ack, IbcAck::Result(_)));
+    }
+
+    let msg = mock_ibc_packet_recv("channel-2", &"not a counter").unwrap();
+    let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&resp.acknowledgement).unwrap();
+    assert!(matches!(ack, IbcAck::Error(_)));
+    // a bad packet is refused in the acknowledgement
+
+    let msg = mock_ibc_packet_recv("channel-2", &CountingPacket::CounterDelta { delta: -5 }).unwrap();
+    let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&resp.acknowledgement).unwrap();
+    assert!(matches!(ack, IbcAck::Error(_)));
+    // and so is a delta taking the counter below zero
+
+    let resp: RemoteCountersResp =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RemoteCounters {}).unwrap()).unwrap();
+    assert_eq!(
+        resp.counters,
+        vec![
+            RemoteCounter {
+                channel_id: "channel-1".to_owned(),
+                value: 2,
+            },
+            RemoteCounter {
+                channel_id: "channel-2".to_owned(),
+                value: 4,
+            },
+        ]
+    );
+
+    let resp: ValueResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GlobalValue {}).unwrap()).unwrap();
+    assert_eq!(resp, ValueResp { value: 11 });
+
+    for channel_id in ["channel-1", "channel-2"] {
+        let msg = mock_ibc_packet_recv(channel_id, &CountingPacket::CounterDelta { delta: i64::MAX }).unwrap();
+        ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    }
+    query(deps.as_ref(), mock_env(), QueryMsg::GlobalValue {}).unwrap_err();
+    // every remote counter fits, the sum of them doesn't
+}
+
+#[test]
+fn counter_reported_to_hub() {
+    let mut deps = mock_dependencies();
+    instantiate_counter(deps.as_mut(), 0);
+    add_peer(deps.as_mut());
+    connect(deps.as_mut(), "channel-0");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecMsg::SetHub {
+            hub: Some(HubConfig {
+                channel_id: "channel-0".to_owned(),
+                timeout: 600,
+            }),
+        },
+    )
+    .unwrap();
+
+    let donate = |deps: DepsMut| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("donor", &coins(10, ATOM)),
+            ExecMsg::Donate { campaign: None, proof: None, referrer: None },
+        )
+        .unwrap();
+    };
+    let sync = |deps: DepsMut| -> Vec<CosmosMsg> {
+        let resp = execute(deps, mock_env(), mock_info("anyone", &[]), ExecMsg::SyncCounter {}).unwrap();
+        resp.messages.into_iter().map(|msg| msg.msg).collect()
+    };
+    let delta_msg = |delta: i64| -> CosmosMsg {
+        CosmosMsg::Ibc(IbcMsg::SendPacket {
+            channel_id: "channel-0".to_owned(),
+            data: to_binary(&CountingPacket::CounterDelta { delta }).unwrap(),
+            timeout: IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(600)),
+        })
+    };
+
+    donate(deps.as_mut());
+    donate(deps.as_mut());
+
+    assert_eq!(sync(deps.as_mut()), vec![delta_msg(2)]);
+    assert_eq!(sync(deps.as_mut()), vec![]);
+    // nothing new to report
+
+    let msg = mock_ibc_packet_timeout("channel-0", &CountingPacket::CounterDelta { delta: 2 }).unwrap();
+    ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    donate(deps.as_mut());
+
+    assert_eq!(sync(deps.as_mut()), vec![delta_msg(3)]);
+    // the timed out delta is sent again with the new donation
+
+    let ack = IbcAcknowledgement::new(to_binary(&IbcAck::Result(vec![1].into())).unwrap());
+    let msg = mock_ibc_packet_ack("channel-0", &CountingPacket::CounterDelta { delta: 3 }, ack).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+
+    assert_eq!(sync(deps.as_mut()), vec![]);
+
+    sudo(deps.as_mut(), mock_env(), SudoMsg::ResetCounter { counter: u64::MAX }).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecMsg::SyncCounter {}).unwrap_err();
+    // a change which doesn't fit in the delta of a packet is refused, instead of wrapping around
+}
@@ -0,0 +1,60 @@
+// builds merkle trees of donor addresses the same way the contract checks them, to get roots and proofs in tests
+// leaves are sha256 of the addresses, pairs are sorted before hashing, and the last node of an odd level goes up as it is
+
+use sha2::{Digest, Sha256};
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+  let mut pair = [*left, *right];
+  pair.sort_unstable();
+  Sha256::digest(pair.concat()).into()
+}
+
+fn levels(donors: &[&str]) -> Vec<Vec<[u8; 32]>> {
+  let leaves = donors
+    .iter()
+    .map(|donor| Sha256::digest(donor.as_bytes()).into())
+    .collect();
+  let mut levels: Vec<Vec<[u8; 32]>> = vec![leaves];
+
+  while levels.last().unwrap().len() > 1 {
+    let next = levels
+      .last()
+      .unwrap()
+      .chunks(2)
+      .map(|nodes| match nodes {
+        [left, right] => hash_pair(left, right),
+        [node] => *node,
+        _ => unreachable!(),
+      })
+      .collect();
+    levels.push(next);
+  }
+
+  levels
+}
+// from the leaves up to the root
+
+pub fn root(donors: &[&str]) -> String {
+  let levels = levels(donors);
+  hex::encode(levels.last().unwrap().first().expect("the tree needs at least one donor"))
+}
+// hex encoded root, as passed to SetMerkleRoot
+
+pub fn proof(donors: &[&str], donor: &str) -> Vec<String> {
+  let mut idx = donors
+    .iter()
+    .position(|d| *d == donor)
+    .expect("the donor is not in the tree");
+  let levels = levels(donors);
+
+  let mut proof = vec![];
+  for level in &levels[..levels.len() - 1] {
+    if let Some(sibling) = level.get(idx ^ 1) {
+      proof.push(hex::encode(sibling));
+    } // the last node of an odd level has no sibling
+    idx /= 2;
+  }
+
+  proof
+}
+// hex encoded siblings from the leaf up, as passed to Donate
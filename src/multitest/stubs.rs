@@ -0,0 +1,119 @@
+// stub contracts which only exist to put some storage in place, so the real contract can be migrated from them, or to receive its hooks
+// they are stored with ContractWrapper the same way as the counting contract, but do nothing else
+
+use cosmwasm_std::{
+    coin, to_binary, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+};
+use cw2::set_contract_version;
+use cw_multi_test::{App, ContractWrapper};
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::HookExecMsg;
+
+fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Err(StdError::generic_err("stub contract can't be executed"))
+}
+
+fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("stub contract can't be queried"))
+}
+
+fn instantiate_future(deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    set_contract_version(deps.storage, "counting-contract", "99.0.0")?;
+    Ok(Response::new())
+}
+// pretends to be a counting contract newer than the one we are testing
+
+pub fn store_future(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate_future, query);
+    app.store_code(Box::new(contract))
+}
+
+#[derive(Serialize, Deserialize)]
+struct State0_3_0 {
+    counter: u64,
+    minimal_donation: Coin,
+    owner: Addr,
+    donating_parent: Option<u64>,
+}
+
+fn instantiate_crates_io(deps: DepsMut, _env: Env, info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    const STATE: Item<State0_3_0> = Item::new("state");
+
+    set_contract_version(deps.storage, "crates.io:counting-contract", "0.3.0")?;
+    STATE.save(
+        deps.storage,
+        &State0_3_0 {
+            counter: 3,
+            minimal_donation: coin(10, "atom"),
+            owner: info.sender,
+            donating_parent: None,
+        },
+    )?;
+    Ok(Response::new())
+}
+// our own 0.3.0 layout, but stored under the crates.io prefixed name
+
+pub fn store_crates_io(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate_crates_io, query);
+    app.store_code(Box::new(contract))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Config {
+    owner: Addr,
+    min_donation: Coin,
+}
+
+fn instantiate_donation_counter(deps: DepsMut, _env: Env, info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    const CONFIG: Item<Config> = Item::new("config");
+    const COUNT: Item<u64> = Item::new("count");
+
+    set_contract_version(deps.storage, "donation-counter", "1.2.0")?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner: info.sender,
+            min_donation: coin(5, "atom"),
+        },
+    )?;
+    COUNT.save(deps.storage, &7)?;
+    Ok(Response::new())
+}
+// a fork with its own name and storage layout
+
+pub fn store_donation_counter(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate_donation_counter, query);
+    app.store_code(Box::new(contract))
+}
+
+const RECEIVED_HOOKS: Item<Vec<HookExecMsg>> = Item::new("received_hooks");
+
+fn instantiate_hook_receiver(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn execute_hook_receiver(deps: DepsMut, _env: Env, _info: MessageInfo, msg: HookExecMsg) -> StdResult<Response> {
+    let mut received = RECEIVED_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+    received.push(msg);
+    RECEIVED_HOOKS.save(deps.storage, &received)?;
+    Ok(Response::new())
+}
+
+fn query_hook_receiver(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&RECEIVED_HOOKS.may_load(deps.storage)?.unwrap_or_default())
+}
+// returns every hook message received so far, oldest first
+
+pub fn store_hook_receiver(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute_hook_receiver, instantiate_hook_receiver, query_hook_receiver);
+    app.store_code(Box::new(contract))
+}
+// a hook contract which only records the messages it gets
+
+pub fn store_broken_hook(app: &mut App) -> u64 {
+    let contract = ContractWrapper::new(execute, instantiate_hook_receiver, query);
+    app.store_code(Box::new(contract))
+}
+// a hook contract failing on every message
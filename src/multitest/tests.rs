@@ -1,11 +1,23 @@
-use cosmwasm_std::{coin, coins, Addr, Decimal};
-use cw_multi_test::App;
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{coin, coins, to_vec, Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128, Validator};
+use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse};
+use cw721::{NftInfoResponse, NumTokensResponse, OwnerOfResponse};
+use cw_multi_test::{next_block, App, ContractWrapper, Executor, StakingInfo};
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
 
 use crate::error::ContractError;
-use crate::msg::{ValueResp, Parent};
+use crate::msg::{
+    CampaignInfo, Contribution, ContributionResp, DelegationInfo, DonationHookMsg, GovernanceConfig, HistoryEntry, HistoryResp, HookExecMsg, HookInfo, HooksResp, MigrateMsg, MigrationPlanResp,
+    InstantiateMsg, MigrationStep, ValueResp, Parent, Payout, PayoutAmount, ReceiptConfig, RewardConfig, RewardRate,
+    ProposalStatus, RewardRateResp, StakingConfig, StakingResp, SudoMsg, Update, DonationLimits, DonationWindow, LimitPolicy,
+    AccessMode, Ledger, LedgerPage, ReferralConfig, ReferralPayout, Voucher, VoucherPayload,
+};
 use crate::state::{ParentDonation, State, PARENT_DONATION, STATE};
 
 use super::contract::CountingContract;
+use super::merkle;
+use super::stubs;
 use counting_contract_0_1::multitest::contract::CountingContract as CountingContract_0_1;
 // used counting_contract_0_1 to avoid name conflict with the current contract, which is also called CountingContract. In addition, this is a path to counting contract v1.0.0, which we set in dev-dependencies in Cargo.toml
 
@@ -232,6 +244,138 @@ fn withdraw_to() {
     );
 }
 
+#[test]
+fn withdraw_many() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+
+    contract
+        .withdraw_many(
+            &mut app,
+            &owner,
+            vec![
+                Payout {
+                    receiver: alice.to_string(),
+                    amount: PayoutAmount::Funds {
+                        funds: coins(5, ATOM),
+                    },
+                },
+                Payout {
+                    receiver: bob.to_string(),
+                    amount: PayoutAmount::Share {
+                        share: Decimal::percent(50),
+                    },
+                },
+            ],
+        )
+        .unwrap();
+    // alice gets exactly 5 atom, bob gets half of the 20 atom balance
+
+    assert_eq!(
+        app.wrap().query_all_balances(alice).unwrap(),
+        coins(5, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(bob).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(5, ATOM)
+    );
+}
+
+#[test]
+fn withdraw_many_exceeding_balance() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .withdraw_many(
+            &mut app,
+            &owner,
+            vec![
+                Payout {
+                    receiver: alice.to_string(),
+                    amount: PayoutAmount::Funds {
+                        funds: coins(5, ATOM),
+                    },
+                },
+                Payout {
+                    receiver: bob.to_string(),
+                    amount: PayoutAmount::Share {
+                        share: Decimal::percent(60),
+                    },
+                },
+            ],
+        )
+        .unwrap_err();
+    // 5 atom + 60% of 10 atom is more than the contract holds
+
+    assert_eq!(
+        err,
+        ContractError::InsufficientFunds {
+            denom: ATOM.to_owned()
+        }
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
 #[test]
 fn unauthorized_withdraw() {
     let owner = Addr::unchecked("owner");
@@ -292,6 +436,37 @@ fn unauthorized_withdraw_to() {
     );
 }
 
+#[test]
+fn unauthorized_withdraw_many() {
+    let owner = Addr::unchecked("owner");
+    let member = Addr::unchecked("member");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .withdraw_many(&mut app, &member, vec![])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.into()
+        },
+    );
+}
+
 #[test]
 fn unauthorized_reset() {
     let owner = Addr::unchecked("owner");
@@ -372,7 +547,6 @@ fn migration() {
     assert_eq!(
         state,
         State {
-            counter: 1,
             minimal_donation: coin(10, ATOM),
             owner,
             donating_parent: None,
@@ -422,6 +596,7 @@ fn migration_with_parent() {
             addr: parent.to_string(),
             donating_period: 2,
             part: Decimal::percent(10),
+            ibc: None,
         },
     )
     .unwrap();
@@ -433,7 +608,6 @@ fn migration_with_parent() {
     assert_eq!(
         state,
         State {
-            counter: 1,
             minimal_donation: coin(10, ATOM),
             owner,
             donating_parent: Some(2),
@@ -449,6 +623,7 @@ fn migration_with_parent() {
             address: parent,
             donating_parent_period: 2,
             part: Decimal::percent(10),
+            ibc: None,
         }
     )
 }
@@ -504,7 +679,6 @@ fn migration_same_version() {
     assert_eq!(
         state,
         State {
-            counter: 1,
             minimal_donation: coin(10, ATOM),
             owner,
             donating_parent: None,
@@ -551,6 +725,7 @@ fn donating_parent() {
             addr: parent_contract.addr().to_string(),
             donating_period: 2,
             part: Decimal::percent(10),
+            ibc: None,
         },
     )
     .unwrap();
@@ -581,4 +756,3588 @@ fn donating_parent() {
             .unwrap(),
         coins(2, ATOM)
     );
-}
\ No newline at end of file
+}
+#[test]
+fn withdraw_to_with_allowance() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let spender = Addr::unchecked("spender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(20, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+
+    contract
+        .set_allowance(&mut app, &owner, &spender, coins(10, ATOM), 86400)
+        .unwrap();
+    // spender can withdraw 10 atom every 24h
+
+    contract
+        .withdraw_to(&mut app, &spender, &receiver, coins(6, ATOM))
+        .unwrap();
+
+    let resp = contract.query_allowance(&app, &spender).unwrap();
+    assert_eq!(resp.remaining, coins(4, ATOM));
+
+    let err = contract
+        .withdraw_to(&mut app, &spender, &receiver, coins(6, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AllowanceExceeded {
+            denom: ATOM.to_owned()
+        }
+    );
+    // only 4 atom left in this period
+
+    app.update_block(|block| block.time = block.time.plus_seconds(86400));
+    // moving to the next period resets what was spent
+
+    contract
+        .withdraw_to(&mut app, &spender, &receiver, coins(6, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(receiver).unwrap(),
+        coins(12, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(8, ATOM)
+    );
+}
+
+#[test]
+fn revoked_allowance() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let spender = Addr::unchecked("spender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract
+        .set_allowance(&mut app, &owner, &spender, coins(10, ATOM), 86400)
+        .unwrap();
+
+    let err = contract
+        .revoke_allowance(&mut app, &spender, &spender)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    // only the owner can manage allowances
+
+    contract
+        .revoke_allowance(&mut app, &owner, &spender)
+        .unwrap();
+
+    let err = contract
+        .withdraw_to(&mut app, &spender, &spender, coins(5, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    let resp = contract.query_allowance(&app, &spender).unwrap();
+    assert_eq!(resp.limit, vec![]);
+}
+
+#[test]
+fn invalid_allowance() {
+    let owner = Addr::unchecked("owner");
+    let spender = Addr::unchecked("spender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .set_allowance(&mut app, &owner, &spender, coins(10, ATOM), 0)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidAllowance {
+            reason: "the period can't be zero".to_owned()
+        }
+    );
+
+    let err = contract
+        .set_allowance(&mut app, &owner, &spender, vec![], 86400)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidAllowance {
+            reason: "the limit can't be empty".to_owned()
+        }
+    );
+
+    let err = contract
+        .set_allowance(&mut app, &owner, &spender, vec![coin(10, ATOM), coin(5, ATOM)], 86400)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidAllowance {
+            reason: "the limit lists a denom twice".to_owned()
+        }
+    );
+
+    assert_eq!(contract.query_allowance(&app, &spender).unwrap().limit, vec![]);
+}
+
+#[test]
+fn receiver_allowlist() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+
+    contract
+        .set_receiver_policy(&mut app, &owner, true, 3600)
+        .unwrap();
+    contract.add_receiver(&mut app, &owner, &receiver).unwrap();
+
+    let err = contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: receiver.to_string()
+        }
+    );
+    // the receiver was added, but the delay didn't pass yet
+
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&receiver).unwrap(),
+        coins(5, ATOM)
+    );
+
+    contract
+        .remove_receiver(&mut app, &owner, &receiver)
+        .unwrap();
+
+    let err = contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: receiver.to_string()
+        }
+    );
+
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: owner.to_string()
+        }
+    );
+    // the owner is not on the list, so it can't withdraw to itself either
+
+    contract
+        .set_receiver_policy(&mut app, &owner, false, 0)
+        .unwrap();
+    let err = contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: receiver.to_string()
+        }
+    );
+    // turning the policy off waits for the delay, like adding a receiver
+
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+
+    contract
+        .withdraw_to(&mut app, &owner, &receiver, coins(5, ATOM))
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&receiver).unwrap(),
+        coins(10, ATOM)
+    );
+}
+
+#[test]
+fn migration_downgrade() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let future_code_id = stubs::store_future(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = app
+        .instantiate_contract(
+            future_code_id,
+            owner,
+            &Empty {},
+            &[],
+            "Counting contract",
+            Some(admin.to_string()),
+        )
+        .unwrap();
+    // the stub stores a counting contract version newer than ours
+
+    let err = CountingContract::migrate(&mut app, contract, code_id, &admin, None)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DowngradeNotAllowed {
+            from: "99.0.0".to_owned(),
+            to: env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    );
+}
+
+#[test]
+fn migration_plan() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let resp = contract.query_migration_plan(&app, "0.1.0").unwrap();
+    assert_eq!(
+        resp,
+        MigrationPlanResp {
+            from_version: "0.1.0".to_owned(),
+            to_version: env!("CARGO_PKG_VERSION").to_owned(),
+            steps: vec![
+                MigrationStep {
+                    from: "0.1.0".to_owned(),
+                    to: "0.2.0".to_owned(),
+                    reads: vec!["counter".to_owned(), "minimal_donation".to_owned(), "owner".to_owned()],
+                    writes: vec![
+                        "counter".to_owned(),
+                        "minimal_donation".to_owned(),
+                        "owner".to_owned(),
+                        "state".to_owned()
+                    ],
+                },
+                MigrationStep {
+                    from: "0.2.0".to_owned(),
+                    to: "0.3.0".to_owned(),
+                    reads: vec!["state".to_owned()],
+                    writes: vec!["state".to_owned()],
+                },
+                MigrationStep {
+                    from: "0.3.0".to_owned(),
+                    to: "0.4.0".to_owned(),
+                    reads: vec!["state".to_owned()],
+                    writes: vec![
+                        "state".to_owned(),
+                        "counter".to_owned(),
+                        "counter__changelog".to_owned()
+                    ],
+                },
+            ],
+            reads: vec!["contract_info".to_owned(), "state".to_owned()],
+            writes: vec![
+                "state".to_owned(),
+                "parent_donation".to_owned(),
+                "counter".to_owned(),
+                "counter__changelog".to_owned(),
+                "contract_info".to_owned()
+            ],
+        }
+    );
+
+    contract.query_migration_plan(&app, "99.0.0").unwrap_err();
+    // downgrades are refused by the plan the same way as by the migration
+}
+
+#[test]
+fn migration_invariant_violated() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::default();
+
+    let old_code_id = CountingContract_0_1::store_code(&mut app);
+    let new_code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract_0_1::instantiate(
+        &mut app,
+        old_code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+    )
+    .unwrap();
+
+    let err = CountingContract::migrate(
+        &mut app,
+        contract.into(),
+        new_code_id,
+        &admin,
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 0,
+            part: Decimal::percent(10),
+            ibc: None,
+        },
+    )
+    .map(|_| ())
+    .unwrap_err();
+    // a parent with a zero period would never get donated to
+
+    assert_eq!(
+        err,
+        ContractError::InvalidParent {
+            reason: "donating period has to be at least one donation".to_owned()
+        }
+    );
+}
+
+#[test]
+fn migration_set_parent_same_version() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            parent: Update::Set(Parent {
+                addr: parent.to_string(),
+                donating_period: 2,
+                part: Decimal::percent(10),
+                ibc: None,
+            }),
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+    // migrating to the same code only updates the config
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(2));
+
+    let parent_donation = PARENT_DONATION
+        .query(&app.wrap(), contract.addr().clone())
+        .unwrap();
+    assert_eq!(
+        parent_donation,
+        ParentDonation {
+            address: parent,
+            donating_parent_period: 2,
+            part: Decimal::percent(10),
+            ibc: None,
+        }
+    );
+}
+
+#[test]
+fn migration_clear_parent() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let parent = Addr::unchecked("parent");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent.to_string(),
+            donating_period: 2,
+            part: Decimal::percent(10),
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    let contract = CountingContract::migrate(&mut app, contract.into(), code_id, &admin, None).unwrap();
+    // no parent in the message keeps the parent
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, Some(2));
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            parent: Update::Clear,
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.donating_parent, None);
+
+    let parent_donation = app
+        .wrap()
+        .query_wasm_raw(contract.addr(), b"parent_donation".to_vec())
+        .unwrap();
+    assert_eq!(parent_donation, None);
+    // the parent is removed from the storage
+}
+
+#[test]
+fn migration_set_minimal_donation() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            minimal_donation: Update::Set(coin(20, ATOM)),
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(state.minimal_donation, coin(20, ATOM));
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            minimal_donation: Update::Clear,
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+    // cleared minimal donation counts every donation
+
+    contract.donate(&mut app, &owner, &[]).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+}
+
+#[test]
+fn migration_set_owner() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let new_owner = Addr::unchecked("new_owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            owner: Update::Set(new_owner.to_string()),
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+
+    let err = contract.reset(&mut app, &owner, 10).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: new_owner.to_string()
+        }
+    );
+
+    contract.reset(&mut app, &new_owner, 10).unwrap();
+
+    let err = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            owner: Update::Clear,
+            ..MigrateMsg::default()
+        },
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotClear {
+            field: "owner".to_owned()
+        }
+    );
+}
+
+#[test]
+fn migration_set_counter() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        5,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+    let start = app.block_info().height;
+
+    app.update_block(next_block);
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            counter: Update::Set(42),
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 42 });
+
+    app.update_block(next_block);
+    assert_eq!(contract.query_value_at(&app, start + 1).unwrap(), ValueResp { value: 5 });
+    assert_eq!(contract.query_value_at(&app, start + 2).unwrap(), ValueResp { value: 42 });
+    // the migration is recorded at its height like a reset
+
+    let resp = contract.query_history(&app, None, None).unwrap();
+    assert_eq!(
+        resp,
+        HistoryResp {
+            entries: vec![
+                HistoryEntry {
+                    height: start,
+                    value: 5,
+                },
+                HistoryEntry {
+                    height: start + 1,
+                    value: 42,
+                },
+            ],
+        }
+    );
+
+    let contract = CountingContract::migrate_with_msg(
+        &mut app,
+        contract.into(),
+        code_id,
+        &admin,
+        MigrateMsg {
+            counter: Update::Clear,
+            ..MigrateMsg::default()
+        },
+    )
+    .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 0 });
+}
+
+#[test]
+fn migration_from_crates_io_name() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let old_code_id = stubs::store_crates_io(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = app
+        .instantiate_contract(
+            old_code_id,
+            owner,
+            &Empty {},
+            &[],
+            "Counting contract",
+            Some(admin.to_string()),
+        )
+        .unwrap();
+
+    let contract = CountingContract::migrate(&mut app, contract, code_id, &admin, None).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 3 });
+
+    let version = cw2::query_contract_info(&app.wrap(), contract.addr().to_string()).unwrap();
+    assert_eq!(version.contract, env!("CARGO_PKG_NAME"));
+    // after the migration the contract is stored under our own name
+}
+
+#[test]
+fn migration_from_foreign_layout() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let old_code_id = stubs::store_donation_counter(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = app
+        .instantiate_contract(
+            old_code_id,
+            owner.clone(),
+            &Empty {},
+            &[],
+            "Donation counter",
+            Some(admin.to_string()),
+        )
+        .unwrap();
+
+    let contract = CountingContract::migrate(&mut app, contract, code_id, &admin, None).unwrap();
+
+    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
+    assert_eq!(
+        state,
+        State {
+            minimal_donation: coin(5, ATOM),
+            owner,
+            donating_parent: None,
+        }
+    );
+    // the adapter maps the fork config into our state
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 7 });
+    // and the count into the counter
+}
+
+#[test]
+fn export_and_restore_state() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let spender = Addr::unchecked("spender");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Parent contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None,
+    )
+    .unwrap();
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: parent_contract.addr().to_string(),
+            donating_period: 3,
+            part: Decimal::percent(10),
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .set_allowance(&mut app, &owner, &spender, coins(10, ATOM), 86400)
+        .unwrap();
+    contract
+        .set_receiver_policy(&mut app, &owner, true, 3600)
+        .unwrap();
+    contract.add_receiver(&mut app, &owner, &receiver).unwrap();
+
+    let snapshot = contract.query_export_state(&app).unwrap();
+
+    let restored = CountingContract::restore(
+        &mut app,
+        code_id,
+        &sender,
+        "Restored contract",
+        None,
+        snapshot.clone(),
+    )
+    .unwrap();
+    // the owner comes from the snapshot, not from the instantiating sender
+
+    assert_eq!(
+        STATE.query(&app.wrap(), restored.addr().clone()).unwrap(),
+        State {
+            minimal_donation: coin(10, ATOM),
+            owner: owner.clone(),
+            donating_parent: Some(2),
+        }
+    );
+    assert_eq!(restored.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(
+        PARENT_DONATION
+            .query(&app.wrap(), restored.addr().clone())
+            .unwrap(),
+        ParentDonation {
+            address: parent_contract.addr().clone(),
+            donating_parent_period: 3,
+            part: Decimal::percent(10),
+            ibc: None,
+        }
+    );
+    assert_eq!(
+        restored.query_allowance(&app, &spender).unwrap(),
+        contract.query_allowance(&app, &spender).unwrap()
+    );
+
+    let err = restored
+        .donate(&mut app, &sender, &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::Restoring);
+    // nothing but the import runs until the ledgers are in
+
+    let err = restored.finish_restore(&mut app, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSnapshot {
+            reason: "imported donations add up to 0, the snapshot has 10".to_owned()
+        }
+    );
+    // finishing before the donations are imported
+
+    copy_ledgers(&mut app, &contract, &restored, &owner);
+    restored.finish_restore(&mut app, &owner).unwrap();
+
+    let restored_snapshot = restored.query_export_state(&app).unwrap();
+    assert_eq!(restored_snapshot.data, snapshot.data);
+    // exporting the restored contract gives back the same data
+
+    for ledger in [Ledger::Donations, Ledger::Campaigns, Ledger::Referrers] {
+        assert_eq!(
+            restored.query_export_ledger(&app, ledger, None, None).unwrap(),
+            contract.query_export_ledger(&app, ledger, None, None).unwrap()
+        );
+    }
+}
+
+fn copy_ledgers(app: &mut App, from: &CountingContract, to: &CountingContract, owner: &Addr) {
+    let ledgers = [
+        Ledger::Donations,
+        Ledger::Campaigns,
+        Ledger::Allowlist,
+        Ledger::Blocklist,
+        Ledger::Referrers,
+        Ledger::Referrals,
+    ];
+
+    for ledger in ledgers {
+        let mut start_after = None;
+        loop {
+            let page = from.query_export_ledger(app, ledger, start_after, 1).unwrap();
+            start_after = match &page {
+                LedgerPage::Donations(page) => page.last().map(|item| item.donor.clone()),
+                LedgerPage::Campaigns(page) => page.last().map(|item| item.id.clone()),
+                LedgerPage::Allowlist(page) | LedgerPage::Blocklist(page) => page.last().cloned(),
+                LedgerPage::Referrers(page) => page.last().map(|item| item.address.clone()),
+                LedgerPage::Referrals(page) => page.last().map(|item| item.donor.clone()),
+            };
+            if start_after.is_none() {
+                break;
+            }
+            to.import_ledger(app, owner, page).unwrap();
+        }
+    }
+    // pages of one entry make sure paging doesn't skip or repeat anything
+}
+
+#[test]
+fn restore_tampered_snapshot() {
+    let owner = Addr::unchecked("owner");
+    let attacker = Addr::unchecked("attacker");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let mut snapshot = contract.query_export_state(&app).unwrap();
+    snapshot.data.owner = attacker.to_string();
+
+    let err = CountingContract::restore(
+        &mut app,
+        code_id,
+        &attacker,
+        "Restored contract",
+        None,
+        snapshot,
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSnapshot {
+            reason: "checksum mismatch".to_owned()
+        }
+    );
+}
+
+#[test]
+fn sudo_reset_and_replace_owner() {
+    let owner = Addr::unchecked("owner");
+    let new_owner = Addr::unchecked("new_owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .sudo(&mut app, SudoMsg::ResetCounter { counter: 7 })
+        .unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 7 });
+
+    contract
+        .sudo(
+            &mut app,
+            SudoMsg::ReplaceOwner {
+                owner: new_owner.to_string(),
+            },
+        )
+        .unwrap();
+
+    let err = contract.reset(&mut app, &owner, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: new_owner.to_string()
+        }
+    );
+    // the old owner lost the contract without having to cooperate
+
+    contract.reset(&mut app, &new_owner, 0).unwrap();
+}
+
+#[test]
+fn sudo_pause() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract.sudo(&mut app, SudoMsg::Pause {}).unwrap();
+
+    let err = contract.donate(&mut app, &sender, &[]).unwrap_err();
+    assert_eq!(err, ContractError::Paused);
+
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, ContractError::Paused);
+
+    contract.sudo(&mut app, SudoMsg::Unpause {}).unwrap();
+
+    contract.donate(&mut app, &sender, &[]).unwrap();
+
+    let resp = contract.query_value(&app).unwrap();
+    assert_eq!(resp, ValueResp { value: 1 });
+}
+
+#[test]
+fn counter_history() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None
+    )
+    .unwrap();
+    let start = app.block_info().height;
+
+    app.update_block(next_block);
+    contract.donate(&mut app, &sender, &[]).unwrap();
+    contract.donate(&mut app, &sender, &[]).unwrap();
+    // two donations in the same block are a single change of the history
+
+    app.update_block(next_block);
+    app.update_block(next_block);
+    contract.reset(&mut app, &owner, 10).unwrap();
+
+    app.update_block(next_block);
+
+    assert_eq!(contract.query_value_at(&app, start + 1).unwrap(), ValueResp { value: 0 });
+    assert_eq!(contract.query_value_at(&app, start + 2).unwrap(), ValueResp { value: 2 });
+    assert_eq!(contract.query_value_at(&app, start + 3).unwrap(), ValueResp { value: 2 });
+    assert_eq!(contract.query_value_at(&app, start + 4).unwrap(), ValueResp { value: 10 });
+    // the value at a height is the one from before the block was executed
+
+    contract.query_value_at(&app, start).unwrap_err();
+    // the contract didn't exist yet at the beginning of its instantiation block
+
+    let resp = contract.query_history(&app, None, 2).unwrap();
+    assert_eq!(
+        resp,
+        HistoryResp {
+            entries: vec![
+                HistoryEntry {
+                    height: start,
+                    value: 0,
+                },
+                HistoryEntry {
+                    height: start + 1,
+                    value: 2,
+                },
+            ],
+        }
+    );
+
+    let resp = contract.query_history(&app, start + 1, None).unwrap();
+    assert_eq!(
+        resp,
+        HistoryResp {
+            entries: vec![HistoryEntry {
+                height: start + 3,
+                value: 10,
+            }],
+        }
+    );
+}
+
+#[test]
+fn donation_hooks() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let receiver_code_id = stubs::store_hook_receiver(&mut app);
+    let broken_code_id = stubs::store_broken_hook(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let receiver = app
+        .instantiate_contract(receiver_code_id, owner.clone(), &Empty {}, &[], "Hook receiver", None)
+        .unwrap();
+    let broken = app
+        .instantiate_contract(broken_code_id, owner.clone(), &Empty {}, &[], "Broken hook", None)
+        .unwrap();
+
+    let err = contract
+        .add_hook(&mut app, &sender, &receiver, true, false)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .add_hook(&mut app, &owner, &receiver, true, false)
+        .unwrap();
+    contract
+        .add_hook(&mut app, &owner, &broken, false, false)
+        .unwrap();
+
+    let resp = contract.query_hooks(&app).unwrap();
+    assert_eq!(
+        resp,
+        HooksResp {
+            hooks: vec![
+                HookInfo {
+                    hook: receiver.to_string(),
+                    on_reset: true,
+                    on_withdraw: false,
+                },
+                HookInfo {
+                    hook: broken.to_string(),
+                    on_reset: false,
+                    on_withdraw: false,
+                },
+            ],
+        }
+    );
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    // the broken hook doesn't make the donation fail
+    contract.donate(&mut app, &sender, &[]).unwrap();
+    // not counted, so the hooks are not called
+    contract.reset(&mut app, &owner, 0).unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    // the receiver didn't subscribe to withdrawals
+
+    let received: Vec<HookExecMsg> = app.wrap().query_wasm_smart(&receiver, &Empty {}).unwrap();
+    assert_eq!(
+        received,
+        vec![
+            HookExecMsg::DonationHook(DonationHookMsg {
+                donor: sender.to_string(),
+                funds: coins(10, ATOM),
+                counter: 1,
+                campaign: None,
+            }),
+            HookExecMsg::ResetHook(DonationHookMsg {
+                donor: owner.to_string(),
+                funds: vec![],
+                counter: 0,
+                campaign: None,
+            }),
+        ]
+    );
+
+    contract.remove_hook(&mut app, &owner, &broken).unwrap();
+
+    let resp = contract.query_hooks(&app).unwrap();
+    assert_eq!(resp.hooks.len(), 1);
+}
+
+#[test]
+fn donation_receipts() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, coins(40, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let cw721_code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    )));
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let cw721 = app
+        .instantiate_contract(
+            cw721_code_id,
+            owner.clone(),
+            &cw721_base::InstantiateMsg {
+                name: "Donation receipts".to_owned(),
+                symbol: "RCPT".to_owned(),
+                minter: contract.addr().to_string(),
+            },
+            &[],
+            "Receipts",
+            None,
+        )
+        .unwrap();
+    // the counting contract has to be the minter of the receipts
+
+    contract
+        .set_receipts(
+            &mut app,
+            &owner,
+            ReceiptConfig {
+                cw721: cw721.to_string(),
+                token_uri: "https://receipts.example/{donor}/{amount}{denom}/{counter}/{time}".to_owned(),
+                threshold: Some(20u128.into()),
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    // counted, but below the threshold
+
+    let resp: NumTokensResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &cw721_base::QueryMsg::<Empty>::NumTokens {})
+        .unwrap();
+    assert_eq!(resp.count, 0);
+
+    contract
+        .donate(&mut app, &sender, &coins(20, ATOM))
+        .unwrap();
+
+    let owner_of: OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &cw721,
+            &cw721_base::QueryMsg::<Empty>::OwnerOf {
+                token_id: "1".to_owned(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner_of.owner, sender.to_string());
+
+    let info: NftInfoResponse<Option<Empty>> = app
+        .wrap()
+        .query_wasm_smart(
+            &cw721,
+            &cw721_base::QueryMsg::<Empty>::NftInfo {
+                token_id: "1".to_owned(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        info.token_uri,
+        Some(format!(
+            "https://receipts.example/{}/20atom/2/{}",
+            sender,
+            app.block_info().time.seconds()
+        ))
+    );
+
+    contract.set_receipts(&mut app, &owner, None).unwrap();
+    contract
+        .donate(&mut app, &sender, &coins(10, ATOM))
+        .unwrap();
+    // receipts are turned off
+
+    let resp: NumTokensResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &cw721_base::QueryMsg::<Empty>::NumTokens {})
+        .unwrap();
+    assert_eq!(resp.count, 1);
+}
+
+#[test]
+fn donation_rewards() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &sender, vec![coin(20, ATOM), coin(10, "btc")])
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let cw20_code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+
+    let token = app
+        .instantiate_contract(
+            cw20_code_id,
+            owner.clone(),
+            &cw20_base::msg::InstantiateMsg {
+                name: "Supporter token".to_owned(),
+                symbol: "SUP".to_owned(),
+                decimals: 6,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: owner.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            &[],
+            "Supporter token",
+            None,
+        )
+        .unwrap();
+
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        InstantiateMsg::New {
+            counter: 0,
+            minimal_donation: coin(10, ATOM),
+            parent: None,
+            rewards: Some(RewardConfig {
+                token: token.to_string(),
+                rates: vec![RewardRate {
+                    denom: ATOM.to_owned(),
+                    rate: Decimal::percent(250),
+                }],
+            }),
+            owner: None,
+            limits: None,
+        },
+    )
+    .unwrap();
+
+    app.execute_contract(
+        owner.clone(),
+        token.clone(),
+        &cw20_base::msg::ExecuteMsg::UpdateMinter {
+            new_minter: Some(contract.addr().to_string()),
+        },
+        &[],
+    )
+    .unwrap();
+    // the token exists before the counting contract, so the minter is handed over afterwards
+
+    contract
+        .donate(&mut app, &sender, &[coin(10, ATOM), coin(10, "btc")])
+        .unwrap();
+    // btc has no rate, so only the atom is rewarded
+
+    let resp: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &token,
+            &Cw20QueryMsg::Balance {
+                address: sender.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(resp.balance.u128(), 25);
+
+    let err = contract
+        .set_reward_rates(
+            &mut app,
+            &sender,
+            vec![RewardRate {
+                denom: ATOM.to_owned(),
+                rate: Decimal::one(),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .set_reward_rates(
+            &mut app,
+            &owner,
+            vec![RewardRate {
+                denom: ATOM.to_owned(),
+                rate: Decimal::one(),
+            }],
+        )
+        .unwrap();
+
+    let resp = contract.query_reward_rate(&app).unwrap();
+    assert_eq!(
+        resp,
+        RewardRateResp {
+            token: Some(token.to_string()),
+            rates: vec![RewardRate {
+                denom: ATOM.to_owned(),
+                rate: Decimal::one(),
+            }],
+        }
+    );
+
+    contract
+        .donate(&mut app, &sender, &coins(5, ATOM))
+        .unwrap();
+    // too small to be counted, but still rewarded
+
+    let resp: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &token,
+            &Cw20QueryMsg::Balance {
+                address: sender.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(resp.balance.u128(), 30);
+}
+
+#[test]
+fn donor_governance() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(30, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(30, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+
+    assert_eq!(
+        contract.query_contribution(&app, &alice).unwrap(),
+        ContributionResp {
+            donor: alice.to_string(),
+            amount: 30u128.into(),
+            total: 40u128.into(),
+        }
+    );
+
+    let err = contract
+        .propose(&mut app, &alice, vec![], "too early")
+        .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceDisabled);
+
+    contract
+        .set_governance(
+            &mut app,
+            &owner,
+            GovernanceConfig {
+                quorum: Decimal::percent(50),
+                threshold: Decimal::percent(60),
+                voting_period: 3600,
+                restrict_withdrawals: true,
+            },
+        )
+        .unwrap();
+
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, ContractError::WithdrawalsRestricted);
+
+    let payouts = vec![Payout {
+        receiver: receiver.to_string(),
+        amount: PayoutAmount::Funds {
+            funds: coins(20, ATOM),
+        },
+    }];
+
+    let err = contract
+        .propose(&mut app, &receiver, payouts.clone(), "pay me")
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoVotingPower);
+
+    let id = contract
+        .propose(&mut app, &alice, payouts, "pay the receiver")
+        .unwrap();
+
+    contract.vote(&mut app, &bob, id, false).unwrap();
+
+    let err = contract.execute_proposal(&mut app, &bob, id).unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed { id });
+
+    contract.vote(&mut app, &alice, id, true).unwrap();
+    // 30 yes out of 40 votes
+
+    let err = contract.vote(&mut app, &alice, id, true).unwrap_err();
+    assert_eq!(err, ContractError::AlreadyVoted { id });
+
+    let resp = contract.query_proposal(&app, id).unwrap();
+    assert_eq!(resp.status, ProposalStatus::Passed);
+    assert_eq!(resp.yes.u128(), 30);
+    assert_eq!(resp.no.u128(), 10);
+
+    contract.execute_proposal(&mut app, &bob, id).unwrap();
+    // anyone can execute a passed proposal
+
+    assert_eq!(
+        app.wrap().query_all_balances(&receiver).unwrap(),
+        coins(20, ATOM)
+    );
+
+    let err = contract.execute_proposal(&mut app, &bob, id).unwrap_err();
+    assert_eq!(err, ContractError::ProposalExecuted { id });
+
+    let err = contract
+        .set_governance(&mut app, &owner, None)
+        .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceLocked);
+
+    let unrestricted = GovernanceConfig {
+        quorum: Decimal::percent(50),
+        threshold: Decimal::percent(60),
+        voting_period: 3600,
+        restrict_withdrawals: false,
+    };
+    let err = contract
+        .set_governance(&mut app, &owner, unrestricted.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceLocked);
+
+    let err = contract
+        .set_governance(
+            &mut app,
+            &owner,
+            GovernanceConfig {
+                quorum: Decimal::percent(10),
+                threshold: Decimal::percent(60),
+                voting_period: 3600,
+                restrict_withdrawals: true,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceLocked);
+    // the owner can't lift the restriction or make proposals easier to pass
+
+    contract
+        .set_governance(
+            &mut app,
+            &owner,
+            GovernanceConfig {
+                quorum: Decimal::percent(50),
+                threshold: Decimal::percent(60),
+                voting_period: 7200,
+                restrict_withdrawals: true,
+            },
+        )
+        .unwrap();
+    // but can still change what doesn't loosen it
+
+    let id = contract
+        .propose_governance(&mut app, &alice, Update::Set(unrestricted), "lift the restriction")
+        .unwrap();
+    contract.vote(&mut app, &alice, id, true).unwrap();
+    contract.execute_proposal(&mut app, &bob, id).unwrap();
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    // the donors voted to lift the restriction
+
+    let id = contract
+        .propose(&mut app, &owner, vec![], "nothing")
+        .unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+
+    let err = contract.vote(&mut app, &alice, id, true).unwrap_err();
+    assert_eq!(err, ContractError::VotingClosed { id });
+
+    let resp = contract.query_proposal(&app, id).unwrap();
+    assert_eq!(resp.status, ProposalStatus::Rejected);
+}
+
+#[test]
+fn governance_votes() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+    let dave = Addr::unchecked("dave");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(30, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(60, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &carol, coins(60, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &dave, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(30, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &carol, &coins(60, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+
+    let restricted = GovernanceConfig {
+        quorum: Decimal::percent(50),
+        threshold: Decimal::percent(60),
+        voting_period: 3600,
+        restrict_withdrawals: true,
+    };
+    contract
+        .set_governance(&mut app, &owner, restricted)
+        .unwrap();
+
+    let unrestricted = GovernanceConfig {
+        quorum: Decimal::percent(50),
+        threshold: Decimal::percent(60),
+        voting_period: 3600,
+        restrict_withdrawals: false,
+    };
+
+    let no_quorum = contract
+        .propose_governance(&mut app, &alice, Update::Set(unrestricted.clone()), "lift the restriction")
+        .unwrap();
+    contract.vote(&mut app, &alice, no_quorum, true).unwrap();
+    // 30 yes out of 100 donated, below the quorum
+
+    let no_threshold = contract
+        .propose_governance(&mut app, &alice, Update::Set(unrestricted.clone()), "lift it anyway")
+        .unwrap();
+    contract.vote(&mut app, &alice, no_threshold, true).unwrap();
+    contract.vote(&mut app, &carol, no_threshold, false).unwrap();
+    // 90 voted, but only a third of the votes are yes
+
+    contract
+        .donate(&mut app, &bob, &coins(50, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &dave, &coins(10, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+
+    contract.vote(&mut app, &bob, no_quorum, true).unwrap();
+    let resp = contract.query_proposal(&app, no_quorum).unwrap();
+    assert_eq!(resp.total_weight.u128(), 100);
+    assert_eq!(resp.yes.u128(), 40);
+    assert_eq!(resp.status, ProposalStatus::Open);
+    // bob votes with the 10 he donated before the proposal, not with the 60 he has now
+
+    let err = contract.vote(&mut app, &dave, no_quorum, true).unwrap_err();
+    assert_eq!(err, ContractError::NoVotingPower);
+    // donating after the proposal gives no votes on it
+
+    let err = contract.execute_proposal(&mut app, &alice, no_quorum).unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed { id: no_quorum });
+
+    let err = contract.execute_proposal(&mut app, &alice, no_threshold).unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed { id: no_threshold });
+
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+
+    let err = contract.vote(&mut app, &carol, no_quorum, true).unwrap_err();
+    assert_eq!(err, ContractError::VotingClosed { id: no_quorum });
+
+    let err = contract.vote(&mut app, &bob, no_threshold, true).unwrap_err();
+    assert_eq!(err, ContractError::VotingClosed { id: no_threshold });
+    // the yes votes which would pass them come after the deadline
+
+    for id in [no_quorum, no_threshold] {
+        let resp = contract.query_proposal(&app, id).unwrap();
+        assert_eq!(resp.status, ProposalStatus::Rejected);
+
+        let err = contract.execute_proposal(&mut app, &alice, id).unwrap_err();
+        assert_eq!(err, ContractError::ProposalNotPassed { id });
+    }
+
+    let err = contract
+        .set_governance(&mut app, &owner, unrestricted)
+        .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceLocked);
+
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, ContractError::WithdrawalsRestricted);
+    // the rejected proposals left the restriction in place
+}
+
+#[test]
+fn auto_delegation() {
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::new(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(100, ATOM))
+            .unwrap();
+
+        router
+            .staking
+            .setup(
+                storage,
+                StakingInfo {
+                    bonded_denom: ATOM.to_owned(),
+                    unbonding_time: 60,
+                    apr: Decimal::percent(10),
+                },
+            )
+            .unwrap();
+
+        for validator in ["validator1", "validator2"] {
+            router
+                .staking
+                .add_validator(
+                    api,
+                    storage,
+                    &mock_env().block,
+                    Validator {
+                        address: validator.to_owned(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::percent(20),
+                        max_change_rate: Decimal::percent(1),
+                    },
+                )
+                .unwrap();
+        }
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .set_staking(
+            &mut app,
+            &owner,
+            StakingConfig {
+                validator: "validator3".to_owned(),
+                fraction: Decimal::percent(50),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownValidator {
+            validator: "validator3".to_owned()
+        }
+    );
+
+    let err = contract
+        .set_staking(
+            &mut app,
+            &owner,
+            StakingConfig {
+                validator: "validator1".to_owned(),
+                fraction: Decimal::percent(150),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidStakingFraction);
+
+    contract
+        .set_staking(
+            &mut app,
+            &owner,
+            StakingConfig {
+                validator: "validator1".to_owned(),
+                fraction: Decimal::percent(50),
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate(&mut app, &donor, &coins(100, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(50, ATOM)
+    );
+    assert_eq!(
+        contract.query_staking(&app).unwrap(),
+        StakingResp {
+            staking: Some(StakingConfig {
+                validator: "validator1".to_owned(),
+                fraction: Decimal::percent(50),
+            }),
+            delegations: vec![DelegationInfo {
+                validator: "validator1".to_owned(),
+                amount: coin(50, ATOM),
+            }],
+        }
+    );
+
+    let err = contract
+        .redelegate(&mut app, &donor, "validator1", "validator2", coin(20, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .redelegate(&mut app, &owner, "validator1", "validator2", coin(20, ATOM))
+        .unwrap();
+    contract
+        .undelegate(&mut app, &owner, "validator1", coin(10, ATOM))
+        .unwrap();
+
+    assert_eq!(
+        contract.query_staking(&app).unwrap().delegations,
+        vec![
+            DelegationInfo {
+                validator: "validator1".to_owned(),
+                amount: coin(20, ATOM),
+            },
+            DelegationInfo {
+                validator: "validator2".to_owned(),
+                amount: coin(20, ATOM),
+            },
+        ]
+    );
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(365 * 24 * 3600);
+        block.height += 1;
+    });
+    contract.claim_rewards(&mut app, &owner).unwrap();
+
+    let liquid = app
+        .wrap()
+        .query_balance(contract.addr(), ATOM)
+        .unwrap()
+        .amount;
+    assert!(liquid > Uint128::new(50));
+    // a year of rewards on the delegated funds, the undelegated ones are still unbonding
+
+    contract.withdraw(&mut app, &owner).unwrap();
+
+    assert_eq!(
+        app.wrap().query_balance(&owner, ATOM).unwrap().amount,
+        liquid
+    );
+    assert_eq!(contract.query_staking(&app).unwrap().delegations.len(), 2);
+    // withdraw leaves the delegations alone
+}
+
+#[test]
+fn campaigns() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Parent contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .create_campaign(&mut app, &alice, "trees", coin(5, ATOM), None, None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    // only the contract owner creates campaigns, for themselves or for someone else
+
+    let err = contract
+        .create_campaign(
+            &mut app,
+            &owner,
+            "trees",
+            coin(5, ATOM),
+            Some(&alice),
+            Parent {
+                addr: parent_contract.addr().to_string(),
+                donating_period: 2,
+                part: Decimal::percent(150),
+                ibc: None,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidParent {
+            reason: "part is greater than the whole donation".to_owned()
+        }
+    );
+
+    contract
+        .create_campaign(
+            &mut app,
+            &owner,
+            "trees",
+            coin(5, ATOM),
+            Some(&alice),
+            Parent {
+                addr: parent_contract.addr().to_string(),
+                donating_period: 2,
+                part: Decimal::percent(10),
+                ibc: None,
+            },
+        )
+        .unwrap();
+
+    for id in ["trees", "default"] {
+        let err = contract
+            .create_campaign(&mut app, &owner, id, coin(5, ATOM), None, None)
+            .unwrap_err();
+        assert_eq!(err, ContractError::CampaignExists { id: id.to_owned() });
+    }
+
+    contract
+        .donate(&mut app, &donor, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate_campaign(&mut app, &donor, "trees", &coins(20, ATOM))
+        .unwrap();
+    contract
+        .donate_campaign(&mut app, &donor, "trees", &coins(20, ATOM))
+        .unwrap();
+    // the second donation to trees sends 10% of what it collected to the parent
+
+    let err = contract
+        .donate_campaign(&mut app, &donor, "unknown", &coins(20, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownCampaign {
+            id: "unknown".to_owned()
+        }
+    );
+
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(
+        contract.query_campaign_value(&app, "trees").unwrap(),
+        ValueResp { value: 2 }
+    );
+    assert_eq!(parent_contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(
+        app.wrap().query_all_balances(parent_contract.addr()).unwrap(),
+        coins(4, ATOM)
+    );
+
+    assert_eq!(
+        contract.query_campaign(&app, "trees").unwrap(),
+        CampaignInfo {
+            id: "trees".to_owned(),
+            owner: alice.to_string(),
+            minimal_donation: coin(5, ATOM),
+            counter: 2,
+            donating_parent: Some(2),
+            parent: Some(Parent {
+                addr: parent_contract.addr().to_string(),
+                donating_period: 2,
+                part: Decimal::percent(10),
+                ibc: None,
+            }),
+            balance: coins(36, ATOM),
+        }
+    );
+
+    let err = contract
+        .reset_campaign(&mut app, &owner, "trees", 0)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: alice.to_string()
+        }
+    );
+    // the campaign belongs to its owner, not to the owner of the contract
+
+    contract
+        .reset_campaign(&mut app, &alice, "trees", 0)
+        .unwrap();
+    assert_eq!(
+        contract.query_campaign_value(&app, "trees").unwrap(),
+        ValueResp { value: 0 }
+    );
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    contract
+        .withdraw_campaign(&mut app, &alice, "trees")
+        .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(&owner).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(&alice).unwrap(),
+        coins(36, ATOM)
+    );
+    // the default campaign only withdraws what was donated to it
+    assert_eq!(contract.query_campaign(&app, "trees").unwrap().balance, vec![]);
+}
+
+#[test]
+fn campaign_owners_and_parents() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+    let parent_contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Parent contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .create_campaign(&mut app, &bob, "water", coin(5, ATOM), Some(&bob), None)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    // naming themselves the owner doesn't let anyone else create a campaign
+
+    let parent = Parent {
+        addr: parent_contract.addr().to_string(),
+        donating_period: 0,
+        part: Decimal::percent(50),
+        ibc: None,
+    };
+    let err = contract
+        .create_campaign(&mut app, &owner, "water", coin(5, ATOM), Some(&alice), parent.clone())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidParent {
+            reason: "donating period has to be at least one donation".to_owned()
+        }
+    );
+    contract
+        .create_campaign(
+            &mut app,
+            &owner,
+            "water",
+            coin(5, ATOM),
+            Some(&alice),
+            Parent {
+                donating_period: 1,
+                ..parent
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate_campaign(&mut app, &donor, "water", &coins(20, ATOM))
+        .unwrap();
+    contract
+        .donate_campaign(&mut app, &donor, "water", &coins(2, ATOM))
+        .unwrap();
+    // below the minimal donation, so kept by the campaign without being forwarded
+
+    assert_eq!(parent_contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(
+        app.wrap().query_all_balances(parent_contract.addr()).unwrap(),
+        coins(10, ATOM)
+    );
+    assert_eq!(contract.query_campaign(&app, "water").unwrap().balance, coins(12, ATOM));
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 0 });
+    // half of what the campaign collected went to the parent, the default campaign got nothing
+
+    for sender in [&owner, &bob] {
+        let err = contract
+            .withdraw_campaign(&mut app, sender, "water")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Unauthorized {
+                owner: alice.to_string()
+            }
+        );
+    } // not even the contract owner withdraws a campaign it created for alice
+
+    let err = contract
+        .withdraw_campaign(&mut app, &alice, "unknown")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownCampaign {
+            id: "unknown".to_owned()
+        }
+    );
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(12, ATOM)
+    );
+    contract
+        .withdraw_campaign(&mut app, &alice, "water")
+        .unwrap();
+    assert_eq!(app.wrap().query_all_balances(&alice).unwrap(), coins(12, ATOM));
+}
+
+#[test]
+fn campaign_withdrawals_gated() {
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &donor, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let receiver_code_id = stubs::store_hook_receiver(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let receiver = app
+        .instantiate_contract(receiver_code_id, owner.clone(), &Empty {}, &[], "Hook receiver", None)
+        .unwrap();
+    contract
+        .add_hook(&mut app, &owner, &receiver, true, true)
+        .unwrap();
+
+    contract
+        .create_campaign(&mut app, &owner, "trees", coin(5, ATOM), None, None)
+        .unwrap();
+    // without an explicit owner, the campaign belongs to the contract owner
+
+    contract
+        .donate_campaign(&mut app, &donor, "trees", &coins(20, ATOM))
+        .unwrap();
+    contract
+        .reset_campaign(&mut app, &owner, "trees", 5)
+        .unwrap();
+    contract
+        .withdraw_campaign(&mut app, &owner, "trees")
+        .unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(20, ATOM));
+
+    let received: Vec<HookExecMsg> = app.wrap().query_wasm_smart(&receiver, &Empty {}).unwrap();
+    assert_eq!(
+        received,
+        vec![
+            HookExecMsg::ResetHook(DonationHookMsg {
+                donor: owner.to_string(),
+                funds: vec![],
+                counter: 5,
+                campaign: Some("trees".to_owned()),
+            }),
+            HookExecMsg::WithdrawHook(DonationHookMsg {
+                donor: owner.to_string(),
+                funds: coins(20, ATOM),
+                counter: 5,
+                campaign: Some("trees".to_owned()),
+            }),
+        ]
+    );
+
+    contract
+        .donate_campaign(&mut app, &donor, "trees", &coins(20, ATOM))
+        .unwrap();
+
+    contract
+        .set_receiver_policy(&mut app, &owner, true, 0)
+        .unwrap();
+    let err = contract
+        .withdraw_campaign(&mut app, &owner, "trees")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: owner.to_string()
+        }
+    );
+    contract.add_receiver(&mut app, &owner, &owner).unwrap();
+
+    contract
+        .set_governance(
+            &mut app,
+            &owner,
+            GovernanceConfig {
+                quorum: Decimal::percent(50),
+                threshold: Decimal::percent(60),
+                voting_period: 3600,
+                restrict_withdrawals: true,
+            },
+        )
+        .unwrap();
+    let err = contract
+        .withdraw_campaign(&mut app, &owner, "trees")
+        .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawalsRestricted);
+    assert_eq!(contract.query_campaign(&app, "trees").unwrap().balance, coins(20, ATOM));
+}
+
+#[test]
+fn contributions_between_heights() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(30, ATOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &bob, coins(10, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+    let start = app.block_info().height;
+
+    contract
+        .donate(&mut app, &alice, &coins(20, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap();
+    app.update_block(next_block);
+
+    let resp = contract.query_contributions(&app, start, None, None, None).unwrap();
+    assert_eq!(resp.denom, ATOM);
+    assert_eq!(
+        resp.contributions,
+        vec![
+            Contribution {
+                donor: alice.to_string(),
+                amount: 20u128.into(),
+            },
+            Contribution {
+                donor: bob.to_string(),
+                amount: 10u128.into(),
+            },
+        ]
+    );
+    // the first donation of alice was made before the start height
+
+    let resp = contract.query_contributions(&app, None, start, None, None).unwrap();
+    assert_eq!(
+        resp.contributions,
+        vec![
+            Contribution {
+                donor: alice.to_string(),
+                amount: 10u128.into(),
+            },
+            Contribution {
+                donor: bob.to_string(),
+                amount: Uint128::zero(),
+            },
+        ]
+    );
+
+    let resp = contract
+        .query_contributions(&app, None, None, alice.to_string(), 1)
+        .unwrap();
+    assert_eq!(
+        resp.contributions,
+        vec![Contribution {
+            donor: bob.to_string(),
+            amount: 10u128.into(),
+        }]
+    );
+}
+
+#[test]
+fn donation_limits_rejected() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(50, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let limits = DonationLimits {
+        window: Some(DonationWindow {
+            max_donations: 2,
+            period: 3600,
+        }),
+        max_total: None,
+        min_blocks: 0,
+        policy: LimitPolicy::Reject,
+    };
+
+    let err = contract
+        .set_limits(&mut app, &alice, limits.clone())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    contract.set_limits(&mut app, &owner, limits).unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RateLimited {
+            reason: "at most 2 donations are counted every 3600 seconds".to_owned()
+        }
+    );
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 2 });
+    assert_eq!(
+        app.wrap().query_all_balances(&alice).unwrap(),
+        coins(30, ATOM)
+    );
+    // the rejected donation is sent back
+
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 3 });
+    // a new window started
+}
+
+#[test]
+fn donation_limits_kept_uncounted() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(50, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        InstantiateMsg::New {
+            counter: 0,
+            minimal_donation: coin(10, ATOM),
+            parent: None,
+            rewards: None,
+            owner: None,
+            limits: Some(DonationLimits {
+                window: None,
+                max_total: Some(Uint128::new(30)),
+                min_blocks: 2,
+                policy: LimitPolicy::KeepUncounted,
+            }),
+        },
+    )
+    .unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    // the second donation came too soon, it is kept without being counted
+
+    app.update_block(next_block);
+    app.update_block(next_block);
+
+    contract
+        .donate(&mut app, &alice, &coins(20, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 2 });
+
+    app.update_block(next_block);
+    app.update_block(next_block);
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 2 });
+    // alice already gave 30 in counted donations
+
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(50, ATOM)
+    );
+
+    let activity = contract.query_limits(&app, "alice").unwrap().activity.unwrap();
+    assert_eq!(activity.donations, 2);
+    assert_eq!(activity.total, Uint128::new(30));
+
+    let contribution = contract.query_contribution(&app, &alice).unwrap();
+    assert_eq!(contribution.amount, Uint128::new(30));
+    assert_eq!(contribution.total, Uint128::new(30));
+    // the donations kept over the limits are not credited to alice
+}
+
+#[test]
+fn donation_limits_over_campaigns() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+    for id in ["trees", "water"] {
+        contract
+            .create_campaign(&mut app, &owner, id, coin(10, ATOM), None, None)
+            .unwrap();
+    }
+
+    let limits = DonationLimits {
+        window: Some(DonationWindow {
+            max_donations: 2,
+            period: 3600,
+        }),
+        max_total: None,
+        min_blocks: 0,
+        policy: LimitPolicy::Reject,
+    };
+    contract.set_limits(&mut app, &owner, limits.clone()).unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    contract
+        .donate_campaign(&mut app, &alice, "trees", &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .donate_campaign(&mut app, &alice, "water", &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RateLimited {
+            reason: "at most 2 donations are counted every 3600 seconds".to_owned()
+        }
+    );
+    // the window is per donor, moving to another campaign doesn't start a new one
+
+    contract
+        .set_limits(
+            &mut app,
+            &owner,
+            DonationLimits {
+                policy: LimitPolicy::KeepUncounted,
+                ..limits
+            },
+        )
+        .unwrap();
+    contract
+        .donate_campaign(&mut app, &alice, "water", &coins(10, ATOM))
+        .unwrap();
+
+    let water = contract.query_campaign(&app, "water").unwrap();
+    assert_eq!(water.counter, 0);
+    assert_eq!(water.balance, coins(10, ATOM));
+    // kept by the campaign without being counted
+    assert_eq!(
+        contract.query_campaign_value(&app, "trees").unwrap(),
+        ValueResp { value: 1 }
+    );
+}
+
+#[test]
+fn donation_limits_validated() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+
+    let code_id = CountingContract::store_code(&mut app);
+    let limits = DonationLimits {
+        window: Some(DonationWindow {
+            max_donations: 2,
+            period: 0,
+        }),
+        max_total: None,
+        min_blocks: u64::MAX,
+        policy: LimitPolicy::Reject,
+    };
+
+    let err = CountingContract::instantiate_with_msg(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        InstantiateMsg::New {
+            counter: 0,
+            minimal_donation: coin(10, ATOM),
+            parent: None,
+            rewards: None,
+            owner: None,
+            limits: Some(limits.clone()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidLimits {
+            reason: "the window period can't be 0".to_owned()
+        }
+    );
+
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(0, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .set_limits(
+            &mut app,
+            &owner,
+            DonationLimits {
+                window: Some(DonationWindow {
+                    max_donations: 0,
+                    period: 3600,
+                }),
+                ..limits.clone()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidLimits {
+            reason: "the window has to count at least one donation".to_owned()
+        }
+    );
+
+    contract
+        .set_limits(&mut app, &owner, DonationLimits { window: None, ..limits })
+        .unwrap();
+    contract.donate(&mut app, &owner, &[]).unwrap();
+    app.update_block(next_block);
+
+    let err = contract.donate(&mut app, &owner, &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RateLimited {
+            reason: format!("{} blocks have to pass between counted donations", u64::MAX)
+        }
+    );
+    // the block distance saturates instead of overflowing, with no minimal donation every donation is counted
+}
+
+#[test]
+fn access_list() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        for donor in [&alice, &bob, &carol] {
+            router
+                .bank
+                .init_balance(storage, donor, coins(50, ATOM))
+                .unwrap();
+        }
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .add_to_access_list(&mut app, &alice, AccessMode::Allowlist, &[&alice])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+
+    contract
+        .add_to_access_list(&mut app, &owner, AccessMode::Allowlist, &[&alice, &carol])
+        .unwrap();
+    contract
+        .set_access_mode(&mut app, &owner, AccessMode::Allowlist)
+        .unwrap();
+
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    let err = contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: bob.to_string()
+        }
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(&bob).unwrap(),
+        coins(50, ATOM)
+    );
+    // the transaction reverted, so bob keeps the funds
+
+    let resp = contract.query_access_list(&app, None, None, 1).unwrap();
+    assert_eq!(resp.mode, Some(AccessMode::Allowlist));
+    assert_eq!(resp.donors, vec![alice.to_string()]);
+    let resp = contract
+        .query_access_list(&app, None, alice.to_string(), None)
+        .unwrap();
+    assert_eq!(resp.donors, vec![carol.to_string()]);
+
+    contract
+        .add_to_access_list(&mut app, &owner, AccessMode::Blocklist, &[&carol])
+        .unwrap();
+    contract
+        .set_access_mode(&mut app, &owner, AccessMode::Blocklist)
+        .unwrap();
+
+    contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap();
+    let err = contract
+        .donate(&mut app, &carol, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: carol.to_string()
+        }
+    );
+
+    let resp = contract
+        .query_access_list(&app, AccessMode::Allowlist, None, None)
+        .unwrap();
+    assert_eq!(resp.donors, vec![alice.to_string(), carol.to_string()]);
+    // the allowlist is kept while the blocklist is used
+
+    contract
+        .remove_from_access_list(&mut app, &owner, AccessMode::Blocklist, &[&carol])
+        .unwrap();
+    contract
+        .donate(&mut app, &carol, &coins(10, ATOM))
+        .unwrap();
+
+    contract
+        .add_to_access_list(&mut app, &owner, AccessMode::Blocklist, &[&bob])
+        .unwrap();
+    contract.set_access_mode(&mut app, &owner, None).unwrap();
+    contract
+        .donate(&mut app, &bob, &coins(10, ATOM))
+        .unwrap();
+
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 4 });
+}
+
+#[test]
+fn merkle_allowlist() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let carol = Addr::unchecked("carol");
+    let dave = Addr::unchecked("dave");
+
+    let root = "2b218ed65d2b6091544e15a20d7f356d7f6ca3992908f4b2b15dd75b41f22b1c".to_owned();
+    let alice_proof = vec![
+        "81b637d8fcd2c6da6359e6963113a1170de795e4b725b84d1e0b4cfd9ec58ce9".to_owned(),
+        "4c26d9074c27d89ede59270c0ac14b71e071b15239519f75474b2f3ba63481f5".to_owned(),
+    ];
+    // tree of alice, bob and carol computed outside of the contract
+    assert_eq!(merkle::root(&["alice", "bob", "carol"]), root);
+    assert_eq!(merkle::proof(&["alice", "bob", "carol"], "alice"), alice_proof);
+
+    let mut app = App::new(|router, _api, storage| {
+        for donor in [&alice, &carol, &dave] {
+            router
+                .bank
+                .init_balance(storage, donor, coins(50, ATOM))
+                .unwrap();
+        }
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .set_merkle_root(&mut app, &alice, root.clone())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: owner.to_string()
+        }
+    );
+    let err = contract
+        .set_merkle_root(&mut app, &owner, "not a root".to_owned())
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMerkleRoot);
+
+    contract.set_merkle_root(&mut app, &owner, root).unwrap();
+
+    let err = contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: alice.to_string()
+        }
+    );
+
+    contract
+        .donate_with_proof(&mut app, &alice, alice_proof.clone(), &coins(10, ATOM))
+        .unwrap();
+    assert!(contract.query_merkle_root(&app, &alice).unwrap().verified);
+    contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap();
+    // alice is verified, so the proof is not needed anymore
+
+    let err = contract
+        .donate_with_proof(&mut app, &carol, alice_proof, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidProof);
+    contract
+        .donate_with_proof(
+            &mut app,
+            &carol,
+            merkle::proof(&["alice", "bob", "carol"], "carol"),
+            &coins(10, ATOM),
+        )
+        .unwrap();
+
+    let err = contract
+        .donate_with_proof(&mut app, &dave, vec![], &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidProof);
+
+    let donors = ["bob", "dave"];
+    contract
+        .set_merkle_root(&mut app, &owner, merkle::root(&donors))
+        .unwrap();
+    assert!(!contract.query_merkle_root(&app, &alice).unwrap().verified);
+    // alice was verified against the old root only
+
+    let err = contract
+        .donate(&mut app, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: alice.to_string()
+        }
+    );
+    contract
+        .donate_with_proof(&mut app, &dave, merkle::proof(&donors, "dave"), &coins(10, ATOM))
+        .unwrap();
+
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 4 });
+}
+
+fn voucher(key: &SigningKey, contract: &Addr, beneficiary: &Addr, amount: Coin, nonce: u64, expiry: Timestamp) -> Voucher {
+    let payload = to_vec(&VoucherPayload {
+        contract: contract.to_string(),
+        beneficiary: beneficiary.to_string(),
+        amount: amount.clone(),
+        nonce,
+        expiry,
+    })
+    .unwrap();
+    let signature: Signature = key.sign(&payload);
+    // signing hashes the payload with sha256, like the contract does before verifying
+
+    Voucher {
+        amount,
+        nonce,
+        expiry,
+        signature: Binary::from(signature.as_ref()),
+    }
+}
+
+#[test]
+fn donate_for_beneficiary() {
+    let owner = Addr::unchecked("owner");
+    let exchange = Addr::unchecked("exchange");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let signer = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+    let other = SigningKey::from_bytes(&[8u8; 32]).unwrap();
+    let pubkey = Binary::from(signer.verifying_key().to_bytes().as_slice());
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &exchange, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let expiry = app.block_info().time.plus_seconds(3600);
+    let first = voucher(&signer, contract.addr(), &alice, coin(10, ATOM), 1, expiry);
+
+    let err = contract
+        .donate_for(&mut app, &exchange, &alice, first.clone(), None, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoVoucherSigner);
+
+    let err = contract
+        .set_voucher_signer(&mut app, &owner, Binary::from(vec![1, 2, 3]))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPubkey);
+    contract
+        .set_voucher_signer(&mut app, &owner, pubkey.clone())
+        .unwrap();
+
+    contract
+        .donate_for(&mut app, &exchange, &alice, first.clone(), None, &coins(10, ATOM))
+        .unwrap();
+
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(
+        contract.query_contribution(&app, &alice).unwrap().amount,
+        Uint128::new(10)
+    );
+    assert_eq!(
+        contract.query_contribution(&app, &exchange).unwrap().amount,
+        Uint128::zero()
+    );
+    // the beneficiary is credited, not the exchange sending the funds
+    assert!(contract.query_voucher_signer(&app, 1).unwrap().used);
+
+    let err = contract
+        .donate_for(&mut app, &exchange, &alice, first, None, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::VoucherUsed { nonce: 1 });
+
+    let err = contract
+        .donate_for(
+            &mut app,
+            &exchange,
+            &alice,
+            voucher(&signer, contract.addr(), &alice, coin(10, ATOM), 2, expiry),
+            None,
+            &coins(20, ATOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidVoucher {
+            reason: "funds sent have to be exactly 10atom".to_owned()
+        }
+    );
+
+    let err = contract
+        .donate_for(
+            &mut app,
+            &exchange,
+            &alice,
+            voucher(&signer, contract.addr(), &bob, coin(10, ATOM), 2, expiry),
+            None,
+            &coins(10, ATOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidVoucher {
+            reason: "not signed by the voucher signer".to_owned()
+        }
+    );
+    // signed for bob, so it can't be credited to alice
+
+    let err = contract
+        .donate_for(
+            &mut app,
+            &exchange,
+            &alice,
+            voucher(&other, contract.addr(), &alice, coin(10, ATOM), 2, expiry),
+            None,
+            &coins(10, ATOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidVoucher {
+            reason: "not signed by the voucher signer".to_owned()
+        }
+    );
+
+    let late = voucher(&signer, contract.addr(), &bob, coin(20, ATOM), 2, expiry);
+    app.update_block(|block| block.time = block.time.plus_seconds(3601));
+    let err = contract
+        .donate_for(&mut app, &exchange, &bob, late, None, &coins(20, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidVoucher {
+            reason: "expired".to_owned()
+        }
+    );
+    assert!(!contract.query_voucher_signer(&app, 2).unwrap().used);
+
+    contract
+        .set_merkle_root(&mut app, &owner, merkle::root(&["alice", "bob", "carol"]))
+        .unwrap();
+    let expiry = app.block_info().time.plus_seconds(3600);
+    let next = voucher(&signer, contract.addr(), &alice, coin(10, ATOM), 3, expiry);
+
+    let err = contract
+        .donate_for(&mut app, &exchange, &alice, next.clone(), None, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DonorNotAllowed {
+            donor: alice.to_string()
+        }
+    );
+    contract
+        .donate_for(
+            &mut app,
+            &exchange,
+            &alice,
+            next,
+            merkle::proof(&["alice", "bob", "carol"], "alice"),
+            &coins(10, ATOM),
+        )
+        .unwrap();
+    assert!(contract.query_merkle_root(&app, &alice).unwrap().verified);
+    // the proof of the beneficiary is passed along with the voucher
+}
+
+#[test]
+fn referral_rewards() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        for donor in [&alice, &bob, &carol] {
+            router
+                .bank
+                .init_balance(storage, donor, coins(100, ATOM))
+                .unwrap();
+        }
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    let err = contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(150),
+                payout: ReferralPayout::Immediate,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidReferralShare);
+    contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(10),
+                payout: ReferralPayout::Immediate,
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate_with_referrer(&mut app, &alice, &bob, &coins(50, ATOM))
+        .unwrap();
+
+    assert_eq!(contract.query_value(&app).unwrap(), ValueResp { value: 1 });
+    assert_eq!(app.wrap().query_all_balances(&bob).unwrap(), coins(105, ATOM));
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(45, ATOM)
+    );
+    let stats = contract.query_referrer(&app, &bob).unwrap();
+    assert_eq!((stats.donors, stats.donations), (1, 1));
+    assert_eq!(stats.earned, coins(5, ATOM));
+    assert!(stats.accrued.is_empty());
+
+    let err = contract
+        .donate_with_referrer(&mut app, &alice, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "donors can't refer themselves".to_owned()
+        }
+    );
+
+    let err = contract
+        .donate_with_referrer(&mut app, &alice, &carol, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "alice was already referred by bob".to_owned()
+        }
+    );
+
+    let err = contract
+        .donate_with_referrer(&mut app, &bob, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "bob is already up the referral chain of alice".to_owned()
+        }
+    );
+    // alice was referred by bob, so bob can't be referred by alice
+
+    contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(10),
+                payout: ReferralPayout::Accrue,
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate_with_referrer(&mut app, &carol, &alice, &coins(20, ATOM))
+        .unwrap();
+    assert_eq!(contract.query_referrer(&app, &alice).unwrap().accrued, coins(2, ATOM));
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(63, ATOM));
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(2, ATOM)
+    );
+    // the accrued reward is not part of what the owner can withdraw
+
+    contract.claim_referral_rewards(&mut app, &alice).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&alice).unwrap(), coins(52, ATOM));
+    assert!(app.wrap().query_all_balances(contract.addr()).unwrap().is_empty());
+
+    let stats = contract.query_referrer(&app, &alice).unwrap();
+    assert_eq!(stats.earned, coins(2, ATOM));
+    assert!(stats.accrued.is_empty());
+}
+
+#[test]
+fn referral_payouts_checked() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(storage, &alice, coins(100, ATOM))
+            .unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        None,
+        None,
+        coin(10, ATOM),
+        Parent {
+            addr: "parent".to_owned(),
+            donating_period: 10,
+            part: Decimal::percent(80),
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    let err = contract
+        .donate_with_referrer(&mut app, &alice, &bob, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "referrals are off".to_owned()
+        }
+    );
+    // rejected like a referrer passed to another campaign, instead of being ignored
+
+    let err = contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(30),
+                payout: ReferralPayout::Immediate,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSplit);
+    // the parent takes 80% already
+    contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(20),
+                payout: ReferralPayout::Immediate,
+            },
+        )
+        .unwrap();
+
+    contract
+        .set_receiver_policy(&mut app, &owner, true, 0)
+        .unwrap();
+
+    contract
+        .donate_with_referrer(&mut app, &alice, &bob, &coins(50, ATOM))
+        .unwrap();
+    assert!(app.wrap().query_all_balances(&bob).unwrap().is_empty());
+    assert_eq!(contract.query_referrer(&app, &bob).unwrap().accrued, coins(10, ATOM));
+    // bob is not an allowed receiver, so the reward is accrued instead of sent
+
+    let err = contract
+        .claim_referral_rewards(&mut app, &bob)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ReceiverNotAllowed {
+            receiver: bob.to_string()
+        }
+    );
+
+    contract.add_receiver(&mut app, &owner, &bob).unwrap();
+    contract.claim_referral_rewards(&mut app, &bob).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&bob).unwrap(), coins(10, ATOM));
+
+    contract
+        .donate_with_referrer(&mut app, &alice, &bob, &coins(20, ATOM))
+        .unwrap();
+    assert_eq!(app.wrap().query_all_balances(&bob).unwrap(), coins(14, ATOM));
+    // once allowed, bob is paid right away
+}
+
+#[test]
+fn referral_split_and_cycles() {
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        for donor in [&alice, &bob, &carol] {
+            router
+                .bank
+                .init_balance(storage, donor, coins(100, ATOM))
+                .unwrap();
+        }
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+    let contract = CountingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Counting contract",
+        &admin,
+        None,
+        coin(10, ATOM),
+        None
+    )
+    .unwrap();
+
+    contract
+        .set_referrals(
+            &mut app,
+            &owner,
+            ReferralConfig {
+                share: Decimal::percent(30),
+                payout: ReferralPayout::Immediate,
+            },
+        )
+        .unwrap();
+
+    let err = CountingContract::migrate(
+        &mut app,
+        contract.addr().clone(),
+        code_id,
+        &admin,
+        Parent {
+            addr: "parent".to_owned(),
+            donating_period: 10,
+            part: Decimal::percent(80),
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSplit);
+    // the referral share is checked also when the parent comes after it
+
+    contract
+        .set_governance(
+            &mut app,
+            &owner,
+            GovernanceConfig {
+                quorum: Decimal::percent(50),
+                threshold: Decimal::percent(60),
+                voting_period: 3600,
+                restrict_withdrawals: true,
+            },
+        )
+        .unwrap();
+
+    contract
+        .donate_with_referrer(&mut app, &alice, &bob, &coins(50, ATOM))
+        .unwrap();
+    assert_eq!(app.wrap().query_all_balances(&bob).unwrap(), coins(100, ATOM));
+    assert_eq!(contract.query_referrer(&app, &bob).unwrap().accrued, coins(15, ATOM));
+    // withdrawals are restricted, so the immediate reward is accrued instead of sent
+
+    let err = contract
+        .claim_referral_rewards(&mut app, &bob)
+        .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawalsRestricted);
+
+    let err = contract
+        .donate_with_referrer(&mut app, &bob, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "bob is already up the referral chain of alice".to_owned()
+        }
+    );
+
+    contract
+        .donate_with_referrer(&mut app, &bob, &carol, &coins(10, ATOM))
+        .unwrap();
+
+    let err = contract
+        .donate_with_referrer(&mut app, &carol, &alice, &coins(10, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReferrer {
+            reason: "carol is already up the referral chain of alice".to_owned()
+        }
+    );
+    // alice was referred by bob, who was referred by carol
+}
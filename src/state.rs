@@ -1,26 +1,258 @@
-use cosmwasm_std::{Addr, Coin};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Binary, Coin, Empty, IbcEndpoint, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
 use serde::{Deserialize, Serialize};
 use cosmwasm_std::Decimal;
 
+use crate::msg::{AccessMode, DonationLimits, DonorActivity, GovernanceConfig, HubConfig, IbcParent, Payout, PendingReceiverPolicy, ReferralConfig, Update};
+
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct State {
-  pub counter: u64,
   pub minimal_donation: Coin,
   pub owner: Addr,
   pub donating_parent: Option<u64>,
 } // added donating_parent field which is a countdown till the donation period ends
+// the counter itself moved to COUNTER, so its past values can be queried
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct ParentDonation {
   pub address: Addr,
   pub donating_parent_period: u64,
   pub part: Decimal,
+  #[serde(default)]
+  pub ibc: Option<IbcParent>,
 } // added donation_parent field which is a value to be reset when it reaches 0
+// with ibc set, address is on the other chain and is not validated, default keeps the parents stored before it readable
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct Referrer {
+  pub donors: u64,
+  pub donations: u64,
+  pub earned: Vec<Coin>,
+  pub accrued: Vec<Coin>,
+} // stats of a referrer, accrued is what it can still claim
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Restoring {
+  pub total_donated: Uint128,
+  pub referral_accrued: Vec<Coin>,
+  pub paused: bool,
+} // totals the imported ledgers have to add up to, and the pause applied once the restore is finished
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Allowance {
+  pub limit: Vec<Coin>,
+  pub period: u64,
+  pub spent: Vec<Coin>,
+  pub period_start: Timestamp,
+} // spending allowance given by the owner to a spender, limit can be spent every period (in seconds), spent is what was already withdrawn since period_start
+
+impl Allowance {
+  pub fn refresh(&mut self, now: Timestamp) {
+    if now >= self.period_start.plus_seconds(self.period) {
+      self.spent = vec![];
+      self.period_start = now;
+    }
+  } // starts a new window once the period has passed, forgetting what was spent in the old one
+
+  pub fn remaining(&self, denom: &str) -> Uint128 {
+    let limit = self
+      .limit
+      .iter()
+      .find(|c| c.denom == denom)
+      .map(|c| c.amount)
+      .unwrap_or_default();
+    let spent = self
+      .spent
+      .iter()
+      .find(|c| c.denom == denom)
+      .map(|c| c.amount)
+      .unwrap_or_default();
+    limit.saturating_sub(spent)
+  } // how much of the denom can still be spent in the current window, denoms not in the limit can't be spent at all
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ReceiverPolicy {
+  pub enforce: bool,
+  pub delay: u64,
+  #[serde(default)]
+  pub pending: Option<PendingReceiverPolicy>,
+} // when enforce is set, Withdraw and WithdrawTo only send to allowed receivers, new receivers become allowed delay seconds after being added
+
+impl ReceiverPolicy {
+  pub fn refresh(&mut self, now: Timestamp) {
+    if let Some(pending) = self.pending.clone().filter(|pending| pending.effective <= now) {
+      self.enforce = pending.enforce;
+      self.delay = pending.delay;
+      self.pending = None;
+    }
+  } // applies the pending policy once its time has come
+
+  pub fn loosened_by(&self, enforce: bool, delay: u64) -> bool {
+    self.enforce && (!enforce || delay < self.delay)
+  } // a stolen owner key could use a looser policy to empty the contract, so it has to wait like a new receiver
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Hook {
+  pub on_reset: bool,
+  pub on_withdraw: bool,
+} // every hook is notified about donations, resets and withdrawals only if it subscribed to them
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Governance {
+  pub quorum: Decimal,
+  pub threshold: Decimal,
+  pub voting_period: u64,
+  pub restrict_withdrawals: bool,
+} // quorum is the part of all donations that has to vote, threshold the part of the votes that has to be yes
+
+impl Governance {
+  pub fn loosened_by(&self, config: Option<&GovernanceConfig>) -> bool {
+    match config {
+      Some(config) => {
+        self.restrict_withdrawals
+          && (!config.restrict_withdrawals || config.quorum < self.quorum || config.threshold < self.threshold)
+      }
+      None => self.restrict_withdrawals,
+    }
+  } // while withdrawals are restricted the donors decide, so the owner can't lift the restriction or make proposals easier to pass
+}
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Proposal {
+  pub proposer: Addr,
+  pub description: String,
+  pub payouts: Vec<Payout>,
+  pub start_height: u64,
+  pub expires: Timestamp,
+  pub quorum: Decimal,
+  pub threshold: Decimal,
+  pub total_weight: Uint128,
+  pub yes: Uint128,
+  pub no: Uint128,
+  pub executed: bool,
+  #[serde(default)]
+  pub governance: Update<GovernanceConfig>,
+} // votes are weighted by the donations made before start_height, quorum and threshold are copied from the config when proposing
+// governance is applied to the config on execution, the only way to loosen a config which restricts withdrawals
+
+impl Proposal {
+  pub fn passed(&self) -> bool {
+    let voted = self.yes + self.no;
+    if self.total_weight.is_zero() || voted.is_zero() {
+      return false;
+    }
+
+    Decimal::from_ratio(voted, self.total_weight) >= self.quorum
+      && Decimal::from_ratio(self.yes, voted) >= self.threshold
+  } // no votes can arrive after the deadline, so a passed proposal stays passed
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Receipts {
+  pub cw721: Addr,
+  pub token_uri: String,
+  pub threshold: Option<Uint128>,
+} // nft contract and token uri template of the donation receipts
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Campaign {
+  pub owner: Addr,
+  pub minimal_donation: Coin,
+  pub counter: u64,
+  pub donating_parent: Option<u64>,
+  pub parent: Option<ParentDonation>,
+  pub balance: Vec<Coin>,
+} // campaign sharing the contract with the default one, all of them keep their funds on the contract account
+// receipts, rewards, staking and the donor ledger are only there for the default campaign, the hooks get the resets and withdrawals of every campaign
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ParentTransfer {
+  pub campaign: Option<String>,
+  pub channel_id: String,
+  pub receiver: String,
+  pub amount: Coin,
+} // ics20 transfer of a parent share, campaign is None for the default campaign
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Staking {
+  pub validator: String,
+  pub fraction: Decimal,
+  pub denom: String,
+} // fraction of every bond denom donation delegated to the validator, denom is the bond denom of the chain
+
+pub const DEFAULT_CAMPAIGN: &str = "default";
+// STATE and COUNTER are the default campaign, so contracts migrated from before campaigns keep their storage as it is
 pub const STATE: Item<State> = Item::new("state"); // key is "state" attached to the binary data. Accessing the State value on the storage
+pub const COUNTER_NAMESPACE: &str = "counter"; // SnapshotItem doesn't expose its primary key, the migration plan lists it from here
+pub const COUNTER: SnapshotItem<u64> = SnapshotItem::new(
+  COUNTER_NAMESPACE,
+  "counter__checkpoints",
+  "counter__changelog",
+  Strategy::EveryBlock,
+); // every block changing the counter keeps the value it had before in the changelog, keyed by the block height
 pub const PARENT_DONATION: Item<ParentDonation> = Item::new("parent_donation"); // key is "parent_donation" attached to the binary data. Accessing the ParentDonation value on the storage
+pub const ALLOWANCES: Map<&Addr, Allowance> = Map::new("allowances"); // spender address is the key, Map stores many values under a common prefix
+pub const RECEIVER_POLICY: Item<ReceiverPolicy> = Item::new("receiver_policy");
+pub const RECEIVERS: Map<&Addr, Timestamp> = Map::new("receivers"); // receiver address is the key, the value is the time from which the receiver is allowed
+pub const RECEIPTS: Item<Receipts> = Item::new("receipts"); // missing means no receipts are minted
+pub const RECEIPTS_MINTED: Item<u64> = Item::new("receipts_minted"); // never goes down, so receipt token ids are unique even after a reset
+pub const REWARD_TOKEN: Item<Addr> = Item::new("reward_token"); // cw20 contract minting the supporter tokens, missing means no rewards
+pub const REWARD_RATES: Map<&str, Decimal> = Map::new("reward_rates"); // denom is the key, the value is minted per unit donated
+pub const DONATIONS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+  "donations",
+  "donations__checkpoints",
+  "donations__changelog",
+  Strategy::EveryBlock,
+); // donor ledger, everything the donor gave in the minimal donation denom, kept by height like the counter
+pub const TOTAL_DONATED: SnapshotItem<Uint128> = SnapshotItem::new(
+  "total_donated",
+  "total_donated__checkpoints",
+  "total_donated__changelog",
+  Strategy::EveryBlock,
+); // sum of the donor ledger
+pub const GOVERNANCE: Item<Governance> = Item::new("governance"); // missing means no proposals can be made
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count"); // id of the last proposal
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+pub const VOTES: Map<(u64, &Addr), bool> = Map::new("votes"); // proposal id and voter are the key, the value is the vote
+pub const HOOKS: Map<&Addr, Hook> = Map::new("hooks"); // hook contract address is the key
+pub const PENDING_TRANSFERS: Item<Vec<ParentTransfer>> = Item::new("pending_transfers"); // sent in this transaction, waiting for the reply with their sequence
+pub const TRANSFERS_IN_FLIGHT: Map<(&str, u64), ParentTransfer> = Map::new("transfers_in_flight"); // channel and packet sequence are the key, removed by the ibc-hooks callback
+pub const FAILED_TRANSFERS: Map<u64, ParentTransfer> = Map::new("failed_transfers"); // removed once retried
+pub const FAILED_TRANSFER_COUNT: Item<u64> = Item::new("failed_transfer_count"); // id of the last failed transfer
+pub const CAMPAIGNS: Map<&str, Campaign> = Map::new("campaigns"); // campaign id is the key, the default campaign is not in it
+pub const CAMPAIGN_BALANCES: Item<Vec<Coin>> = Item::new("campaign_balances"); // sum of the balances of all campaigns, so the default balance doesn't range over them
+pub const COUNTING_CHANNELS: Map<&str, IbcEndpoint> = Map::new("counting_channels"); // open counting-v1 channels, the value is the other end
+pub const HUB: Item<HubConfig> = Item::new("hub"); // missing means the counter is not reported anywhere
+pub const REPORTED: Item<u64> = Item::new("reported"); // counter value the hub knows about, packets still on their way included
+pub const REMOTE_COUNTERS: Map<&str, u64> = Map::new("remote_counters"); // counters reported to this contract, channel id is the key
+pub const PEERS: Map<(&str, &str), Empty> = Map::new("peers"); // connection id and counterparty port id of the contracts allowed to open counting-v1 channels
+pub const STAKING: Item<Staking> = Item::new("staking"); // missing means donations stay liquid
+pub const PAUSED: Item<bool> = Item::new("paused"); // set by governance through sudo, missing means not paused
+pub const RESTORING: Item<Restoring> = Item::new("restoring"); // set while a restored contract waits for its ledgers
+pub const LIMITS: Item<DonationLimits> = Item::new("limits"); // missing means every donation above the minimal one is counted
+pub const DONOR_ACTIVITY: Map<&Addr, DonorActivity> = Map::new("donor_activity"); // counted donations of the donor, kept even while there are no limits
+pub const REFERRALS: Item<ReferralConfig> = Item::new("referrals"); // missing means referrers are not paid
+pub const REFERRED_BY: Map<&Addr, Addr> = Map::new("referred_by"); // donor is the key, the referrer of its first referred donation is the value
+pub const REFERRERS: Map<&Addr, Referrer> = Map::new("referrers");
+pub const REFERRAL_ACCRUED: Item<Vec<Coin>> = Item::new("referral_accrued"); // sum of what all the referrers accrued, not part of the default campaign funds
+pub const VOUCHER_SIGNER: Item<Binary> = Item::new("voucher_signer"); // secp256k1 public key, missing means DonateFor is rejected
+pub const USED_NONCES: Map<u64, Empty> = Map::new("used_nonces"); // nonces of the vouchers already donated, so none is used twice
+pub const MERKLE_ROOT: Item<String> = Item::new("merkle_root"); // lowercase hex, missing means donors don't need a proof
+pub const MERKLE_VERIFIED: Map<&Addr, String> = Map::new("merkle_verified"); // root the donor proved to be in, stale once the root changes
+pub const ACCESS_MODE: Item<AccessMode> = Item::new("access_mode"); // missing means anyone can donate
+pub const ALLOWLIST: Map<&Addr, Empty> = Map::new("allowlist");
+pub const BLOCKLIST: Map<&Addr, Empty> = Map::new("blocklist");
+// both lists are kept whatever the mode is, so switching it back doesn't lose the other one
+
+pub fn donor_list<'a>(list: AccessMode) -> Map<'static, &'a Addr, Empty> {
+  match list {
+    AccessMode::Allowlist => ALLOWLIST,
+    AccessMode::Blocklist => BLOCKLIST,
+  }
+}
 
 
 // Item would use this value to access data, taking care of serialization and deserialization of it, so you don't need to work on raw binary data.